@@ -1,5 +1,7 @@
 use std::env;
-use std::process::{self, Command};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -7,12 +9,435 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 compile_error!("honggfuzz-rs does not currently support Windows but works well under WSL (Windows Subsystem for Linux)");
 
 // TODO: maybe use `make-cmd` crate
-#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly", target_os = "bitrig", target_os = "openbsd", target_os = "netbsd")))]
+#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd", target_os = "netbsd")))]
 const GNU_MAKE: &str = "make";
-#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "bitrig", target_os = "openbsd", target_os = "netbsd"))]
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd", target_os = "netbsd"))]
 const GNU_MAKE: &str = "gmake";
 
+// the variables of the C toolchain the engine is built with
+const TOOLCHAIN: &[&str] = &["CC", "AR", "CFLAGS", "LDFLAGS"];
+
+// the libraries linked into the targets, and the engine running them
+const LIBRARIES: &[&str] = &["libhfuzz/libhfuzz.a", "libhfcommon/libhfcommon.a"];
+const ENGINE: &str = "honggfuzz";
+
+fn host() -> String {
+    env::var("HOST").unwrap_or_default()
+}
+
+fn target() -> String {
+    env::var("TARGET").unwrap_or_default()
+}
+
+// the `no-engine` feature, building only the libraries
+fn no_engine() -> bool {
+    env::var_os("CARGO_FEATURE_NO_ENGINE").is_some()
+}
+
+// the triple of the engine: a musl target of the architecture of the host, like `x86_64-unknown-linux-musl`
+// on `x86_64-unknown-linux-gnu`, is fuzzed by the engine of the host, only the libraries being built for it
+fn engine_triple() -> String {
+    let (host, target) = (host(), target());
+    let arch = |triple: &str| triple.split('-').next().unwrap_or_default().to_string();
+    match target.ends_with("-linux-musl") && host.contains("-linux-") && arch(&host) == arch(&target) {
+        true => host,
+        false => target,
+    }
+}
+
+// the variables setting `name` for `triple`, in the order the `cc` crate reads them: like `CC_<triple>`,
+// `CC_<triple with underscores>`, `TARGET_CC` when cross-compiling or `HOST_CC`, then `CC`
+fn toolchain_vars(name: &str, triple: &str) -> [String; 4] {
+    let kind = if triple == host() { "HOST" } else { "TARGET" };
+    [format!("{}_{}", name, triple), format!("{}_{}", name, triple.replace('-', "_")), format!("{}_{}", kind, name), name.to_string()]
+}
+
+// when cross-compiling CC and AR default to the GNU ones named after the triple, like
+// `aarch64-linux-gnu-gcc` for `aarch64-unknown-linux-gnu`, and to the `musl-gcc` of the musl tools for
+// the musl targets of x86
+fn toolchain_value(name: &str, triple: &str) -> Option<String> {
+    let cross = triple != host();
+    let x86_musl = triple.ends_with("-linux-musl") && ["x86_64-", "i686-", "i586-"].iter().any(|arch| triple.starts_with(arch));
+    let prefix = triple.replace("-unknown-", "-");
+    toolchain_vars(name, triple).iter().find_map(|v| env::var(v).ok()).or_else(|| match name {
+        "CC" if cross && x86_musl => Some("musl-gcc".to_string()),
+        "AR" if cross && x86_musl => Some("ar".to_string()),
+        "CC" if cross => Some(format!("{}-gcc", prefix)),
+        "AR" if cross => Some(format!("{}-ar", prefix)),
+        _ => None,
+    })
+}
+
+// the C toolchain building for `triple`: CC and AR are given to make, overriding the ones of the Makefile
+// of honggfuzz, CFLAGS and LDFLAGS are given in the environment, for the Makefile to add its own flags to them
+fn toolchain(command: &mut Command, triple: &str) {
+    for name in TOOLCHAIN {
+        match toolchain_value(name, triple) {
+            Some(value) if name.ends_with("FLAGS") => {
+                command.env(name, value);
+            }
+            Some(value) => {
+                command.arg(format!("{}={}", name, value));
+            }
+            None => {}
+        }
+    }
+}
+
+// like "2.6", from the sources of honggfuzz in `dir`
+fn engine_version(dir: &str) -> Option<String> {
+    let header = fs::read_to_string(format!("{}/honggfuzz.h", dir)).ok()?;
+    header.lines().find_map(|l| l.strip_prefix("#define PROG_VERSION \"").map(|v| v.trim_end_matches('"').to_string()))
+}
+
+// `cargo --offline` or `--frozen`, which cargo-hfuzz gives as CARGO_NET_OFFLINE, or the variable set by hand
+fn offline() -> bool {
+    env::var("CARGO_NET_OFFLINE").is_ok_and(|v| v == "true" || v == "1")
+}
+
+// the sources of honggfuzz packaged with the library, a submodule in a git checkout
+fn bundled_sources() -> String {
+    if !Path::new("honggfuzz/Makefile").exists() {
+        eprintln!("the sources of honggfuzz bundled with the library are missing from \"{}/honggfuzz\", \
+                   in a git checkout of honggfuzz-rs run \"git submodule update --init\"",
+                  env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+        process::exit(1);
+    }
+    "honggfuzz".to_string()
+}
+
+// the sources of the release of honggfuzz set by `build.engine_version` in hfuzz.toml, downloaded once
+// into OUT_DIR, or the bundled ones
+fn engine_sources(out_dir: &str) -> String {
+    let version = match env::var("CARGO_HONGGFUZZ_ENGINE_VERSION") {
+        Ok(version) if !version.is_empty() => version,
+        _ => return bundled_sources(),
+    };
+    if engine_version("honggfuzz").as_ref() == Some(&version) {
+        return bundled_sources();
+    }
+    let dir = format!("{}/honggfuzz-{}", out_dir, version);
+    if !Path::new(&dir).join("Makefile").exists() {
+        let url = format!("https://github.com/google/honggfuzz/archive/refs/tags/{}.tar.gz", version);
+        // the bundled sources need nothing from the network, a release does the first time
+        if offline() {
+            eprintln!("the sources of honggfuzz {} would be downloaded from \"{}\", which an offline build doesn't do: \
+                       remove `build.engine_version` from hfuzz.toml to build the sources bundled with the library ({}), \
+                       or build once online",
+                      version, url, engine_version("honggfuzz").unwrap_or_else(|| "unknown".to_string()));
+            process::exit(1);
+        }
+        let archive = format!("{}.tar.gz", dir);
+        let downloaded = Command::new("curl").args(["-fsSL", "-o", &archive, &url]).status().is_ok_and(|s| s.success())
+            && Command::new("tar").args(["-xzf", &archive, "-C", out_dir]).status().is_ok_and(|s| s.success());
+        if !downloaded || !Path::new(&dir).join("Makefile").exists() {
+            eprintln!("failed to download the sources of honggfuzz {} from \"{}\", see https://github.com/google/honggfuzz/tags for its releases", version, url);
+            process::exit(1);
+        }
+    }
+    dir
+}
+
+// the optional parts of the engine, the feature of the library building each of them, and the flags the
+// Makefile of honggfuzz sets for it on Linux, filtered out when the feature is disabled
+const ENGINE_FEATURES: &[(&str, &[&str])] = &[
+    ("ENGINE_BFD", &["-lopcodes", "-lbfd"]),
+    ("ENGINE_INTEL_PT", &["-D_HF_LINUX_INTEL_PT_LIB", "-lipt"]),
+];
+
+// a library the engine links on Linux, built without it when the C compiler doesn't find it, as on a stock
+// container image
+struct SystemLibrary {
+    name: &'static str,
+    header: &'static str,
+    // the flags the Makefile of honggfuzz links it with
+    flags: &'static [&'static str],
+    // what the engine built without it lacks
+    without: &'static str,
+}
+
+const SYSTEM_LIBRARIES: &[SystemLibrary] = &[
+    SystemLibrary {
+        name: "libbfd",
+        header: "bfd.h",
+        flags: &["-lopcodes", "-lbfd"],
+        without: "its crash reports name no symbol and disassemble no instruction (install binutils-dev, then run \
+                  \"cargo hfuzz clean\" for the engine to be rebuilt with it)",
+    },
+    SystemLibrary {
+        name: "libunwind",
+        header: "libunwind-ptrace.h",
+        flags: &["-lunwind-ptrace", "-lunwind-generic", "-lunwind", "-llzma"],
+        without: "the crashes without a sanitizer report have no stack trace, each one being saved \
+                  instead of the first of every stack (install libunwind-dev and liblzma-dev, then run \"cargo hfuzz clean\" for the engine \
+                  to be rebuilt with them)",
+    },
+];
+
+// the unwinding of the engine built without libunwind, compiled in place of linux/unwind.c: no frame, and
+// the symbols of the frames of the sanitizer reports matched as by honggfuzz
+const NO_UNWIND: &str = r#"#include "linux/unwind.h"
+
+#include <string.h>
+
+size_t arch_unwindStack(pid_t pid, funcs_t* funcs) {
+    (void)pid;
+    (void)funcs;
+    return 0;
+}
+
+char* arch_btContainsSymbol(
+    size_t symbolsListSz, char** symbolsList, size_t num_frames, funcs_t* funcs) {
+    for (size_t frame = 0; frame < num_frames; frame++) {
+        size_t len = strlen(funcs[frame].func);
+        if (len > 0) {
+            for (size_t i = 0; i < symbolsListSz; i++) {
+                char* wOff = strchr(symbolsList[i], '*');
+                if (wOff) {
+                    len = wOff - symbolsList[i] - 1;
+                }
+                if (strncmp(funcs[frame].func, symbolsList[i], len) == 0) {
+                    return funcs[frame].func;
+                }
+            }
+        }
+    }
+    return NULL;
+}
+"#;
+
+// whether the C compiler of `triple` finds the header of `library` and links it
+fn finds(out_dir: &str, triple: &str, library: &SystemLibrary) -> bool {
+    let source = format!("{}/probe-{}.c", out_dir, library.name);
+    // bfd.h refuses to be included outside of a configured build
+    fs::write(&source, format!("#define PACKAGE\n#define PACKAGE_VERSION\n#include <{}>\nint main(void) {{ return 0; }}\n", library.header))
+        .unwrap_or_else(|_| panic!("failed to write \"{}\"", &source));
+    let cc = toolchain_value("CC", triple).unwrap_or_else(|| "cc".to_string());
+    let mut cc = cc.split_whitespace();
+    let flags = |name| toolchain_value(name, triple).unwrap_or_default();
+    Command::new(cc.next().unwrap_or("cc"))
+        .args(cc)
+        .args(flags("CFLAGS").split_whitespace())
+        .args(["-I/usr/local/include", "-o", &format!("{}/probe-{}", out_dir, library.name), &source])
+        .args(flags("LDFLAGS").split_whitespace())
+        .args(library.flags)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+// the libraries the engine is built without, not found for the Linux engine, libbfd only probed when the
+// `engine-bfd` feature wants it
+fn missing_libraries(out_dir: &str) -> Vec<&'static SystemLibrary> {
+    let triple = engine_triple();
+    if no_engine() || !triple.contains("-linux-") || android_abi(&triple).is_some() {
+        return vec![];
+    }
+    SYSTEM_LIBRARIES.iter()
+        .filter(|library| library.name != "libbfd" || env::var_os("CARGO_FEATURE_ENGINE_BFD").is_some())
+        .filter(|library| !finds(out_dir, &triple, library))
+        .collect()
+}
+
+// the arguments of make building for `triple`, with a makefile read after the one of honggfuzz: it removes
+// the flags of the parts of the engine whose feature is disabled, and of the libraries it is built without
+fn make_args(out_dir: &str, triple: &str, missing: &[&SystemLibrary]) -> Vec<String> {
+    let disabled: Vec<&str> = ENGINE_FEATURES.iter()
+        .filter(|(feature, _)| env::var_os(format!("CARGO_FEATURE_{}", feature)).is_none())
+        .flat_map(|(_, flags)| flags.iter().copied())
+        .chain(missing.iter().flat_map(|library| library.flags.iter().copied()))
+        .collect();
+    let without = |name| missing.iter().any(|library| library.name == name);
+    let mut makefile = String::new();
+    if !disabled.is_empty() {
+        makefile += &format!("ARCH_CFLAGS := $(filter-out {0},$(ARCH_CFLAGS))\nARCH_LDFLAGS := $(filter-out {0},$(ARCH_LDFLAGS))\n", disabled.join(" "));
+    }
+    if without("libunwind") {
+        let source = format!("{}/no-unwind.c", out_dir);
+        fs::write(&source, NO_UNWIND).unwrap_or_else(|_| panic!("failed to write \"{}\"", &source));
+        makefile += &format!("linux/unwind.o: {0}\n\t$(CC) -c $(CFLAGS) $(CFLAGS_BLOCKS) -o $@ {0}\n", source);
+    }
+    // musl-gcc only searches the headers of musl, the ones of Linux included by the libraries are those of
+    // the host, like in /usr/include/x86_64-linux-gnu for its `asm` directory
+    if triple.ends_with("-linux-musl") && toolchain_value("CC", triple).as_deref() == Some("musl-gcc") && Path::new("/usr/include/linux").exists() {
+        makefile += &format!("LIBS_CFLAGS += -idirafter /usr/include -idirafter /usr/include/{}\n", host().replace("-unknown-", "-"));
+    }
+    let mut args = vec![];
+    if !makefile.is_empty() {
+        let path = format!("{}/engine-{}.mk", out_dir, triple);
+        fs::write(&path, makefile).unwrap_or_else(|_| panic!("failed to write \"{}\"", &path));
+        args.extend(["-f".to_string(), "Makefile".to_string(), "-f".to_string(), path]);
+    }
+    // BUILD_LINUX_NO_BFD builds the symbolization of the engine without libbfd, the makefile unlinks it
+    if env::var_os("CARGO_FEATURE_ENGINE_BFD").is_none() || without("libbfd") {
+        args.push("BUILD_LINUX_NO_BFD=true".to_string());
+    }
+    args
+}
+
+// the ABI of the NDK for an Android triple, like `arm64-v8a` for `aarch64-linux-android`
+fn android_abi(triple: &str) -> Option<&'static str> {
+    if !triple.contains("-linux-android") {
+        return None;
+    }
+    match triple.split('-').next().unwrap_or_default() {
+        "aarch64" => Some("arm64-v8a"),
+        "armv7" | "thumbv7neon" => Some("armeabi-v7a"),
+        "i686" => Some("x86"),
+        "x86_64" => Some("x86_64"),
+        _ => None,
+    }
+}
+
+// the directory of the Android NDK, as its tools and the Android Gradle plugin name it
+fn android_ndk() -> Option<String> {
+    ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "NDK"].iter().find_map(|v| env::var(v).ok().filter(|dir| !dir.is_empty()))
+}
+
+// make compiles in parallel, sharing the jobserver of cargo with the build of the other crates, or with
+// NUM_JOBS jobs when there is none
+fn parallel(command: &mut Command) {
+    match env::var("CARGO_MAKEFLAGS") {
+        Ok(flags) => {
+            command.env("MAKEFLAGS", flags);
+        }
+        Err(_) => {
+            command.arg(format!("-j{}", env::var("NUM_JOBS").unwrap_or_else(|_| "1".to_string())));
+        }
+    }
+}
+
+// builds the engine and the libraries into `partial`, the engine for the host when it fuzzes a target of
+// another libc (see engine_triple)
+fn make_engine(sources: &str, out_dir: &str, partial: &str, missing: &[&SystemLibrary]) {
+    let (engine, target) = (engine_triple(), target());
+    let all = [LIBRARIES, &[ENGINE]].concat();
+    let builds: Vec<(&str, &[&str])> = match engine == target {
+        _ if no_engine() => vec![(&target, LIBRARIES)],
+        true => vec![(&target, &all)],
+        false => vec![(&engine, &[ENGINE]), (&target, LIBRARIES)],
+    };
+    for (triple, products) in builds {
+        // clean upsteam honggfuzz directory, built with another configuration before
+        let status = Command::new(GNU_MAKE)
+            .args(["-C", sources, "clean"])
+            .status()
+            .unwrap_or_else(|_| panic!("failed to run \"make -C {} clean\"", sources));
+        assert!(status.success());
+
+        // build honggfuzz command and hfuzz static library
+        let mut command = Command::new(GNU_MAKE);
+        command.args(["-C", sources]).args(make_args(out_dir, triple, missing)).args(products);
+        toolchain(&mut command, triple);
+        parallel(&mut command);
+        let status = command.status()
+            .unwrap_or_else(|_| panic!("failed to run \"make -C {} {}\"", sources, products.join(" ")));
+        assert!(status.success());
+        for product in products {
+            copy(&format!("{}/{}", sources, product), partial);
+        }
+    }
+}
+
+// the Android engine is built by the `android` rule of the Makefile with ndk-build, and with libunwind and
+// capstone: git submodules of honggfuzz, which the sources packaged on crates.io don't have
+fn make_android_engine(sources: &str, abi: &str, partial: &str) {
+    let ndk = android_ndk().unwrap_or_else(|| {
+        eprintln!("building honggfuzz for Android needs the Android NDK, set ANDROID_NDK_HOME to its directory");
+        process::exit(1);
+    });
+    if !Path::new(sources).join(".git").exists() && !Path::new(sources).join("third_party/android/libunwind/.git").exists() {
+        eprintln!("building honggfuzz for Android needs its libunwind and capstone submodules, which the sources packaged \
+                   on crates.io don't have: depend on honggfuzz-rs from git (`honggfuzz = {{ git = \"https://github.com/rust-fuzz/honggfuzz-rs\" }}`), \
+                   cargo checking out its submodules");
+        process::exit(1);
+    }
+    let status = Command::new(GNU_MAKE)
+        .args(["-C", sources, "clean"])
+        .status()
+        .unwrap_or_else(|_| panic!("failed to run \"make -C {} clean\"", sources));
+    assert!(status.success());
+    let mut command = Command::new(GNU_MAKE);
+    command.args(["-C", sources, "android", &format!("ANDROID_APP_ABI={}", abi)])
+        .env("NDK", &ndk)
+        .env("PATH", format!("{}:{}", ndk, env::var("PATH").unwrap_or_default())); // for ndk-build
+    parallel(&mut command);
+    let status = command.status()
+        .unwrap_or_else(|_| panic!("failed to run \"make -C {} android ANDROID_APP_ABI={}\"", sources, abi));
+    assert!(status.success());
+    // ndk-build names the library of libhfcommon after its module, `common`
+    if !no_engine() {
+        copy(&format!("{}/libs/{}/honggfuzz", sources, abi), partial);
+    }
+    copy(&format!("{}/obj/local/{}/libhfuzz.a", sources, abi), partial);
+    copy(&format!("{}/obj/local/{}/libcommon.a", sources, abi), &format!("{}/libhfcommon.a", partial));
+}
+
+// FNV-1a, stable across the builds and the versions of Rust unlike the hashers of std
+fn fnv(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+// the C sources and the makefiles in `dir`, what the engine is built from
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|e| e.path()) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if path.is_dir() {
+            if name != ".git" {
+                source_files(&path, files);
+            }
+        } else if name == "Makefile" || [".c", ".h", ".mk"].iter().any(|e| name.ends_with(e)) {
+            files.push(path);
+        }
+    }
+}
+
+// what the engine built from `sources` depends on: the sources, the target, the features and the
+// toolchain, the same in every build directory, so that the builds of the targets with other profiles
+// or sanitizers share it
+fn engine_fingerprint(sources: &str, missing: &[&SystemLibrary]) -> String {
+    let mut files = vec![];
+    source_files(Path::new(sources), &mut files);
+    files.sort();
+    let mut hash = fnv(0xcbf2_9ce4_8422_2325, VERSION.as_bytes());
+    for file in &files {
+        // computed again when the submodule is updated, the files rather than their directory, where
+        // make writes its objects
+        println!("cargo:rerun-if-changed={}", file.display());
+        hash = fnv(hash, file.strip_prefix(sources).unwrap_or(file).to_string_lossy().as_bytes());
+        hash = fnv(hash, &fs::read(file).unwrap_or_default());
+    }
+    let features = ENGINE_FEATURES.iter().map(|(feature, _)| (feature.to_string(), env::var_os(format!("CARGO_FEATURE_{}", feature)).is_some()));
+    let toolchain = [engine_triple(), target()].map(|triple| TOOLCHAIN.iter().map(|name| toolchain_value(name, &triple)).collect::<Vec<_>>());
+    let android = (android_ndk(), env::var("ANDROID_API").ok());
+    let missing: Vec<&str> = missing.iter().map(|library| library.name).collect();
+    let configuration = format!("{} {} {:?} {:?} {:?} {:?} {}", engine_triple(), target(), features.collect::<Vec<_>>(), toolchain, android, missing, no_engine());
+    format!("{:016x}", fnv(hash, configuration.as_bytes()))
+}
+
+fn copy(from: &str, to: &str) {
+    let status = Command::new("cp")
+        .args([from, to])
+        .status()
+        .unwrap_or_else(|_| panic!("failed to run \"cp {} {}\"", from, to));
+    assert!(status.success());
+}
+
 fn main() {
+    // the engine is rebuilt when cargo-hfuzz asks for another one
+    println!("cargo:rerun-if-changed=build.rs");
+    for variable in ["CARGO_HONGGFUZZ_TARGET_DIR", "CARGO_HONGGFUZZ_BUILD_VERSION", "CARGO_HONGGFUZZ_ENGINE_VERSION", "CARGO_NET_OFFLINE", "CRATE_ROOT",
+                     "ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "NDK", "ANDROID_API"] {
+        println!("cargo:rerun-if-env-changed={}", variable);
+    }
+    for triple in [engine_triple(), target()] {
+        for variable in TOOLCHAIN.iter().flat_map(|name| toolchain_vars(name, &triple)) {
+            println!("cargo:rerun-if-env-changed={}", variable);
+        }
+    }
+
     // Only build honggfuzz binaries if we are in the process of building an instrumentized binary
     let honggfuzz_target=  match env::var("CARGO_HONGGFUZZ_TARGET_DIR") {
         Ok(path) => path, // path where to place honggfuzz binary. provided by cargo-hfuzz command.
@@ -32,43 +457,60 @@ fn main() {
 
     let out_dir = env::var("OUT_DIR").unwrap(); // from cargo
     let crate_root = env::var("CRATE_ROOT").unwrap(); //from honggfuzz
+    let sources = engine_sources(&out_dir);
+    let missing = missing_libraries(&out_dir);
+    // only shown by cargo for a path dependency, cargo-hfuzz warns from the version file
+    for library in &missing {
+        println!("cargo:warning=honggfuzz is built without {}, not found: {}", library.name, library.without);
+    }
 
-    // clean upsteam honggfuzz directory
-    let status = Command::new(GNU_MAKE)
-        .args(&["-C", "honggfuzz", "clean"])
-        .status()
-        .expect("failed to run \"make -C honggfuzz clean\"");
-    assert!(status.success());
-    // TODO: maybe it's not a good idea to always clean the sources..
+    // the engine is built once for a fingerprint, then copied from the cache in the honggfuzz target
+    // directory by the builds of the library in the other build directories
+    let cache = format!("{}/{}/honggfuzz-engine/{}", &crate_root, &honggfuzz_target, engine_fingerprint(&sources, &missing));
+    if !Path::new(&cache).join("libhfuzz.a").exists() {
+        // filled aside then renamed, for a concurrent build never to copy half of it
+        let partial = format!("{}.{}", &cache, process::id());
+        fs::create_dir_all(&partial).unwrap_or_else(|_| panic!("failed to create \"{}\"", &partial));
 
-    // build honggfuzz command and hfuzz static library
-    let status = Command::new(GNU_MAKE)
-        .args(&["-C", "honggfuzz", "honggfuzz", "libhfuzz/libhfuzz.a", "libhfcommon/libhfcommon.a"])
-        .status()
-        .expect("failed to run \"make -C honggfuzz hongfuzz libhfuzz/libhfuzz.a libhfcommon/libhfcommon.a\"");
-    assert!(status.success());
+        match android_abi(&target()) {
+            Some(abi) => make_android_engine(&sources, abi, &partial),
+            None => make_engine(&sources, &out_dir, &partial, &missing),
+        }
+        if fs::rename(&partial, &cache).is_err() {
+            // another build filled it first
+            let _ = fs::remove_dir_all(&partial);
+        }
+    }
 
     // copy hfuzz static library to output directory
-    let status = Command::new("cp")
-        .args(&["honggfuzz/libhfuzz/libhfuzz.a", &out_dir])
-        .status()
-        .expect(&format!("failed to run \"cp honggfuzz/libhfuzz/libhfuzz.a {}\"", &out_dir));
-    assert!(status.success());
-    let status = Command::new("cp")
-        .args(&["honggfuzz/libhfcommon/libhfcommon.a", &out_dir])
-        .status()
-        .expect(&format!("failed to run \"cp honggfuzz/libhfcommon/libhfcommon.a {}\"", &out_dir));
-    assert!(status.success());
+    copy(&format!("{}/libhfuzz.a", &cache), &out_dir);
+    copy(&format!("{}/libhfcommon.a", &cache), &out_dir);
 
     // copy honggfuzz executable to honggfuzz target directory
-    let status = Command::new("cp")
-        .args(&["honggfuzz/honggfuzz", &format!("{}/{}", &crate_root, &honggfuzz_target)])
-        .status()
-        .expect(&format!("failed to run \"cp honggfuzz/honggfuzz {}\"", &honggfuzz_target));
-    assert!(status.success());
+    if !no_engine() {
+        copy(&format!("{}/honggfuzz", &cache), &format!("{}/{}", &crate_root, &honggfuzz_target));
+    }
+
+    // honggfuzz has no option printing its version, "cargo hfuzz version" reads it from here
+    let engine_version = engine_version(&sources).unwrap_or_else(|| "unknown".to_string());
+    // only a git checkout (the submodule) knows its commit, not the sources packaged on crates.io
+    let engine_commit = if sources == "honggfuzz" && Path::new("honggfuzz/.git").exists() {
+        Command::new("git")
+            .args(["-C", "honggfuzz", "rev-parse", "--short", "HEAD"])
+            .output().ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    } else {
+        None
+    }.unwrap_or_else(|| "unknown".to_string());
+    // then the libraries the engine is built without, with what it lacks
+    let without: String = missing.iter().map(|library| format!("without {}: {}\n", library.name, library.without)).collect();
+    let version_file = format!("{}/{}/honggfuzz.version", &crate_root, &honggfuzz_target);
+    fs::write(&version_file, format!("{}\n{}\n{}\n{}", engine_version, engine_commit, VERSION, without))
+        .unwrap_or_else(|_| panic!("failed to write \"{}\"", &version_file));
 
     // tell cargo how to link final executable to hfuzz static library
-    println!("cargo:rustc-link-lib=static={}", "hfuzz");
-    println!("cargo:rustc-link-lib=static={}", "hfcommon");
+    println!("cargo:rustc-link-lib=static=hfuzz");
+    println!("cargo:rustc-link-lib=static=hfcommon");
     println!("cargo:rustc-link-search=native={}", &out_dir);
 }
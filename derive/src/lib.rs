@@ -0,0 +1,228 @@
+//! The `#[derive(FuzzInput)]` of [honggfuzz](https://docs.rs/honggfuzz), see `honggfuzz::FuzzInput`.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+// how a field is read, from its `#[fuzz(...)]` attribute
+enum Read {
+    Value,
+    // `#[fuzz(skip)]`, left to `Default`
+    Skip,
+    // `#[fuzz(rest)]`, the elements until the end of the input
+    Rest,
+    // `#[fuzz(len = u8)]`, after a length of this type
+    Len(String),
+    // `#[fuzz(range = START..END)]` or `START..=END`
+    Range { start: String, end: String, inclusive: bool },
+}
+
+struct Field {
+    name: Option<String>,
+    ty: String,
+    read: Read,
+}
+
+enum Fields {
+    Named(Vec<Field>),
+    Unnamed(Vec<Field>),
+    Unit,
+}
+
+struct Variant {
+    name: String,
+    fields: Fields,
+}
+
+#[proc_macro_derive(FuzzInput, attributes(fuzz))]
+pub fn derive_fuzz_input(input: TokenStream) -> TokenStream {
+    let code = derive(input).unwrap_or_else(|error| format!("compile_error!({:?});", error));
+    code.parse().unwrap()
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(i)) if i.to_string() == name)
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+// the parts of a list separated by commas, the ones between `<` and `>` of the types not counting
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0;
+    let mut previous_dash = false;
+    for token in stream {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                parts.push(Vec::new());
+                previous_dash = false;
+                continue;
+            }
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            // the `>` of `->` doesn't close anything
+            TokenTree::Punct(p) if p.as_char() == '>' && !previous_dash => depth -= 1,
+            _ => {}
+        }
+        previous_dash = matches!(&token, TokenTree::Punct(p) if p.as_char() == '-' && p.spacing() == Spacing::Joint);
+        parts.last_mut().unwrap().push(token);
+    }
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+fn parse_read(stream: TokenStream) -> Result<Read, String> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let value = || match is_punct(tokens.get(1), '=') {
+        true => Ok(&tokens[2..]),
+        false => Err(format!("expected `{} = ...` in `#[fuzz(...)]`", tokens[0])),
+    };
+    match tokens.first() {
+        Some(TokenTree::Ident(i)) if i.to_string() == "skip" && tokens.len() == 1 => Ok(Read::Skip),
+        Some(TokenTree::Ident(i)) if i.to_string() == "rest" && tokens.len() == 1 => Ok(Read::Rest),
+        Some(TokenTree::Ident(i)) if i.to_string() == "len" => match to_string(value()?).as_str() {
+            len @ ("u8" | "u16" | "u32") => Ok(Read::Len(len.to_string())),
+            other => Err(format!("unsupported length type `{}` in `#[fuzz(len = ...)]`, expected u8, u16 or u32", other)),
+        },
+        Some(TokenTree::Ident(i)) if i.to_string() == "range" => {
+            let range = value()?;
+            let dots = (0..range.len()).find(|&i| is_punct(range.get(i), '.') && is_punct(range.get(i + 1), '.'))
+                .ok_or("expected `range = START..END` or `range = START..=END` in `#[fuzz(...)]`")?;
+            let inclusive = is_punct(range.get(dots + 2), '=');
+            let end = &range[dots + 2 + inclusive as usize..];
+            if dots == 0 || end.is_empty() {
+                return Err("`#[fuzz(range = ...)]` needs both ends of the range".to_string());
+            }
+            Ok(Read::Range { start: to_string(&range[..dots]), end: to_string(end), inclusive })
+        }
+        _ => Err(format!("unsupported `#[fuzz({})]`, expected `skip`, `rest`, `len = u8|u16|u32` or `range = START..END`", to_string(&tokens))),
+    }
+}
+
+// the outer attributes at `tokens[*i]`, skipped, the value of a `#[fuzz(...)]` among them
+fn parse_attributes(tokens: &[TokenTree], i: &mut usize) -> Result<Read, String> {
+    let mut read = Read::Value;
+    while is_punct(tokens.get(*i), '#') {
+        let Some(TokenTree::Group(group)) = tokens.get(*i + 1) else { break };
+        let attribute: Vec<TokenTree> = group.stream().into_iter().collect();
+        if is_ident(attribute.first(), "fuzz") {
+            match attribute.get(1) {
+                Some(TokenTree::Group(arguments)) if arguments.delimiter() == Delimiter::Parenthesis => read = parse_read(arguments.stream())?,
+                _ => return Err("expected `#[fuzz(...)]`".to_string()),
+            }
+        }
+        *i += 2;
+    }
+    Ok(read)
+}
+
+// `pub`, `pub(crate)` and the like
+fn skip_visibility(tokens: &[TokenTree], i: &mut usize) {
+    if is_ident(tokens.get(*i), "pub") {
+        *i += 1;
+        if matches!(tokens.get(*i), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis) {
+            *i += 1;
+        }
+    }
+}
+
+fn parse_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, String> {
+    split_commas(stream).into_iter().map(|tokens| {
+        let mut i = 0;
+        let read = parse_attributes(&tokens, &mut i)?;
+        skip_visibility(&tokens, &mut i);
+        let name = match named {
+            true => {
+                let name = tokens.get(i).map(|t| t.to_string()).ok_or("expected a field name")?;
+                if !is_punct(tokens.get(i + 1), ':') {
+                    return Err(format!("expected the type of the field `{}`", name));
+                }
+                i += 2;
+                Some(name)
+            }
+            false => None,
+        };
+        Ok(Field { name, ty: to_string(&tokens[i..]), read })
+    }).collect()
+}
+
+fn parse_group_fields(token: Option<&TokenTree>) -> Result<Fields, String> {
+    match token {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => Ok(Fields::Named(parse_fields(g.stream(), true)?)),
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => Ok(Fields::Unnamed(parse_fields(g.stream(), false)?)),
+        _ => Ok(Fields::Unit),
+    }
+}
+
+fn parse_variants(stream: TokenStream) -> Result<Vec<Variant>, String> {
+    split_commas(stream).into_iter().map(|tokens| {
+        let mut i = 0;
+        parse_attributes(&tokens, &mut i)?;
+        let name = tokens.get(i).map(|t| t.to_string()).ok_or("expected a variant name")?;
+        // an explicit discriminant, like `= 3`, changes nothing
+        let fields = parse_group_fields(tokens.get(i + 1))?;
+        Ok(Variant { name, fields })
+    }).collect()
+}
+
+fn decode_field(field: &Field) -> String {
+    let ty = &field.ty;
+    match &field.read {
+        Read::Value => format!("<{} as ::honggfuzz::FuzzInput>::decode(decoder)", ty),
+        Read::Skip => "::core::default::Default::default()".to_string(),
+        Read::Rest => format!("<{} as ::honggfuzz::input::Sequence>::decode_len(decoder, usize::MAX)", ty),
+        Read::Len(len) => format!("{{ let len = <{} as ::honggfuzz::FuzzInput>::decode(decoder) as usize; <{} as ::honggfuzz::input::Sequence>::decode_len(decoder, len) }}", len, ty),
+        Read::Range { start, end, inclusive } => format!("::honggfuzz::input::Ranged::in_range(<{} as ::honggfuzz::FuzzInput>::decode(decoder), {}, {}, {})", ty, start, end, inclusive),
+    }
+}
+
+// the fields are decoded in their order, the one of the expressions of a struct literal
+fn construct(path: &str, fields: &Fields) -> String {
+    match fields {
+        Fields::Named(fields) => format!("{} {{ {} }}", path, fields.iter()
+            .map(|f| format!("{}: {}", f.name.as_deref().unwrap(), decode_field(f)))
+            .collect::<Vec<_>>().join(", ")),
+        Fields::Unnamed(fields) => format!("{}({})", path, fields.iter().map(decode_field).collect::<Vec<_>>().join(", ")),
+        Fields::Unit => path.to_string(),
+    }
+}
+
+fn derive(input: TokenStream) -> Result<String, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+    parse_attributes(&tokens, &mut i)?;
+    skip_visibility(&tokens, &mut i);
+    let kind = tokens.get(i).map(|t| t.to_string()).unwrap_or_default();
+    let name = tokens.get(i + 1).map(|t| t.to_string()).ok_or("expected the name of the type")?;
+    if is_punct(tokens.get(i + 2), '<') {
+        return Err(format!("`#[derive(FuzzInput)]` doesn't support the generic type `{}`", name));
+    }
+    if is_ident(tokens.get(i + 2), "where") {
+        return Err(format!("`#[derive(FuzzInput)]` doesn't support the `where` clause of `{}`", name));
+    }
+    let body = match kind.as_str() {
+        "struct" => construct("Self", &parse_group_fields(tokens.get(i + 2))?),
+        "enum" => {
+            let Some(TokenTree::Group(group)) = tokens.get(i + 2) else { return Err(format!("expected the variants of `{}`", name)) };
+            let variants = parse_variants(group.stream())?;
+            if variants.is_empty() {
+                return Err(format!("the enum `{}` has no variant to decode", name));
+            }
+            // a byte chooses the variant, two with more than 256 of them
+            let tag = if variants.len() <= 256 { "u8" } else { "u16" };
+            let arms: Vec<String> = variants.iter().enumerate().map(|(index, variant)| {
+                let pattern = if index + 1 == variants.len() { "_".to_string() } else { index.to_string() };
+                format!("{} => {},", pattern, construct(&format!("Self::{}", variant.name), &variant.fields))
+            }).collect();
+            format!("match <{} as ::honggfuzz::FuzzInput>::decode(decoder) as usize % {} {{ {} }}", tag, variants.len(), arms.join(" "))
+        }
+        "union" => return Err(format!("`#[derive(FuzzInput)]` doesn't support the union `{}`", name)),
+        _ => return Err("expected a struct or an enum".to_string()),
+    };
+    Ok(format!(
+        "#[automatically_derived] impl ::honggfuzz::FuzzInput for {} {{ #[allow(unused_variables)] fn decode(decoder: &mut ::honggfuzz::input::Decoder) -> Self {{ {} }} }}",
+        name, body))
+}
@@ -0,0 +1,12 @@
+#![no_main]
+use honggfuzz::fuzz_target;
+
+// A harness written for cargo-fuzz, as it is: the fuzz_target macro defines
+// the main function which calls `fuzz` ad vitam aeternam.
+fuzz_target!(|data: &[u8]| {
+    if data.len() != 3 {return}
+    if data[0] != b'h' {return}
+    if data[1] != b'e' {return}
+    if data[2] != b'y' {return}
+    panic!("BOOM")
+});
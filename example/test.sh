@@ -38,6 +38,25 @@ crash_path="$(ls $workspace/*.fuzz | head -n1)"
 # verify that the fuzzing process found the crash
 test $(cat "$crash_path") = "hey"
 
+# fuzz for a while with the options of cargo-hfuzz, the summary of the session telling it fuzzed
+cd subdirectory
+HFUZZ_RUN_ARGS="-v" cargo hfuzz run example --max-time 10 --timeout 2
+cd ..
+grep -q '"exit_code":0,' $workspace/summary.json
+grep -Eq '"total_exec":[1-9]' $workspace/summary.json
+
+# the coverage not growing anymore is POSTed to the webhook, here to a curl saving what it is given
+notifications="$(mktemp -d)"
+printf '#!/bin/sh\ncat >> "%s/posted"\n' "$notifications" > "$notifications/curl"
+chmod +x "$notifications/curl"
+# the hfuzz.toml of the job, like the musl target of ubuntu-musl, being kept
+if [ -f hfuzz.toml ]; then cp hfuzz.toml "$notifications/hfuzz.toml"; fi
+printf '[notify]\nplateau = 1\n' >> hfuzz.toml
+PATH="$notifications:$PATH" HFUZZ_WEBHOOK="http://localhost/hook" HFUZZ_RUN_ARGS="-v" cargo hfuzz run example --max-time 15
+grep -q '"event":"plateau"' "$notifications/posted"
+if [ -f "$notifications/hfuzz.toml" ]; then mv "$notifications/hfuzz.toml" hfuzz.toml; else rm hfuzz.toml; fi
+rm -rf "$notifications"
+
 # build example in debug mode (and without sanitizers)
 cd subdirectory
 RUSTFLAGS="" cargo hfuzz build-debug --verbose
@@ -64,6 +83,13 @@ status=$?
 set -e
 test $status -eq 2
 
+# replaying the workspace should fail as long as the crash file still crashes
+set +e
+cargo hfuzz replay example
+status=$?
+set -e
+test $status -eq 1
+
 # run `hfuzz clean` from a subdirectory just to check that hfuzz subcommands are run at the crate root
 cd subdirectory
 cargo hfuzz clean
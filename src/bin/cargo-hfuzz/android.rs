@@ -0,0 +1,153 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, ExitStatus};
+
+use crate::cli::Opt;
+use crate::log;
+use crate::workspace;
+
+pub const ANDROID: Opt = Opt {
+    name: "--android",
+    value: None,
+    help: "builds for Android (aarch64-linux-android unless \"--target\" is given) and fuzzes on the device of adb",
+};
+
+// the triple of "--android" without "--target", the one of most devices
+pub const DEFAULT_TRIPLE: &str = "aarch64-linux-android";
+
+// where the engine, the target and its workspace are pushed on the device
+const DEVICE_DIR: &str = "/data/local/tmp/hfuzz";
+
+pub fn is_android(triple: &str) -> bool {
+    triple.contains("-linux-android")
+}
+
+// the adb of HFUZZ_ADB or the one in PATH, ANDROID_SERIAL picking the device when several are connected
+fn adb() -> Command {
+    Command::new(env::var_os("HFUZZ_ADB").filter(|a| !a.is_empty()).unwrap_or_else(|| "adb".into()))
+}
+
+// the directory of the Android NDK, which build.rs builds the engine with too
+fn ndk() -> Option<PathBuf> {
+    ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "NDK"].iter().find_map(|v| env::var_os(v).filter(|d| !d.is_empty())).map(PathBuf::from)
+}
+
+// the API level of ANDROID_API, which the Makefile of honggfuzz reads too, like "android-26" by default
+fn api_level() -> String {
+    env::var("ANDROID_API").ok().and_then(|api| api.trim().strip_prefix("android-").map(String::from)).unwrap_or_else(|| "26".to_string())
+}
+
+// the clang of the NDK linking the targets, like
+// `toolchains/llvm/prebuilt/linux-x86_64/bin/aarch64-linux-android26-clang` for `aarch64-linux-android`
+pub fn linker(triple: &str) -> Option<PathBuf> {
+    let clang_triple = match triple.split('-').next()? {
+        "armv7" | "thumbv7neon" => "armv7a-linux-androideabi",
+        _ => triple,
+    };
+    let host = match env::consts::OS {
+        "macos" => "darwin-x86_64",
+        _ => "linux-x86_64",
+    };
+    Some(ndk()?.join("toolchains/llvm/prebuilt").join(host).join("bin").join(format!("{}{}-clang", clang_triple, api_level())))
+}
+
+// the command of honggfuzz on the device and the files it needs there: the workspace of the target is
+// pushed as a whole, the other paths of the command (the target, a dictionary...) one by one
+struct DeviceCommand {
+    line: String,
+    pushes: Vec<(PathBuf, String)>,
+}
+
+fn device_command(target: &str, command: &Command) -> DeviceCommand {
+    let workspace = workspace::target_workspace(target);
+    let absolute_workspace = env::current_dir().unwrap_or_default().join(&workspace);
+    let device_workspace = format!("{}/workspace/{}", DEVICE_DIR, target);
+    let mut pushes = vec![
+        (PathBuf::from(command.get_program()), format!("{}/honggfuzz", DEVICE_DIR)),
+        (workspace.clone(), device_workspace.clone()),
+    ];
+    let mut on_device = |arg: &OsStr| -> OsString {
+        let path = Path::new(arg);
+        for local in [&workspace, &absolute_workspace] {
+            if let Ok(rest) = path.strip_prefix(local) {
+                return Path::new(&device_workspace).join(rest).into_os_string();
+            }
+        }
+        // only what looks like a path, a thread count naming a file of the current directory isn't one
+        match arg.to_string_lossy().contains('/') && path.exists() {
+            true => {
+                let device_path = format!("{}/files/{}", DEVICE_DIR, path.file_name().unwrap_or_default().to_string_lossy());
+                pushes.push((path.to_path_buf(), device_path.clone()));
+                device_path.into()
+            }
+            false => arg.to_os_string(),
+        }
+    };
+    let env: Vec<String> = command.get_envs()
+        .filter_map(|(key, value)| Some(format!("{}={}", key.to_string_lossy(), log::quoted(&on_device(value?)))))
+        .collect();
+    let args: Vec<String> = command.get_args().map(|a| log::quoted(&on_device(a))).collect();
+    let line = format!("cd {} && env {} ./honggfuzz {}", DEVICE_DIR, env.join(" "), args.join(" "));
+    DeviceCommand { line, pushes }
+}
+
+fn run(command: &mut Command, what: &str) {
+    match log::status(command) {
+        Ok(status) if status.success() => {}
+        Ok(_) => {
+            eprintln!("error: failed to {}", what);
+            process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("error: failed to run \"{}\" ({}), install the platform tools of the Android SDK or point HFUZZ_ADB to adb", command.get_program().to_string_lossy(), error);
+            process::exit(1);
+        }
+    }
+}
+
+// `-n` with the number of CPUs of the device, nothing when it doesn't tell
+pub fn device_jobs() -> Vec<String> {
+    let output = log::output(adb().args(["shell", "nproc"])).ok().filter(|o| o.status.success());
+    match output.and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse::<usize>().ok()) {
+        Some(cpus) => vec!["-n".to_string(), cpus.to_string()],
+        None => vec![],
+    }
+}
+
+// `adb shell` running the command of honggfuzz on the device, previewed by `--dry-run`
+pub fn shell(target: &str, command: &Command) -> Command {
+    let mut shell = adb();
+    shell.arg("shell").arg(device_command(target, command).line);
+    shell
+}
+
+// fuzzes on the device: pushes the engine, the target and its workspace, runs honggfuzz there, then
+// pulls the workspace back with the inputs and the crashes it found
+pub fn fuzz(target: &str, command: &Command) -> ExitStatus {
+    let device_command = device_command(target, command);
+    let crashes = workspace::crash_files(target).len();
+    run(adb().args(["shell", "mkdir", "-p"]).arg(format!("{}/workspace", DEVICE_DIR)).arg(format!("{}/files", DEVICE_DIR)),
+        &format!("create \"{}\" on the device, is one connected? (\"adb devices\")", DEVICE_DIR));
+    for (local, device_path) in &device_command.pushes {
+        // a directory goes into the parent of its device path, adb naming it after the local one
+        let destination = match local.is_dir() {
+            true => device_path.rsplit_once('/').map_or(DEVICE_DIR.to_string(), |(parent, _)| format!("{}/", parent)),
+            false => device_path.clone(),
+        };
+        run(adb().arg("push").arg(local).arg(&destination), &format!("push \"{}\" to the device", local.display()));
+    }
+
+    println!("{}: fuzzing on the device, in \"{}\"", target, DEVICE_DIR);
+    let status = log::status(adb().arg("shell").arg(&device_command.line)).unwrap_or_else(|error| {
+        eprintln!("error: failed to run \"adb shell\" ({})", error);
+        process::exit(1);
+    });
+
+    let workspace = workspace::target_workspace(target);
+    let parent = workspace.parent().map_or(PathBuf::from("."), Path::to_path_buf);
+    run(adb().arg("pull").arg(format!("{}/workspace/{}", DEVICE_DIR, target)).arg(&parent), "pull the workspace from the device");
+    let new_crashes = workspace::crash_files(target).len().saturating_sub(crashes);
+    println!("{}: pulled the workspace from the device into \"{}\", {} new crash files", target, workspace.display(), new_crashes);
+    status
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use crate::cli::{Matches, Opt, Subcommand};
+use crate::crash::{self, Outcome};
+
+pub const ANNOTATE: Opt = Opt {
+    name: "--annotate",
+    value: Some("FORMAT"),
+    help: "also prints the crashes as annotations of the CI on the sources: github (for GitHub Actions)",
+};
+
+pub enum Format {
+    // workflow commands like `::error file=src/lib.rs,line=12,col=5::message`
+    Github,
+}
+
+pub fn selected(command: &Subcommand, matches: &Matches) -> Option<Format> {
+    match matches.value("--annotate")? {
+        "github" => Some(Format::Github),
+        format => command.error(&format!("unknown annotation format \"{}\", expected github", format)),
+    }
+}
+
+// the messages and the properties of workflow commands are on one line
+fn escape(text: &str, property: bool) -> String {
+    let text = text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    match property {
+        true => text.replace(':', "%3A").replace(',', "%2C"),
+        false => text,
+    }
+}
+
+// annotates the sources with the crash of `target` on `input`
+pub fn crash(format: &Format, target: &str, input: &Path, outcome: &Outcome) {
+    match format {
+        Format::Github => github(target, input, outcome),
+    }
+}
+
+// an error on the frame of the workspace closest to the crash, and a notice on each of the frames
+// calling it in the workspace, so that the crash shows up inline on pull requests
+fn github(target: &str, input: &Path, outcome: &Outcome) {
+    let root = crash::source_root();
+    let what = match (&outcome.panic_message, outcome.timed_out) {
+        (_, true) => "hangs".to_string(),
+        (Some(message), _) => format!("panicked: {}", message),
+        (None, _) => format!("crashed (exit code {:?})", outcome.code),
+    };
+    let title = format!("{} {}", target, if outcome.timed_out { "hangs" } else { "crashes" });
+    let frames: Vec<(String, u64, u64, &str)> = outcome.relevant_frames()
+        .filter_map(|f| f.source(&root).map(|(file, line, column)| (file, line, column, f.function.as_str())))
+        .collect();
+    let input = input.display();
+    match frames.split_first() {
+        Some(((file, line, column, _), callers)) => {
+            println!("::error file={},line={},col={},title={}::{}", escape(file, true), line, column, escape(&title, true),
+                escape(&format!("\"{}\" {} on the input {}", target, what, input), false));
+            for (file, line, column, function) in callers {
+                println!("::notice file={},line={},col={},title={}::{}", escape(file, true), line, column, escape(&title, true),
+                    escape(&format!("{} is on the stack of the crash of \"{}\" on the input {}", function, target, input), false));
+            }
+        }
+        // without a frame in the sources the annotation goes to the summary of the run
+        None => println!("::error title={}::{}", escape(&title, true), escape(&format!("\"{}\" {} on the input {}", target, what, input), false)),
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::Path;
+use std::process::{self, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{BuildType, NO_DEFAULT_RUSTFLAGS_OPT, hfuzz_build, bench_binary, select_build_options};
+use crate::cli::{Opt, Subcommand};
+use crate::config;
+use crate::features;
+use crate::instrumentation;
+use crate::json::Json;
+use crate::log;
+use crate::sanitizer;
+use crate::workspace;
+
+pub const BENCH: Subcommand = Subcommand {
+    name: "bench",
+    usage: "[OPTIONS] TARGET",
+    about: "measures the throughput of a target, its harness replaying the corpus without mutation",
+    options: &[
+        Opt { name: "--time", value: Some("SECS"), help: "how long the harness runs, 10 seconds by default" },
+        Opt { name: "--json", value: None, help: "prints the results as JSON" },
+        Opt { name: "--sanitizer", value: Some("NAME"), help: "builds with `-Z sanitizer=NAME`, to measure what it costs" },
+        NO_DEFAULT_RUSTFLAGS_OPT,
+        features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+        instrumentation::CMP_COVERAGE, instrumentation::NO_CMP_COVERAGE,
+        instrumentation::DIV_COVERAGE, instrumentation::NO_DIV_COVERAGE,
+        instrumentation::GEP_COVERAGE, instrumentation::NO_GEP_COVERAGE,
+    ],
+    passthrough: None,
+};
+
+// how long the harness may run over its time, finishing the input it's on, before being stopped
+const GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+// written by the harness when the time is up, `key=value` lines
+struct Report {
+    inputs: u64,
+    execs: u64,
+    elapsed_ns: u64,
+    p50_ns: u64,
+    p90_ns: u64,
+    p99_ns: u64,
+    max_ns: u64,
+    allocations: u64,
+    allocated_bytes: u64,
+}
+
+impl Report {
+    fn parse(content: &str) -> Report {
+        let value = |key: &str| content.lines()
+            .find_map(|l| l.strip_prefix(key).and_then(|l| l.strip_prefix('=')))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+        Report {
+            inputs: value("inputs"),
+            execs: value("execs"),
+            elapsed_ns: value("elapsed_ns"),
+            p50_ns: value("p50_ns"),
+            p90_ns: value("p90_ns"),
+            p99_ns: value("p99_ns"),
+            max_ns: value("max_ns"),
+            allocations: value("allocations"),
+            allocated_bytes: value("allocated_bytes"),
+        }
+    }
+
+    fn exec_per_sec(&self) -> u64 {
+        (self.execs as f64 / (self.elapsed_ns.max(1) as f64 / 1e9)) as u64
+    }
+
+    fn per_exec(&self, total: u64) -> f64 {
+        total as f64 / self.execs.max(1) as f64
+    }
+}
+
+// like "850ns", "12.5µs" or "3.1ms"
+fn duration(nanos: u64) -> String {
+    match nanos {
+        0..=999 => format!("{}ns", nanos),
+        1_000..=999_999 => format!("{:.1}µs", nanos as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1}ms", nanos as f64 / 1e6),
+        _ => format!("{:.1}s", nanos as f64 / 1e9),
+    }
+}
+
+pub fn hfuzz_bench<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = BENCH.parse(args);
+    let time: u64 = matches.parsed(&BENCH, "--time").unwrap_or(10);
+    let json = matches.flag("--json");
+    select_build_options(&BENCH, &matches);
+    let target = match matches.args.as_slice() {
+        [target] => target.clone(),
+        _ => BENCH.error("please specify the name of the target"),
+    };
+    let corpus = workspace::corpus_files(&target);
+    if corpus.is_empty() {
+        eprintln!("error: the corpus of \"{}\" is empty, fuzz it first with \"cargo hfuzz run {}\"", target, target);
+        process::exit(1);
+    }
+
+    hfuzz_build(vec!["--bin".to_string(), target.clone()].into_iter(), crate_root, &BuildType::Bench);
+
+    let report_file = workspace::report_dir(&target).join("bench.txt");
+    let _ = fs::remove_file(&report_file);
+    let _ = fs::create_dir_all(workspace::report_dir(&target));
+    let mut command = Command::new(bench_binary(&target));
+    command.env("CARGO_HONGGFUZZ_BENCH_INPUTS", workspace::input_dir(&target))
+        .env("CARGO_HONGGFUZZ_BENCH_TIME", time.to_string())
+        .env("CARGO_HONGGFUZZ_BENCH_REPORT", &report_file);
+    if let Some(max_len) = config::max_len(&target) {
+        command.env("CARGO_HONGGFUZZ_MAX_LEN", max_len.to_string());
+    }
+    sanitizer::set_options(&mut command, false);
+
+    log::note(&format!("{}: replaying {} inputs for {}s", target, corpus.len(), time));
+    log::select_target(&target);
+    let mut child = log::spawn(&mut command).unwrap_or_else(|error| {
+        eprintln!("error: failed to run \"{}\" ({})", command.get_program().to_string_lossy(), error);
+        process::exit(1);
+    });
+    // the time is checked between inputs, one running forever would keep the harness going
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            log::exited(&child, status);
+            break status;
+        }
+        if started.elapsed() > Duration::from_secs(time) + GRACE_PERIOD {
+            let _ = child.kill();
+            eprintln!("error: an input of the corpus runs for too long, find it with \"cargo hfuzz replay {}\"", target);
+            process::exit(1);
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+    let report = match fs::read_to_string(&report_file) {
+        Ok(content) if status.success() => Report::parse(&content),
+        _ => {
+            eprintln!("error: \"{}\" failed on an input of its corpus, find it with \"cargo hfuzz replay {}\"", target, target);
+            process::exit(status.code().unwrap_or(1));
+        }
+    };
+
+    if json {
+        println!("{}", Json::object(vec![
+            ("target", target.as_str().into()),
+            ("inputs", report.inputs.into()),
+            ("execs", report.execs.into()),
+            ("elapsed_ns", report.elapsed_ns.into()),
+            ("exec_per_sec", report.exec_per_sec().into()),
+            ("latency_p50_ns", report.p50_ns.into()),
+            ("latency_p90_ns", report.p90_ns.into()),
+            ("latency_p99_ns", report.p99_ns.into()),
+            ("latency_max_ns", report.max_ns.into()),
+            ("allocations", report.allocations.into()),
+            ("allocated_bytes", report.allocated_bytes.into()),
+        ]));
+        return;
+    }
+    println!("{}", target);
+    println!("  inputs:          {}", report.inputs);
+    println!("  execs:           {} in {}", report.execs, duration(report.elapsed_ns));
+    println!("  execs/sec:       {}", report.exec_per_sec());
+    println!("  latency:         p50 {}, p90 {}, p99 {}, max {}",
+        duration(report.p50_ns), duration(report.p90_ns), duration(report.p99_ns), duration(report.max_ns));
+    println!("  allocations:     {:.1} per exec, {:.0} bytes per exec",
+        report.per_exec(report.allocations), report.per_exec(report.allocated_bytes));
+}
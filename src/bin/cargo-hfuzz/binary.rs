@@ -0,0 +1,128 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use crate::{ENGINE, RunOptions, engine_command, pinned, select_layout, without_args};
+use crate::ci;
+use crate::cli::{Opt, Subcommand};
+use crate::dict;
+use crate::log;
+use crate::policy;
+use crate::session;
+use crate::workspace;
+
+pub const RUN_BINARY: Subcommand = Subcommand {
+    name: "run-binary",
+    usage: "[OPTIONS] NAME -- PROGRAM [ARGS ...]",
+    about: "fuzzes a pre-built executable without a Rust harness, like a C library's, with the perf feedback of honggfuzz, in the workspace of NAME",
+    options: &[
+        Opt { name: "--feedback", value: Some("KIND"), help: "what perf counts to guide the fuzzing: instr (instructions, the default), branch, bts-edge or ipt-block (Intel), or none" },
+        Opt { name: "--timeout", value: Some("SECS"), help: "timeout of a single run of the program, honggfuzz's `-t`" },
+        Opt { name: "--jobs", value: Some("N"), help: "number of fuzzing threads, one per CPU by default" },
+        Opt { name: "--pin-cpus", value: Some("LIST"), help: "fuzzes on these CPUs only, like \"0-3,8\" (with `taskset`)" },
+        Opt { name: "--max-time", value: Some("SECS"), help: "stops fuzzing after this time, honggfuzz's `--run_time`" },
+        Opt { name: "--iterations", value: Some("N"), help: "stops fuzzing after N runs of the program, by all the threads, honggfuzz's `-N`" },
+        Opt { name: "--ci", value: None, help: "prints a JSON summary and exits with 1 if any crash was found, needs \"--max-time\" or \"--iterations\"" },
+        Opt { name: "--dict", value: Some("FILE"), help: "mutates with the tokens of a libFuzzer/AFL dictionary, honggfuzz's `-w`" },
+        Opt { name: "--max-len", value: Some("BYTES"), help: "longest input, honggfuzz's `-F`" },
+        Opt { name: "--mutations", value: Some("N"), help: "mutates an input at most N times per run, honggfuzz's `-r`, or `run.mutations`" },
+        Opt { name: "--base-files-only", value: None, help: "runs the inputs of the corpus as they are, without mutating them, honggfuzz's `-r 0`" },
+        Opt { name: "--keep-going", value: Some("[N]"), help: "goes on fuzzing after crashes, even with `--exit_upon_crash`, until N unique ones" },
+        Opt { name: "--input", value: Some("DIR"), help: "draws inputs from this directory, repeatable, the first one being the corpus the new ones are saved in" },
+        Opt { name: "--engine", value: Some("PATH"), help: "fuzzes with this honggfuzz executable instead of the one built with the targets" },
+        Opt { name: "--crash-dir", value: Some("DIR"), help: "saves the crashes in DIR/TARGET instead of the workspace, or HFUZZ_CRASH_DIR or `workspace.crash_dir`" },
+        Opt { name: "--report-dir", value: Some("DIR"), help: "writes the reports and the session summary in DIR/TARGET, or HFUZZ_REPORT_DIR or `workspace.report_dir`" },
+        Opt { name: "--log-dir", value: Some("DIR"), help: "writes the logs in DIR/TARGET, or HFUZZ_LOG_DIR or `workspace.log_dir`" },
+        policy::EXIT_CODE_ON_CRASH, policy::EXIT_ZERO_ON_TIMEOUT, policy::FAIL_ON_HANG,
+    ],
+    passthrough: None,
+};
+
+// where honggfuzz writes the input in the arguments of the program, which reads it on stdin otherwise
+const FILE_PLACEHOLDER: &str = "___FILE___";
+
+// the options of honggfuzz counting what the program executes, the crashes being caught with ptrace
+fn feedback_args(kind: &str) -> Vec<String> {
+    let arg = match kind {
+        "none" => "-x",
+        _ if !cfg!(target_os = "linux") => RUN_BINARY.error(&format!("\"--feedback {}\" counts with the perf events of Linux, fuzz with \"--feedback none\" here", kind)),
+        "instr" => "--linux_perf_instr",
+        "branch" => "--linux_perf_branch",
+        "bts-edge" => "--linux_perf_bts_edge",
+        "ipt-block" => "--linux_perf_ipt_block",
+        _ => RUN_BINARY.error(&format!("unknown feedback \"{}\", expected instr, branch, bts-edge, ipt-block or none", kind)),
+    };
+    vec![arg.to_string()]
+}
+
+// a program without a directory is looked up in PATH, like the shell does
+fn find_program(program: &str) -> PathBuf {
+    let found = match program.contains('/') {
+        true => Some(PathBuf::from(program)).filter(|p| p.is_file()),
+        false => env::var_os("PATH").and_then(|path| env::split_paths(&path).map(|dir| dir.join(program)).find(|p| p.is_file())),
+    };
+    found.unwrap_or_else(|| {
+        eprintln!("error: no program \"{}\" to fuzz (a relative path starts from the crate root)", program);
+        process::exit(1);
+    })
+}
+
+pub fn hfuzz_run_binary<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = RUN_BINARY.parse(args);
+    let options = RunOptions::parse(&RUN_BINARY, &matches);
+    let feedback = feedback_args(matches.value("--feedback").unwrap_or("instr"));
+    if let Some(engine) = matches.value("--engine") {
+        let _ = ENGINE.set(PathBuf::from(engine));
+    }
+    workspace::select_input_dirs(matches.values("--input").into_iter().map(PathBuf::from).collect());
+    select_layout(&matches);
+    let mut args = matches.args.into_iter();
+    let name = args.next().unwrap_or_else(|| RUN_BINARY.error("please specify the name of the workspace and the program, like \"cargo hfuzz run-binary png -- ./pngtest ___FILE___\""));
+    let program = args.next().unwrap_or_else(|| RUN_BINARY.error("please specify the program to fuzz after its name"));
+    let args: Vec<String> = args.collect();
+    log::select_target(&name);
+
+    if options.ci && options.max_time.is_none() && options.iterations.is_none() {
+        RUN_BINARY.error("\"--ci\" needs a limit like \"--max-time 600\" or \"--iterations 1000000\"");
+    }
+    if options.iterations == Some(0) {
+        RUN_BINARY.error("\"--iterations\" needs a positive number, honggfuzz taking 0 as no limit");
+    }
+    options.check_mutations(&RUN_BINARY);
+    if let Some(dict) = &options.dict {
+        dict::check_dict(dict);
+    }
+
+    let program = find_program(&program);
+    let mut honggfuzz_args = options.honggfuzz_args();
+    honggfuzz_args.extend(feedback);
+    if !args.iter().any(|a| a.contains(FILE_PLACEHOLDER)) {
+        honggfuzz_args.push("-s".to_string());
+    }
+    let honggfuzz = || {
+        // the program runs once per input, unless honggfuzz finds the signature of libhfuzz's persistent mode in it
+        let mut command = without_args(&engine_command(&name, &honggfuzz_args, &program, args.iter().cloned()), &["-P"]);
+        if options.keep_going {
+            command = without_args(&command, &["--exit_upon_crash"]);
+        }
+        if let Some(cpus) = &options.pin_cpus {
+            command = pinned(&command, cpus);
+        }
+        command
+    };
+    if log::dry_run() {
+        return log::preview(&honggfuzz());
+    }
+    if options.ci {
+        ci::run(&name, honggfuzz(), options.max_crashes, &options.policy);
+    }
+    let session = session::fuzz(&name, honggfuzz(), options.max_crashes, None);
+    // interrupted, the session is over and so is the run
+    if let Some(signal) = session::stopped_by() {
+        process::exit(128 + signal);
+    }
+    if !session.status.success() {
+        process::exit(session.status.code().unwrap_or(1));
+    }
+    options.policy.exit(session.crashes(), session.hangs);
+}
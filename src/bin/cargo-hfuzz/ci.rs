@@ -0,0 +1,17 @@
+use std::process::{self, Command};
+
+use crate::policy::ExitPolicy;
+use crate::session;
+
+// exits with the code of the policy (1 by default) if honggfuzz found any crash, after printing the
+// summary of the session
+pub fn run(target: &str, command: Command, max_unique_crashes: Option<usize>, policy: &ExitPolicy) -> ! {
+    let session = session::fuzz(target, command, max_unique_crashes, None);
+    if !session.status.success() {
+        eprintln!("error: honggfuzz failed while fuzzing \"{}\"", target);
+        process::exit(session.status.code().unwrap_or(1));
+    }
+
+    println!("{}", session.to_json(target));
+    process::exit(policy.exit_code(session.crashes(), session.hangs).unwrap_or(0));
+}
@@ -0,0 +1,337 @@
+use std::env;
+use std::process;
+use std::str::FromStr;
+
+// an option of a subcommand, like `--timeout SECS`, without value it's a flag, and a value in brackets
+// like `[N]` is optional, only given as `--name=N`
+pub struct Opt {
+    pub name: &'static str,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub struct Subcommand {
+    pub name: &'static str,
+    // what follows the name on the usage line, like `[OPTIONS] TARGET`
+    pub usage: &'static str,
+    pub about: &'static str,
+    pub options: &'static [Opt],
+    // who is given the arguments it doesn't know, like "cargo build" or "the target"
+    pub passthrough: Option<&'static str>,
+}
+
+pub struct Matches {
+    options: Vec<(&'static str, String)>,
+    // the positional arguments, then the ones following `--`
+    pub args: Vec<String>,
+}
+
+const HELP: Opt = Opt { name: "-h, --help", value: None, help: "prints this help" };
+
+// longest distance between an unknown name and a known one to be considered a typo
+const TYPO_DISTANCE: usize = 2;
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            current.push((previous[j] + (ca != *cb) as usize).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// the closest name to an unknown one, if it looks like a typo
+pub fn suggestion<'a, T>(unknown: &str, names: T) -> Option<&'a str> where T: Iterator<Item=&'a str> {
+    names.map(|name| (distance(unknown, name), name))
+        .filter(|&(d, name)| d <= TYPO_DISTANCE && d < name.len() / 2 + 1)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, name)| name)
+}
+
+// the name of an optional value, without its brackets
+fn optional(value: &str) -> Option<&str> {
+    value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+}
+
+impl Subcommand {
+    pub fn usage(&self) -> String {
+        format!("cargo hfuzz {} {}", self.name, self.usage).trim_end().to_string()
+    }
+
+    pub fn print_help(&self) {
+        println!("{}", self.about);
+        println!();
+        println!("usage: {}", self.usage());
+        println!();
+        println!("options:");
+        let options: Vec<(String, &str)> = self.options.iter().chain(Some(&HELP))
+            .map(|o| (o.value.map_or(o.name.to_string(), |v| match optional(v) {
+                Some(v) => format!("{}[={}]", o.name, v),
+                None => format!("{} {}", o.name, v),
+            }), o.help))
+            .collect();
+        let width = options.iter().map(|(o, _)| o.len()).max().unwrap_or(0);
+        for (option, help) in options {
+            println!("    {:width$}    {}", option, help, width = width);
+        }
+        if let Some(receiver) = self.passthrough {
+            println!();
+            println!("the other arguments, and all of those following \"--\", are given to {}", receiver);
+        }
+    }
+
+    // prints an error about the arguments of the command and exits
+    pub fn error(&self, message: &str) -> ! {
+        eprintln!("error: {}", message);
+        eprintln!("usage: {}", self.usage());
+        eprintln!("for more information, try \"cargo hfuzz {} --help\"", self.name);
+        process::exit(1);
+    }
+
+    fn option(&self, name: &str) -> Option<&'static Opt> {
+        self.options.iter().find(|o| o.name == name)
+    }
+
+    // options are accepted anywhere before `--`, as `--name value` or `--name=value`
+    pub fn parse<T>(&self, mut args: T) -> Matches where T: Iterator<Item=String> {
+        let mut matches = Matches { options: Vec::new(), args: Vec::new() };
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                matches.args.extend(args.by_ref());
+                break;
+            }
+            if arg == "--help" || arg == "-h" {
+                self.print_help();
+                process::exit(0);
+            }
+            if !arg.starts_with('-') || arg == "-" {
+                matches.args.push(arg);
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            match self.option(name) {
+                Some(option) if option.value.and_then(optional).is_some() => {
+                    matches.options.push((option.name, inline_value.unwrap_or_default()));
+                }
+                Some(option) if option.value.is_some() => {
+                    let value = inline_value.or_else(|| args.next()).unwrap_or_else(|| {
+                        self.error(&format!("\"{}\" expects a value, like \"{} {}\"", name, name, option.value.unwrap()))
+                    });
+                    matches.options.push((option.name, value));
+                }
+                Some(option) if inline_value.is_none() => matches.options.push((option.name, String::new())),
+                Some(_) => self.error(&format!("\"{}\" doesn't take a value", name)),
+                None => {
+                    let known = self.options.iter().map(|o| o.name);
+                    match suggestion(name, known) {
+                        Some(known) => self.error(&format!("unknown option \"{}\", did you mean \"{}\"?", name, known)),
+                        None if self.passthrough.is_some() => matches.args.push(arg),
+                        None => self.error(&format!("unknown option \"{}\"", name)),
+                    }
+                }
+            }
+        }
+        matches
+    }
+}
+
+impl Matches {
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| *n == name)
+    }
+
+    // the last value given to an option
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+
+    // every value given to a repeatable option, in order
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|(n, _)| *n == name).map(|(_, v)| v.as_str()).collect()
+    }
+
+    pub fn parsed<T: FromStr>(&self, command: &Subcommand, name: &str) -> Option<T> {
+        self.value(name).map(|value| value.parse().unwrap_or_else(|_| {
+            let expected = command.option(name).and_then(|o| o.value).unwrap_or("VALUE");
+            command.error(&format!("invalid value \"{}\" for \"{} {}\"", value, name, expected))
+        }))
+    }
+}
+
+// "cargo hfuzz help [COMMAND]"
+pub fn print_commands(commands: &[&Subcommand]) {
+    println!("cargo-hfuzz: fuzz Rust code with honggfuzz");
+    println!();
+    println!("usage: cargo hfuzz [-v|-vv|-q] [--log-format text|json] [--no-color] [--dry-run] COMMAND [OPTIONS] ...");
+    println!();
+    println!("commands:");
+    let width = commands.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for command in commands {
+        println!("    {:width$}    {}", command.name, command.about, width = width);
+    }
+    println!();
+    println!("in a workspace, \"-p PACKAGE\" (or \"--package PACKAGE\") selects the member of the targets");
+    println!();
+    println!("before the command, \"-v\" prints the commands run (\"-vv\" with their environment) and \"-q\" hides");
+    println!("the progress notes, the commands being logged in hfuzz_workspace/TARGET/cargo-hfuzz.log either way,");
+    println!("and \"--log-format json\" prints them and the build and session events as JSON lines on stderr");
+    println!();
+    println!("\"--dry-run\" before a build or run command prints the cargo and honggfuzz command lines, with the");
+    println!("environment variables they would get (RUSTFLAGS...), instead of running them");
+    println!();
+    println!("see \"cargo hfuzz help COMMAND\" or \"cargo hfuzz COMMAND --help\" for the options of a command");
+}
+
+// splits a command line like a POSIX shell would, without expanding anything: words are separated by
+// whitespace, `'...'` is taken literally, `\` escapes the next character, and in `"..."` only `\"`, `\\`,
+// `\$` and `` \` `` are escapes
+pub fn shell_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".into()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if "\"\\$`".contains(c) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => word.extend(['\\', c]),
+                            None => return Err("unterminated double quote".into()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".into()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("nothing to escape after the final '\\'".into()),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// the arguments held by an environment variable like HFUZZ_RUN_ARGS, quoted like on a command line
+pub fn env_args(variable: &str) -> Vec<String> {
+    let value = env::var(variable).unwrap_or_default();
+    shell_words(&value).unwrap_or_else(|error| {
+        eprintln!("error: invalid {} ({}): {}", variable, error, value);
+        process::exit(1);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: Subcommand = Subcommand {
+        name: "test",
+        usage: "[OPTIONS] TARGET",
+        about: "",
+        options: &[
+            Opt { name: "--timeout", value: Some("SECS"), help: "" },
+            Opt { name: "--jobs", value: Some("[N]"), help: "" },
+            Opt { name: "--ci", value: None, help: "" },
+        ],
+        passthrough: Some("the target"),
+    };
+
+    fn parse(args: &[&str]) -> Matches {
+        COMMAND.parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn values_inline_or_following() {
+        let matches = parse(&["--timeout=5", "target", "--timeout", "7"]);
+        assert_eq!(matches.values("--timeout"), ["5", "7"]);
+        assert_eq!(matches.value("--timeout"), Some("7"));
+        assert_eq!(matches.parsed::<u64>(&COMMAND, "--timeout"), Some(7));
+        assert_eq!(matches.args, ["target"]);
+    }
+
+    #[test]
+    fn optional_values_only_inline() {
+        let matches = parse(&["--jobs", "target"]);
+        assert_eq!(matches.value("--jobs"), Some(""));
+        assert_eq!(matches.args, ["target"]);
+        assert_eq!(parse(&["--jobs=4"]).value("--jobs"), Some("4"));
+    }
+
+    #[test]
+    fn flags() {
+        let matches = parse(&["target", "--ci"]);
+        assert!(matches.flag("--ci"));
+        assert!(!matches.flag("--timeout"));
+        assert_eq!(matches.value("--timeout"), None);
+    }
+
+    #[test]
+    fn passthrough() {
+        // unknown options go to the passthrough, and everything after `--` too, options included
+        let matches = parse(&["--release", "target", "--", "--ci", "--timeout=5", "-"]);
+        assert!(!matches.flag("--ci"));
+        assert_eq!(matches.args, ["--release", "target", "--ci", "--timeout=5", "-"]);
+        assert_eq!(parse(&["-", "--", "--"]).args, ["-", "--"]);
+    }
+
+    fn words(line: &str) -> Vec<String> {
+        shell_words(line).unwrap()
+    }
+
+    #[test]
+    fn shell_words_split_on_whitespace() {
+        assert_eq!(words("  -n 4\t--exit_upon_crash\n"), ["-n", "4", "--exit_upon_crash"]);
+        assert!(words(" ").is_empty());
+    }
+
+    #[test]
+    fn shell_words_quotes() {
+        assert_eq!(words(r#"--dict 'my dict.txt' -w "a b""#), ["--dict", "my dict.txt", "-w", "a b"]);
+        assert_eq!(words(r#"'it''s' "" a"b"'c'"#), ["its", "", "abc"]);
+        assert_eq!(words(r#"'\n $HOME'"#), [r#"\n $HOME"#]);
+        assert_eq!(shell_words("'open"), Err("unterminated single quote".to_string()));
+        assert_eq!(shell_words("\"open"), Err("unterminated double quote".to_string()));
+    }
+
+    #[test]
+    fn shell_words_escapes() {
+        assert_eq!(words(r"a\ b \'c\\"), ["a b", "'c\\"]);
+        assert_eq!(words(r#""\"\\\$\`\n""#), [r#""\$`\n"#]);
+        assert_eq!(words("a\\\nb"), ["ab"]);
+        assert_eq!(shell_words("a\\"), Err("nothing to escape after the final '\\'".to_string()));
+    }
+
+    #[test]
+    fn typos() {
+        let names = ["--timeout", "--jobs", "--ci"];
+        assert_eq!(suggestion("--timout", names.iter().copied()), Some("--timeout"));
+        assert_eq!(suggestion("--release", names.iter().copied()), None);
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt::Write;
+
+use crate::cli::{Opt, Subcommand};
+use crate::sanitizer;
+use crate::scaffold;
+
+pub const COMPLETIONS: Subcommand = Subcommand {
+    name: "completions",
+    usage: "bash|zsh|fish",
+    about: "prints the completion script of a shell, for the commands, their options and the names of the fuzz targets",
+    options: &[],
+    passthrough: None,
+};
+
+// the options given before the command
+const GLOBAL_OPTIONS: &[Opt] = &[
+    Opt { name: "-v", value: None, help: "prints the commands run" },
+    Opt { name: "-vv", value: None, help: "prints the commands run with their environment" },
+    Opt { name: "-q", value: None, help: "hides the progress notes" },
+    Opt { name: "--log-format", value: Some("FORMAT"), help: "prints the commands and the events as JSON lines with json" },
+    Opt { name: "--no-color", value: None, help: "prints the summaries without colors" },
+    Opt { name: "--dry-run", value: None, help: "prints the build and honggfuzz command lines instead of running them" },
+];
+
+// taken by every command, the crate root being found before the command runs
+const PACKAGE: Opt = Opt { name: "--package", value: Some("PACKAGE"), help: "selects the workspace member of the targets" };
+const HELP: Opt = Opt { name: "--help", value: None, help: "prints the help of the command" };
+
+// the names of the fuzz targets, the first column of "cargo hfuzz list", read each time they are completed
+const LIST_TARGETS: &str = "cargo hfuzz list 2>/dev/null | tail -n +2 | cut -d' ' -f1";
+
+// what an option value or a positional argument is completed with
+#[derive(Clone)]
+enum Kind {
+    Nothing,
+    Files,
+    Dirs,
+    Targets,
+    Words(Vec<&'static str>),
+}
+
+fn value_kind(option: &Opt) -> Kind {
+    match (option.name, option.value.unwrap_or_default()) {
+        ("--sanitizer", _) => Kind::Words(sanitizer::SANITIZERS.to_vec()),
+        ("--log-format", _) => Kind::Words(vec!["text", "json"]),
+        ("--annotate", _) => Kind::Words(vec!["github"]),
+        ("--template", _) => Kind::Words(scaffold::template_names()),
+        (_, "FILE") | (_, "PATH") => Kind::Files,
+        (_, "DIR") => Kind::Dirs,
+        _ => Kind::Nothing,
+    }
+}
+
+// the positional arguments of a command, read from its usage line: how the first ones are completed,
+// then all the others
+fn positionals(command: &Subcommand) -> (Vec<Kind>, Kind) {
+    let alternatives: Vec<&'static str> = command.usage.split('|').map(str::trim).collect();
+    let words: Vec<&'static str> = alternatives.iter().filter_map(|a| a.split(' ').next())
+        .filter(|w| !w.is_empty() && w.chars().all(|c| c.is_ascii_lowercase() || c == '-'))
+        .collect();
+    // like "corpus push TARGET [REMOTE]"
+    if alternatives.len() > 1 && words.len() == alternatives.len() {
+        return match command.usage.contains("TARGET") {
+            true => (vec![Kind::Words(words), Kind::Targets], Kind::Files),
+            false => (vec![Kind::Words(words)], Kind::Nothing),
+        };
+    }
+    match command.usage {
+        usage if usage.contains("TARGET ...") => (vec![], Kind::Targets),
+        // the new targets of init and add don't exist yet
+        _ if matches!(command.name, "init" | "add") => (vec![], Kind::Nothing),
+        usage if usage.contains("TARGET") => (vec![Kind::Targets], Kind::Files),
+        _ => (vec![], Kind::Nothing),
+    }
+}
+
+fn options(command: &Subcommand) -> impl Iterator<Item=&Opt> {
+    command.options.iter().chain([&PACKAGE, &HELP])
+}
+
+// every option taking a value, the words following them not being positional arguments
+fn value_options(commands: &[&Subcommand]) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = commands.iter().flat_map(|c| options(c).chain(GLOBAL_OPTIONS))
+        .filter(|o| o.value.is_some_and(|v| !v.starts_with('[')))
+        .map(|o| o.name)
+        .chain(["-p"])
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn command_names(commands: &[&Subcommand]) -> Vec<&'static str> {
+    commands.iter().map(|c| c.name).chain(["help"]).collect()
+}
+
+fn bash_kind(kind: &Kind) -> String {
+    match kind {
+        Kind::Nothing => String::new(),
+        Kind::Files => "files".to_string(),
+        Kind::Dirs => "dirs".to_string(),
+        Kind::Targets => "targets".to_string(),
+        Kind::Words(words) => format!("words:{}", words.join(" ")),
+    }
+}
+
+fn bash(commands: &[&Subcommand]) -> String {
+    let mut script = String::new();
+    let value_options = value_options(commands);
+    let all_options: Vec<&Opt> = commands.iter().flat_map(|c| options(c)).chain(GLOBAL_OPTIONS).collect();
+    let _ = writeln!(script, "# bash completion of \"cargo hfuzz\", from \"cargo hfuzz completions bash\"");
+    let _ = writeln!(script, "_cargo_hfuzz() {{");
+    let _ = writeln!(script, "    local cur=${{COMP_WORDS[COMP_CWORD]}} prev=${{COMP_WORDS[COMP_CWORD-1]}}");
+    let _ = writeln!(script, "    local command= position=0 i");
+    // the values of the options
+    let _ = writeln!(script, "    case $prev in");
+    for name in &value_options {
+        let kind = all_options.iter().find(|o| o.name == *name).map_or(Kind::Nothing, |o| value_kind(o));
+        let reply = match kind {
+            Kind::Files => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+            Kind::Dirs => "COMPREPLY=($(compgen -d -- \"$cur\"))".to_string(),
+            Kind::Words(words) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", words.join(" ")),
+            _ => "COMPREPLY=()".to_string(),
+        };
+        let _ = writeln!(script, "        {}) {}; return ;;", name, reply);
+    }
+    let _ = writeln!(script, "    esac");
+    // the command and the position of the argument being completed
+    let _ = writeln!(script, "    for ((i = 2; i < COMP_CWORD; i++)); do");
+    let _ = writeln!(script, "        case ${{COMP_WORDS[i]}} in");
+    let _ = writeln!(script, "            {}) ((i++)) ;;", value_options.join("|"));
+    let _ = writeln!(script, "            -*) ;;");
+    let _ = writeln!(script, "            *) if [[ -z $command ]]; then command=${{COMP_WORDS[i]}}; else ((position++)); fi ;;");
+    let _ = writeln!(script, "        esac");
+    let _ = writeln!(script, "    done");
+    let _ = writeln!(script, "    local options= kinds=() rest=");
+    let _ = writeln!(script, "    case $command in");
+    let _ = writeln!(script, "        \"\") options=\"{}\"; kinds=(\"words:{}\") ;;",
+        GLOBAL_OPTIONS.iter().map(|o| o.name).collect::<Vec<_>>().join(" "), command_names(commands).join(" "));
+    let _ = writeln!(script, "        help) kinds=(\"words:{}\") ;;", command_names(commands).join(" "));
+    for command in commands {
+        let (kinds, rest) = positionals(command);
+        let kinds: Vec<String> = kinds.iter().map(|k| format!("\"{}\"", bash_kind(k))).collect();
+        let names: Vec<&str> = options(command).map(|o| o.name).chain(["-p"]).collect();
+        let _ = writeln!(script, "        {}) options=\"{}\"; kinds=({}); rest={} ;;", command.name, names.join(" "), kinds.join(" "), bash_kind(&rest));
+    }
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "    if [[ $cur == -* ]]; then");
+    let _ = writeln!(script, "        COMPREPLY=($(compgen -W \"$options\" -- \"$cur\"))");
+    let _ = writeln!(script, "        return");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "    local kind=${{kinds[position]:-$rest}}");
+    let _ = writeln!(script, "    case $kind in");
+    let _ = writeln!(script, "        targets) COMPREPLY=($(compgen -W \"$({})\" -- \"$cur\")) ;;", LIST_TARGETS);
+    let _ = writeln!(script, "        files) COMPREPLY=($(compgen -f -- \"$cur\")) ;;");
+    let _ = writeln!(script, "        dirs) COMPREPLY=($(compgen -d -- \"$cur\")) ;;");
+    let _ = writeln!(script, "        words:*) COMPREPLY=($(compgen -W \"${{kind#words:}}\" -- \"$cur\")) ;;");
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    // cargo's own completion, from rustup, keeps the other commands
+    let _ = writeln!(script, "declare -F _cargo >/dev/null || {{ declare -F _completion_loader >/dev/null && _completion_loader cargo; }}");
+    let _ = writeln!(script, "_cargo_hfuzz_dispatch() {{");
+    let _ = writeln!(script, "    if [[ ${{COMP_WORDS[1]}} == hfuzz ]] && ((COMP_CWORD > 1)); then");
+    let _ = writeln!(script, "        _cargo_hfuzz");
+    let _ = writeln!(script, "    elif declare -F _cargo >/dev/null; then");
+    let _ = writeln!(script, "        _cargo \"$@\"");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "complete -o filenames -F _cargo_hfuzz_dispatch cargo");
+    script
+}
+
+// in single quotes, the brackets ending the description of an option
+fn zsh_quoted(text: &str) -> String {
+    text.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]")
+}
+
+fn zsh_action(kind: &Kind) -> String {
+    match kind {
+        Kind::Nothing => " ".to_string(),
+        Kind::Files => "_files".to_string(),
+        Kind::Dirs => "_files -/".to_string(),
+        Kind::Targets => "_cargo_hfuzz_targets".to_string(),
+        Kind::Words(words) => format!("({})", words.join(" ")),
+    }
+}
+
+fn zsh_option(option: &Opt) -> String {
+    match option.value {
+        Some(value) if value.starts_with('[') => format!("'{}=-[{}]::{}: '", option.name, zsh_quoted(option.help), value.trim_matches(['[', ']'])),
+        Some(value) => format!("'{}[{}]:{}:{}'", option.name, zsh_quoted(option.help), value, zsh_action(&value_kind(option))),
+        None => format!("'{}[{}]'", option.name, zsh_quoted(option.help)),
+    }
+}
+
+fn zsh(commands: &[&Subcommand]) -> String {
+    let mut script = String::new();
+    let _ = writeln!(script, "# zsh completion of \"cargo hfuzz\", from \"cargo hfuzz completions zsh\"");
+    let _ = writeln!(script, "_cargo_hfuzz_targets() {{");
+    let _ = writeln!(script, "    local -a targets");
+    let _ = writeln!(script, "    targets=(${{(f)\"$({})\"}})", LIST_TARGETS);
+    let _ = writeln!(script, "    _describe -t targets 'fuzz target' targets");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "_cargo_hfuzz() {{");
+    let _ = writeln!(script, "    local curcontext=$curcontext state line");
+    let _ = writeln!(script, "    local -a commands");
+    let _ = writeln!(script, "    commands=(");
+    for command in commands {
+        let _ = writeln!(script, "        '{}:{}'", command.name, command.about.replace('\'', "'\\''"));
+    }
+    let _ = writeln!(script, "        'help:prints the help of a command'");
+    let _ = writeln!(script, "    )");
+    let _ = writeln!(script, "    _arguments -C \\");
+    for option in GLOBAL_OPTIONS {
+        let _ = writeln!(script, "        {} \\", zsh_option(option));
+    }
+    let _ = writeln!(script, "        '1:command:->command' \\");
+    let _ = writeln!(script, "        '*::argument:->argument'");
+    let _ = writeln!(script, "    case $state in");
+    let _ = writeln!(script, "        command) _describe -t commands 'cargo hfuzz command' commands ;;");
+    let _ = writeln!(script, "        argument)");
+    let _ = writeln!(script, "            curcontext=${{curcontext%:*:*}}:cargo-hfuzz-$words[1]:");
+    let _ = writeln!(script, "            case $words[1] in");
+    let _ = writeln!(script, "                help) _describe -t commands 'cargo hfuzz command' commands ;;");
+    for command in commands {
+        let (kinds, rest) = positionals(command);
+        let _ = writeln!(script, "                {})", command.name);
+        let _ = writeln!(script, "                    _arguments \\");
+        for option in options(command) {
+            let _ = writeln!(script, "                        {} \\", zsh_option(option));
+        }
+        let _ = writeln!(script, "                        '-p[{}]:PACKAGE: ' \\", zsh_quoted(PACKAGE.help));
+        for (i, kind) in kinds.iter().enumerate() {
+            let _ = writeln!(script, "                        '{}:argument:{}' \\", i + 1, zsh_action(kind));
+        }
+        let _ = writeln!(script, "                        '*:argument:{}'", zsh_action(&rest));
+        let _ = writeln!(script, "                    ;;");
+    }
+    let _ = writeln!(script, "            esac");
+    let _ = writeln!(script, "            ;;");
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    // cargo's own completion, from rustup, keeps the other commands
+    let _ = writeln!(script, "_cargo_hfuzz_dispatch() {{");
+    let _ = writeln!(script, "    if [[ $words[2] == hfuzz ]] && (( CURRENT > 2 )); then");
+    let _ = writeln!(script, "        shift words");
+    let _ = writeln!(script, "        (( CURRENT-- ))");
+    let _ = writeln!(script, "        _cargo_hfuzz");
+    let _ = writeln!(script, "    elif (( $+functions[_cargo] )) || autoload -Uz +X _cargo 2>/dev/null; then");
+    let _ = writeln!(script, "        _cargo \"$@\"");
+    let _ = writeln!(script, "    else");
+    let _ = writeln!(script, "        _default");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "compdef _cargo_hfuzz_dispatch cargo");
+    script
+}
+
+fn fish_quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// the arguments of `complete` completing `kind`
+fn fish_kind(kind: &Kind) -> String {
+    match kind {
+        Kind::Nothing => "-f".to_string(),
+        Kind::Files => "-F".to_string(),
+        Kind::Dirs => "-f -a '(__fish_complete_directories)'".to_string(),
+        Kind::Targets => format!("-f -a {} -d 'fuzz target'", fish_quoted(&format!("({})", LIST_TARGETS))),
+        Kind::Words(words) => format!("-f -a {}", fish_quoted(&words.join(" "))),
+    }
+}
+
+fn fish_option(condition: &str, option: &Opt) -> String {
+    let name = match option.name.strip_prefix("--") {
+        Some(long) => format!("-l {}", long),
+        None if option.name.len() == 2 => format!("-s {}", &option.name[1..]),
+        None => format!("-o {}", &option.name[1..]),
+    };
+    let value = match option.value {
+        Some(value) if value.starts_with('[') => String::new(),
+        Some(_) => match value_kind(option) {
+            Kind::Files => " -r -F".to_string(),
+            Kind::Nothing => " -x".to_string(),
+            kind => format!(" -x {}", fish_kind(&kind).trim_start_matches("-f ")),
+        },
+        None => String::new(),
+    };
+    format!("complete -c cargo -n {} {}{} -d {}", fish_quoted(condition), name, value, fish_quoted(option.help))
+}
+
+fn fish(commands: &[&Subcommand]) -> String {
+    let mut script = String::new();
+    let _ = writeln!(script, "# fish completion of \"cargo hfuzz\", from \"cargo hfuzz completions fish\"");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "# the hfuzz command being completed and the number of its arguments, the values of the options apart");
+    let _ = writeln!(script, "function __cargo_hfuzz_args");
+    let _ = writeln!(script, "    set -l words (commandline -opc)");
+    let _ = writeln!(script, "    test \"$words[2]\" = hfuzz; or return 1");
+    let _ = writeln!(script, "    set -l command ''");
+    let _ = writeln!(script, "    set -l position 0");
+    let _ = writeln!(script, "    set -l skip 0");
+    let _ = writeln!(script, "    for word in $words[3..-1]");
+    let _ = writeln!(script, "        if test $skip = 1");
+    let _ = writeln!(script, "            set skip 0");
+    let _ = writeln!(script, "        else if contains -- $word {}", value_options(commands).join(" "));
+    let _ = writeln!(script, "            set skip 1");
+    let _ = writeln!(script, "        else if string match -q -- '-*' $word");
+    let _ = writeln!(script, "        else if test -z \"$command\"");
+    let _ = writeln!(script, "            set command $word");
+    let _ = writeln!(script, "        else");
+    let _ = writeln!(script, "            set position (math $position + 1)");
+    let _ = writeln!(script, "        end");
+    let _ = writeln!(script, "    end");
+    let _ = writeln!(script, "    echo $command");
+    let _ = writeln!(script, "    echo $position");
+    let _ = writeln!(script, "end");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "# __cargo_hfuzz_at COMMAND [POSITION], POSITION+ meaning this one or a later one");
+    let _ = writeln!(script, "function __cargo_hfuzz_at");
+    let _ = writeln!(script, "    set -l args (__cargo_hfuzz_args); or return 1");
+    let _ = writeln!(script, "    test \"$args[1]\" = \"$argv[1]\"; or return 1");
+    let _ = writeln!(script, "    switch \"$argv[2]\"");
+    let _ = writeln!(script, "        case ''");
+    let _ = writeln!(script, "            return 0");
+    let _ = writeln!(script, "        case '*+'");
+    let _ = writeln!(script, "            test $args[2] -ge (string trim -r -c + -- $argv[2])");
+    let _ = writeln!(script, "        case '*'");
+    let _ = writeln!(script, "            test $args[2] -eq $argv[2]");
+    let _ = writeln!(script, "    end");
+    let _ = writeln!(script, "end");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "complete -c cargo -n '__cargo_hfuzz_at \"\"' -f");
+    for command in commands {
+        let _ = writeln!(script, "complete -c cargo -n '__cargo_hfuzz_at \"\"' -a {} -d {}", command.name, fish_quoted(command.about));
+    }
+    let _ = writeln!(script, "complete -c cargo -n '__cargo_hfuzz_at \"\"' -a help -d 'prints the help of a command'");
+    for option in GLOBAL_OPTIONS {
+        let _ = writeln!(script, "{}", fish_option("__cargo_hfuzz_at \"\"", option));
+    }
+    let _ = writeln!(script, "complete -c cargo -n '__cargo_hfuzz_at help 0' -f -a {}", fish_quoted(&command_names(commands).join(" ")));
+    for command in commands {
+        let _ = writeln!(script);
+        let condition = format!("__cargo_hfuzz_at {}", command.name);
+        for option in options(command) {
+            let _ = writeln!(script, "{}", fish_option(&condition, option));
+        }
+        let _ = writeln!(script, "complete -c cargo -n {} -s p -x -d {}", fish_quoted(&condition), fish_quoted(PACKAGE.help));
+        let (kinds, rest) = positionals(command);
+        for (i, kind) in kinds.iter().enumerate() {
+            let _ = writeln!(script, "complete -c cargo -n {} {}", fish_quoted(&format!("{} {}", condition, i)), fish_kind(kind));
+        }
+        let _ = writeln!(script, "complete -c cargo -n {} {}", fish_quoted(&format!("{} {}+", condition, kinds.len())), fish_kind(&rest));
+    }
+    script
+}
+
+pub fn hfuzz_completions<T>(args: T, commands: &[&Subcommand]) where T: std::iter::Iterator<Item=String> {
+    let matches = COMPLETIONS.parse(args);
+    let script = match matches.args.as_slice() {
+        [shell] if shell == "bash" => bash(commands),
+        [shell] if shell == "zsh" => zsh(commands),
+        [shell] if shell == "fish" => fish(commands),
+        [shell] => COMPLETIONS.error(&format!("unknown shell \"{}\", expected bash, zsh or fish", shell)),
+        _ => COMPLETIONS.error("please specify the shell: bash, zsh or fish"),
+    };
+    print!("{}", script);
+}
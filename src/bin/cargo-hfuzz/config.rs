@@ -0,0 +1,303 @@
+use std::fs;
+use std::io;
+use std::convert::TryFrom;
+use std::process;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::cli;
+use crate::debugger;
+use crate::sanitizer;
+use crate::json::Json;
+use crate::metadata;
+use crate::toml::{self, Entry};
+
+// read from the crate root, where "cargo hfuzz" runs from
+pub const CONFIG_FILE: &str = "hfuzz.toml";
+
+const KEYS: &[&str] = &[
+    "run.args", "run.timeout", "run.jobs", "run.dict", "run.max_len", "run.mutations", "run.base_files_only", "run.input", "run.runner", "run.seeds", "run.link_seeds", "run.post_process",
+    "build.args", "build.sanitizer", "build.target", "build.engine_version",
+    "workspace.crash_dir", "workspace.report_dir", "workspace.log_dir", "debug.debugger", "debug.init", "corpus.remote", "notify.webhook", "notify.plateau",
+    "debugger.NAME.program", "debugger.NAME.style", "debugger.NAME.init",
+];
+
+const TARGET_KEYS: &[&str] = &["args", "timeout", "dict", "max_len", "mutations", "seeds", "link_seeds"];
+
+// project settings, the environment variables and the command line options coming on top of them
+#[derive(Default)]
+pub struct Config {
+    // given to honggfuzz before HFUZZ_RUN_ARGS
+    pub run_args: Vec<String>,
+    pub timeout: Option<u64>,
+    pub jobs: Option<usize>,
+    pub dict: Option<PathBuf>,
+    pub max_len: Option<usize>,
+    // most mutations of an input per run, honggfuzz's `-r`
+    pub mutations: Option<u32>,
+    // runs the inputs as they are, `-r 0`, whatever the targets set
+    pub base_files_only: bool,
+    // the corpus then the other input directories, HFUZZ_INPUT takes precedence
+    pub inputs: Vec<PathBuf>,
+    // command running the engine, HFUZZ_RUNNER takes precedence
+    pub runner: Vec<String>,
+    // seed inputs of every target, added before the ones of the targets
+    pub seeds: Vec<PathBuf>,
+    // symlinks them in the corpus instead of copying them
+    pub link_seeds: bool,
+    // triages and minimizes the crashes after the sessions finding new ones
+    pub post_process: bool,
+    // given to cargo build before HFUZZ_BUILD_ARGS
+    pub build_args: Vec<String>,
+    pub sanitizer: Option<String>,
+    // triple to cross-compile for, `--target` takes precedence
+    pub target: Option<String>,
+    // release of honggfuzz to build, like "2.6", instead of the sources bundled with the library
+    pub engine_version: Option<String>,
+    // directories of the crashes, reports and logs of the targets, out of their workspaces, the
+    // environment variables taking precedence
+    pub crash_dir: Option<PathBuf>,
+    pub report_dir: Option<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+    // profile, or binary, used by run-debug when HFUZZ_DEBUGGER isn't set
+    pub debugger: Option<String>,
+    // commands run by every debugger before the ones of its profile
+    pub debug_init: Vec<String>,
+    // `[debugger.NAME]` tables
+    pub debuggers: Vec<(String, DebuggerConfig)>,
+    // where "cargo hfuzz corpus push/pull" share the corpora, HFUZZ_CORPUS_REMOTE takes precedence
+    pub corpus_remote: Option<String>,
+    // where the crashes, coverage plateaus and session ends are POSTed, HFUZZ_WEBHOOK takes precedence
+    pub webhook: Option<String>,
+    // seconds without new coverage making a plateau
+    pub plateau: Option<u64>,
+}
+
+// a debugger profile, overriding the built-in one of the same name if any
+#[derive(Default)]
+pub struct DebuggerConfig {
+    pub program: Option<String>,
+    pub style: Option<String>,
+    pub init: Vec<String>,
+}
+
+// settings of a fuzz target, from the `[package.metadata.hfuzz.targets.NAME]` table of its Cargo.toml,
+// they take precedence over hfuzz.toml
+#[derive(Default)]
+pub struct TargetConfig {
+    // given to honggfuzz after the ones of hfuzz.toml
+    pub run_args: Vec<String>,
+    pub timeout: Option<u64>,
+    pub dict: Option<PathBuf>,
+    pub max_len: Option<usize>,
+    pub mutations: Option<u32>,
+    // directories, files and patterns like `fixtures/*.bin` of inputs added to the corpus before fuzzing
+    pub seeds: Vec<PathBuf>,
+    pub link_seeds: Option<bool>,
+}
+
+fn entry_error(entry: &Entry, message: &str) -> ! {
+    eprintln!("error: {}:{}: {}", CONFIG_FILE, entry.line, message);
+    process::exit(1);
+}
+
+fn string(entry: &Entry) -> String {
+    entry.value.as_str().map(String::from).unwrap_or_else(|| entry_error(entry, &format!("\"{}\" expects a string", entry.key)))
+}
+
+fn strings(entry: &Entry) -> Vec<String> {
+    entry.value.as_strings().unwrap_or_else(|| entry_error(entry, &format!("\"{}\" expects an array of strings", entry.key)))
+}
+
+fn boolean(entry: &Entry) -> bool {
+    match entry.value {
+        toml::Value::Bool(b) => b,
+        _ => entry_error(entry, &format!("\"{}\" expects true or false", entry.key)),
+    }
+}
+
+fn number<T: TryFrom<i64>>(entry: &Entry) -> T {
+    entry.value.as_integer().and_then(|i| T::try_from(i).ok())
+        .unwrap_or_else(|| entry_error(entry, &format!("\"{}\" expects a positive integer", entry.key)))
+}
+
+fn parse(content: &str) -> Config {
+    let entries = toml::parse(content).unwrap_or_else(|error| {
+        eprintln!("error: invalid {}, {}", CONFIG_FILE, error);
+        process::exit(1);
+    });
+
+    let mut config = Config::default();
+    for entry in &entries {
+        let key = entry.table.iter().map(String::as_str).chain(Some(entry.key.as_str())).collect::<Vec<_>>().join(".");
+        match key.as_str() {
+            "run.args" => config.run_args = strings(entry),
+            "run.timeout" => config.timeout = Some(number(entry)),
+            "run.jobs" => config.jobs = Some(number(entry)),
+            "run.dict" => config.dict = Some(string(entry).into()),
+            "run.max_len" => config.max_len = Some(number(entry)),
+            "run.mutations" => match number(entry) {
+                0 => entry_error(entry, "\"run.mutations\" expects a positive integer, `base_files_only = true` runs the inputs without mutating them"),
+                mutations => config.mutations = Some(mutations),
+            },
+            "run.base_files_only" => config.base_files_only = boolean(entry),
+            // a directory, or a list of them
+            "run.input" => config.inputs = match entry.value.as_str() {
+                Some(input) => vec![input.into()],
+                None => strings(entry).into_iter().map(PathBuf::from).collect(),
+            },
+            "run.runner" => config.runner = strings(entry),
+            "run.seeds" => config.seeds = strings(entry).into_iter().map(PathBuf::from).collect(),
+            "run.link_seeds" => config.link_seeds = boolean(entry),
+            "run.post_process" => config.post_process = boolean(entry),
+            "build.target" => config.target = Some(string(entry)),
+            "build.args" => config.build_args = strings(entry),
+            "build.engine_version" => {
+                let version = string(entry);
+                // it ends up in the URL of the sources
+                if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                    entry_error(entry, &format!("invalid engine version \"{}\", expected a release of honggfuzz like \"2.6\"", version));
+                }
+                config.engine_version = Some(version);
+            }
+            "build.sanitizer" => {
+                let sanitizer = string(entry);
+                if let Err(error) = sanitizer::check(&sanitizer) {
+                    entry_error(entry, &error);
+                }
+                config.sanitizer = Some(sanitizer);
+            }
+            "workspace.crash_dir" => config.crash_dir = Some(string(entry).into()),
+            "workspace.report_dir" => config.report_dir = Some(string(entry).into()),
+            "workspace.log_dir" => config.log_dir = Some(string(entry).into()),
+            "debug.debugger" => config.debugger = Some(string(entry)),
+            "debug.init" => config.debug_init = strings(entry),
+            "corpus.remote" => config.corpus_remote = Some(string(entry)),
+            "notify.webhook" => config.webhook = Some(string(entry)),
+            "notify.plateau" => config.plateau = Some(number(entry)),
+            _ if entry.table.len() == 2 && entry.table[0] == "debugger" => {
+                let name = &entry.table[1];
+                let index = match config.debuggers.iter().position(|(n, _)| n == name) {
+                    Some(index) => index,
+                    None => {
+                        config.debuggers.push((name.clone(), DebuggerConfig::default()));
+                        config.debuggers.len() - 1
+                    }
+                };
+                let profile = &mut config.debuggers[index].1;
+                match entry.key.as_str() {
+                    "program" => profile.program = Some(string(entry)),
+                    "style" => {
+                        let style = string(entry);
+                        if debugger::Style::from_name(&style).is_none() {
+                            entry_error(entry, &format!("unknown debugger style \"{}\", expected one of {}", style, debugger::STYLES.join(", ")));
+                        }
+                        profile.style = Some(style);
+                    }
+                    "init" => profile.init = strings(entry),
+                    _ => entry_error(entry, &format!("unknown setting \"{}\" of a debugger, expected program, style or init", entry.key)),
+                }
+            }
+            _ => match cli::suggestion(&key, KEYS.iter().copied()) {
+                Some(known) => entry_error(entry, &format!("unknown setting \"{}\", did you mean \"{}\"?", key, known)),
+                None => entry_error(entry, &format!("unknown setting \"{}\"", key)),
+            },
+        }
+    }
+    config
+}
+
+// paths are relative to the Cargo.toml they are written in
+pub fn parse_target_config(settings: &Json, manifest_path: &Path, target: &str) -> TargetConfig {
+    let error = |key: &str, message: &str| -> ! {
+        eprintln!("error: {}: [package.metadata.hfuzz.targets.{}]: \"{}\" {}", manifest_path.display(), target, key, message);
+        process::exit(1);
+    };
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let path = |key: &str, value: &Json| manifest_dir.join(value.as_str().unwrap_or_else(|| error(key, "expects a path")));
+    let strings = |key: &str, value: &Json| -> Vec<String> {
+        match value {
+            Json::Array(values) => values.iter().map(|v| v.as_str().map(String::from).unwrap_or_else(|| error(key, "expects an array of strings"))).collect(),
+            _ => error(key, "expects an array of strings"),
+        }
+    };
+    let number = |key: &str, value: &Json| match value {
+        Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => *n as u64,
+        _ => error(key, "expects a positive integer"),
+    };
+    let boolean = |key: &str, value: &Json| match value {
+        Json::Bool(b) => *b,
+        _ => error(key, "expects true or false"),
+    };
+
+    let mut config = TargetConfig::default();
+    let fields = match settings {
+        Json::Object(fields) => fields.as_slice(),
+        _ => error("", "expects a table"),
+    };
+    for (key, value) in fields {
+        match key.as_str() {
+            "args" => config.run_args = strings(key, value),
+            "timeout" => config.timeout = Some(number(key, value)),
+            "dict" => config.dict = Some(path(key, value)),
+            "max_len" => config.max_len = Some(number(key, value) as usize),
+            "mutations" => match u32::try_from(number(key, value)) {
+                Ok(mutations) if mutations > 0 => config.mutations = Some(mutations),
+                _ => error(key, "expects a positive integer"),
+            },
+            "seeds" => config.seeds = strings(key, value).iter().map(|s| manifest_dir.join(s)).collect(),
+            "link_seeds" => config.link_seeds = Some(boolean(key, value)),
+            _ => match cli::suggestion(key, TARGET_KEYS.iter().copied()) {
+                Some(known) => error(key, &format!("is unknown, did you mean \"{}\"?", known)),
+                None => error(key, "is unknown"),
+            },
+        }
+    }
+    config
+}
+
+pub fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(|| match fs::read_to_string(CONFIG_FILE) {
+        Ok(content) => parse(&content),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Config::default(),
+        Err(error) => {
+            eprintln!("error: failed to read \"{}\" ({})", CONFIG_FILE, error);
+            process::exit(1);
+        }
+    })
+}
+
+pub fn target_config(target: &str) -> &'static TargetConfig {
+    static NONE: TargetConfig = TargetConfig { run_args: Vec::new(), timeout: None, dict: None, max_len: None, mutations: None, seeds: Vec::new(), link_seeds: None };
+    metadata::find_target(target).map_or(&NONE, |t| &t.config)
+}
+
+// the settings of a target, hfuzz.toml filling in what its Cargo.toml doesn't set
+pub fn timeout(target: &str) -> Option<u64> {
+    target_config(target).timeout.or(config().timeout)
+}
+
+pub fn max_len(target: &str) -> Option<usize> {
+    target_config(target).max_len.or(config().max_len)
+}
+
+// honggfuzz's `-r`, 0 with `base_files_only`
+pub fn mutations(target: &str) -> Option<u32> {
+    match config().base_files_only {
+        true => Some(0),
+        false => target_config(target).mutations.or(config().mutations),
+    }
+}
+
+pub fn dict(target: &str) -> Option<&'static Path> {
+    target_config(target).dict.as_deref().or(config().dict.as_deref())
+}
+
+pub fn seeds(target: &str) -> Vec<PathBuf> {
+    config().seeds.iter().chain(&target_config(target).seeds).cloned().collect()
+}
+
+pub fn link_seeds(target: &str) -> bool {
+    target_config(target).link_seeds.unwrap_or(config().link_seeds)
+}
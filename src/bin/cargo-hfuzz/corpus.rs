@@ -0,0 +1,158 @@
+use std::fs;
+use std::process;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::cli::Subcommand;
+use crate::remote;
+use crate::workspace::{self, content_hash};
+
+pub const CORPUS: Subcommand = Subcommand {
+    name: "corpus",
+    usage: "import-afl TARGET AFL_OUTPUT_DIR | export-afl TARGET DIR | push TARGET [REMOTE] | pull TARGET [REMOTE]",
+    about: "moves the corpus, crashes and hangs of a target from or to the AFL/AFL++ layout, or shares the corpus through S3, GCS, rsync or HTTP",
+    options: &[],
+    passthrough: None,
+};
+
+// prefixes of the crash files coming from AFL, honggfuzz names its own after the signal
+const AFL_CRASH_PREFIX: &str = "AFL";
+const AFL_HANG_PREFIX: &str = "AFL-HANG";
+
+// honggfuzz saves timeouts only with `-T`, as crashes on SIGVTALRM
+fn is_hang(crash_file: &Path) -> bool {
+    let name = crash_file.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with("SIGVTALRM.") || name.starts_with(&format!("{}.", AFL_HANG_PREFIX))
+}
+
+// the output directory of afl-fuzz holds one directory per instance (`default`, or the names given
+// to `-M`/`-S`), each with `queue`, `crashes` and `hangs`, old AFL versions put them directly in it
+fn afl_instances(dir: &Path) -> Vec<(String, PathBuf)> {
+    if dir.join("queue").is_dir() {
+        let name = dir.file_name().map_or("default".into(), |n| n.to_string_lossy().into_owned());
+        return vec![(name, dir.to_path_buf())];
+    }
+    let mut instances: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.join("queue").is_dir()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| (p.file_name().unwrap().to_string_lossy().into_owned(), p))
+        .collect();
+    instances.sort();
+    instances
+}
+
+// AFL names its files like `id:000042,sig:06,src:000012,time:1200,execs:4521,op:havoc,rep:4`
+fn afl_id(file: &Path) -> String {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    name.strip_prefix("id:")
+        .map_or(&*name, |id| id.split(',').next().unwrap_or(id))
+        .to_string()
+}
+
+// AFL leaves a README.txt in its crash directories
+fn afl_files(dir: &Path) -> Vec<PathBuf> {
+    workspace::files_in(dir).into_iter()
+        .filter(|f| f.file_name().is_some_and(|n| n != "README.txt"))
+        .collect()
+}
+
+fn create_dir(dir: &Path) {
+    fs::create_dir_all(dir).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", dir.display());
+        process::exit(1);
+    });
+}
+
+fn copy(from: &Path, to: &Path) {
+    fs::copy(from, to).unwrap_or_else(|_| {
+        eprintln!("error: failed to copy \"{}\" to \"{}\"", from.display(), to.display());
+        process::exit(1);
+    });
+}
+
+// copies the files not already known by their content, returns how many were copied
+pub fn import<F>(files: &[PathBuf], known: &mut HashSet<u64>, destination: F) -> usize where F: Fn(&Path) -> PathBuf {
+    let mut imported = 0;
+    for file in files {
+        let Some(hash) = content_hash(file) else { continue };
+        let to = destination(file);
+        if known.insert(hash) && !to.exists() {
+            copy(file, &to);
+            imported += 1;
+        }
+    }
+    imported
+}
+
+fn import_afl(target: &str, afl_dir: &Path) {
+    let instances = afl_instances(afl_dir);
+    if instances.is_empty() {
+        eprintln!("error: \"{}\" is not an afl-fuzz output directory, no \"queue\" directory found in it", afl_dir.display());
+        process::exit(1);
+    }
+
+    let input_dir = workspace::input_dir(target);
+    let crash_dir = workspace::crash_dir(target);
+    create_dir(&input_dir);
+    create_dir(&crash_dir);
+    let mut known_inputs: HashSet<u64> = workspace::corpus_files(target).iter().filter_map(|f| content_hash(f)).collect();
+    let mut known_crashes: HashSet<u64> = workspace::crash_files(target).iter().filter_map(|f| content_hash(f)).collect();
+
+    for (instance, dir) in &instances {
+        let inputs = import(&afl_files(&dir.join("queue")), &mut known_inputs, |f| {
+            input_dir.join(format!("afl-{}-{}", instance, afl_id(f)))
+        });
+        let crashes = import(&afl_files(&dir.join("crashes")), &mut known_crashes, |f| {
+            crash_dir.join(format!("{}.{}.{}.fuzz", AFL_CRASH_PREFIX, instance, afl_id(f)))
+        });
+        let hangs = import(&afl_files(&dir.join("hangs")), &mut known_crashes, |f| {
+            crash_dir.join(format!("{}.{}.{}.fuzz", AFL_HANG_PREFIX, instance, afl_id(f)))
+        });
+        println!("{}: imported {} inputs, {} crashes and {} hangs from the \"{}\" AFL instance", target, inputs, crashes, hangs, instance);
+    }
+}
+
+fn export_afl(target: &str, dir: &Path) {
+    let queue = dir.join("queue");
+    let crashes = dir.join("crashes");
+    let hangs = dir.join("hangs");
+    for dir in [&queue, &crashes, &hangs] {
+        create_dir(dir);
+    }
+
+    let export = |files: &[&PathBuf], to: &Path| {
+        // the ids go on after the files already there, which afl-fuzz expects to be unique
+        let first_id = afl_files(to).len();
+        for (i, file) in files.iter().enumerate() {
+            let name = file.file_name().unwrap().to_string_lossy();
+            copy(file, &to.join(format!("id:{:06},orig:{}", first_id + i, name)));
+        }
+        files.len()
+    };
+    let corpus = workspace::corpus_files(target);
+    let crash_files = workspace::crash_files(target);
+    let inputs = export(&corpus.iter().collect::<Vec<_>>(), &queue);
+    let exported_crashes = export(&crash_files.iter().filter(|f| !is_hang(f)).collect::<Vec<_>>(), &crashes);
+    let exported_hangs = export(&crash_files.iter().filter(|f| is_hang(f)).collect::<Vec<_>>(), &hangs);
+
+    println!("{}: exported {} inputs, {} crashes and {} hangs to \"{}\"", target, inputs, exported_crashes, exported_hangs, dir.display());
+    println!("fuzz them with \"afl-fuzz -i {} ...\"", queue.display());
+}
+
+pub fn hfuzz_corpus<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let mut args = CORPUS.parse(args).args.into_iter();
+    let (command, target, location) = match (args.next(), args.next(), args.next(), args.next()) {
+        (Some(command), Some(target), location, None) => (command, target, location),
+        _ => CORPUS.error("please specify what to do"),
+    };
+
+    match (command.as_str(), location) {
+        ("import-afl", Some(dir)) => import_afl(&target, Path::new(&dir)),
+        ("export-afl", Some(dir)) => export_afl(&target, Path::new(&dir)),
+        ("push", remote) => remote::push(&target, &remote::remote(remote)),
+        ("pull", remote) => remote::pull(&target, &remote::remote(remote)),
+        ("import-afl" | "export-afl", None) => CORPUS.error("please specify the directory"),
+        _ => CORPUS.error(&format!("unknown corpus command \"{}\"", command)),
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::process::{self, Command};
+use std::path::{Path, PathBuf};
+
+use crate::{BuildType, hfuzz_build, bin_args, coverage_binary, host_triple};
+use crate::cli::Subcommand;
+use crate::crash;
+use crate::log;
+use crate::metadata;
+use crate::workspace;
+
+pub const COVERAGE: Subcommand = Subcommand {
+    name: "coverage",
+    usage: "[TARGET ...]",
+    about: "replays the corpus of the targets with source-based coverage and prints a report",
+    options: &[],
+    passthrough: None,
+};
+
+// sources we don't want in the reports
+const IGNORE_FILENAME_REGEX: &str = "/.cargo/registry/|/.cargo/git/|/rustc/";
+
+// llvm tools are shipped by rustup with the `llvm-tools-preview` component
+fn llvm_tool(name: &str) -> PathBuf {
+    let sysroot = log::output(Command::new("rustc").args(["--print", "sysroot"]))
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default();
+    let bundled = Path::new(&sysroot).join("lib/rustlib").join(host_triple()).join("bin").join(name);
+    if bundled.is_file() {
+        bundled
+    } else {
+        name.into()
+    }
+}
+
+fn run_llvm_tool(mut command: Command) {
+    let tool = command.get_program().to_string_lossy().into_owned();
+    match log::status(&mut command) {
+        Ok(status) if status.success() => {}
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(_) => {
+            eprintln!("error: failed to execute \"{}\", try to execute \"rustup component add llvm-tools-preview\"", tool);
+            process::exit(1);
+        }
+    }
+}
+
+fn target_coverage(target: &str) {
+    log::select_target(target);
+    let binary = coverage_binary(target);
+    let coverage_dir = workspace::target_workspace(target).join("coverage");
+    let raw_dir = coverage_dir.join("raw");
+    let _ = fs::remove_dir_all(&raw_dir);
+    fs::create_dir_all(&raw_dir).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", raw_dir.display());
+        process::exit(1);
+    });
+
+    // replay the corpus and the crashes, each run writing its own raw profile
+    let inputs: Vec<PathBuf> = workspace::corpus_files(target).into_iter()
+        .chain(workspace::crash_files(target))
+        .collect();
+    if inputs.is_empty() {
+        eprintln!("warning: no corpus for \"{}\", skipping", target);
+        return;
+    }
+    log::note(&format!("replaying {} inputs of \"{}\"", inputs.len(), target));
+    for (i, input) in inputs.iter().enumerate() {
+        let mut command = Command::new(&binary);
+        command.env("LLVM_PROFILE_FILE", raw_dir.join(format!("{}.profraw", i)));
+        crash::replay_command(command, input, crash::DEFAULT_TIMEOUT);
+    }
+
+    let profraws = workspace::files_in(&raw_dir);
+    if profraws.is_empty() {
+        eprintln!("warning: no coverage data was produced for \"{}\"", target);
+        return;
+    }
+    let profdata = coverage_dir.join(format!("{}.profdata", target));
+    let mut merge = Command::new(llvm_tool("llvm-profdata"));
+    merge.args(["merge", "-sparse", "-o"]).arg(&profdata).args(&profraws);
+    run_llvm_tool(merge);
+
+    let html_dir = coverage_dir.join("html");
+    let mut show = Command::new(llvm_tool("llvm-cov"));
+    show.args(["show", "-format=html", "-ignore-filename-regex", IGNORE_FILENAME_REGEX])
+        .arg(format!("-instr-profile={}", profdata.display()))
+        .arg(format!("-output-dir={}", html_dir.display()))
+        .arg(&binary);
+    run_llvm_tool(show);
+
+    let mut report = Command::new(llvm_tool("llvm-cov"));
+    report.args(["report", "-ignore-filename-regex", IGNORE_FILENAME_REGEX])
+        .arg(format!("-instr-profile={}", profdata.display()))
+        .arg(&binary);
+    run_llvm_tool(report);
+
+    println!("coverage report of \"{}\": {}", target, html_dir.join("index.html").display());
+}
+
+pub fn hfuzz_coverage<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let mut targets = COVERAGE.parse(args).args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+
+    hfuzz_build(bin_args(&targets).into_iter(), crate_root, &BuildType::Coverage);
+
+    for target in &targets {
+        target_coverage(target);
+    }
+}
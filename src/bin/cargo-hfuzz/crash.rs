@@ -0,0 +1,200 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::log;
+
+// how long a single replay may take before being considered hung
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// frames belonging to the panic machinery or to honggfuzz itself say nothing about the bug
+const IGNORED_FRAME_PREFIXES: &[&str] = &[
+    "std::", "core::", "alloc::", "honggfuzz::", "<alloc::", "<core::", "<std::",
+    "rust_begin_unwind", "rust_panic", "__rust", "_rust", "__libc", "abort", "raise",
+    "__GI_", "__pthread", "pthread_kill", "gsignal",
+];
+
+// trait implementations from the standard library, e.g. `<usize as core::slice::index::SliceIndex<[T]>>::index`
+const IGNORED_FRAME_IMPLS: &[&str] = &[" as core::", " as alloc::", " as std::"];
+
+// number of meaningful frames used to compute the stack hash
+const HASHED_FRAMES: usize = 5;
+
+pub struct Frame {
+    pub function: String,
+    pub location: Option<String>,
+}
+
+impl Frame {
+    // `src/lib.rs:12:5` becomes the file relative to `root`, the line and the column, files outside of
+    // it like the ones of the registry being left out
+    pub fn source(&self, root: &Path) -> Option<(String, u64, u64)> {
+        let mut parts = self.location.as_deref()?.rsplitn(3, ':');
+        let (column, line, file) = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?);
+        let file = fs::canonicalize(file).ok()?;
+        let relative = file.strip_prefix(fs::canonicalize(root).ok()?).ok()?.to_string_lossy().into_owned();
+        Some((relative, line, column))
+    }
+}
+
+// what the sources of the frames are relative to for the CI and code scanning, the root of the
+// repository, or the crate root outside of git
+pub fn source_root() -> PathBuf {
+    let toplevel = log::output(Command::new("git").args(["rev-parse", "--show-toplevel"])).ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()));
+    toplevel.unwrap_or_else(|| env::current_dir().unwrap())
+}
+
+pub struct Outcome {
+    pub code: Option<i32>,
+    pub crashed: bool,
+    pub timed_out: bool,
+    pub panic_message: Option<String>,
+    pub frames: Vec<Frame>,
+}
+
+impl Outcome {
+    pub fn relevant_frames(&self) -> impl Iterator<Item=&Frame> {
+        self.frames.iter().filter(|f| {
+            !IGNORED_FRAME_PREFIXES.iter().any(|p| f.function.starts_with(p))
+                && !IGNORED_FRAME_IMPLS.iter().any(|i| f.function.contains(i))
+        })
+    }
+
+    // stable (FNV-1a) hash of the top of the stack (functions and source lines),
+    // so that identical bugs land in the same bucket
+    pub fn stack_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut feed = |s: &str| {
+            for b in s.bytes().chain(Some(0)) {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+
+        let mut frames = self.relevant_frames().take(HASHED_FRAMES).peekable();
+        if frames.peek().is_none() {
+            // no usable backtrace, fall back on what we know of the crash
+            feed(self.panic_message.as_deref().unwrap_or_default());
+            feed(&format!("{:?}", self.code));
+        }
+        for frame in frames {
+            feed(&frame.function);
+            // the column is left out as it depends on how the expression is formatted
+            let location = frame.location.as_deref().unwrap_or_default();
+            feed(location.rsplit_once(':').filter(|(_, column)| column.parse::<u32>().is_ok()).map_or(location, |(l, _)| l));
+        }
+        hash
+    }
+}
+
+// removes the `::h0123456789abcdef` suffix of legacy mangled symbols
+fn strip_symbol_hash(symbol: &str) -> &str {
+    match symbol.rfind("::h") {
+        Some(i) if symbol.len() - i == 19 && symbol[i + 3..].chars().all(|c| c.is_ascii_hexdigit()) => &symbol[..i],
+        _ => symbol,
+    }
+}
+
+fn parse_panic_message(stderr: &str) -> Option<String> {
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if !line.starts_with("thread '") {
+            continue;
+        }
+        let Some(index) = line.find(" panicked at ") else { continue };
+        let rest = &line[index + " panicked at ".len()..];
+        if let Some(message) = rest.strip_prefix('\'') {
+            // old format: thread 'main' panicked at 'message', src/main.rs:1:1
+            return Some(message.rsplit_once("', ").map_or(message, |(m, _)| m).to_string());
+        }
+        // new format: thread 'main' panicked at src/main.rs:1:1:\nmessage
+        let message: Vec<&str> = lines.by_ref()
+            .take_while(|l| !l.starts_with("stack backtrace:") && !l.starts_with("note: "))
+            .collect();
+        return Some(message.join("\n"));
+    }
+    None
+}
+
+fn parse_frames(stderr: &str) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = Vec::new();
+    for line in stderr.lines() {
+        let trimmed = line.trim_start();
+        if let Some(location) = trimmed.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                frame.location.get_or_insert_with(|| location.trim().to_string());
+            }
+            continue;
+        }
+        if let Some((index, symbol)) = trimmed.split_once(": ") {
+            if !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()) {
+                let symbol = symbol.split_once(" - ").map_or(symbol, |(_, s)| s); // full format has addresses
+                frames.push(Frame { function: strip_symbol_hash(symbol.trim()).to_string(), location: None });
+            }
+        }
+    }
+    frames
+}
+
+// runs a debug build of the target over a single input and analyses how it ended
+pub fn replay(binary: &Path, input: &Path, timeout: Duration) -> Outcome {
+    replay_command(Command::new(binary), input, timeout)
+}
+
+pub fn replay_command(mut command: Command, input: &Path, timeout: Duration) -> Outcome {
+    let binary = PathBuf::from(command.get_program());
+    let mut child = log::spawn(command
+        .env("CARGO_HONGGFUZZ_CRASH_FILENAME", input)
+        .env("RUST_BACKTRACE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped()))
+        .unwrap_or_else(|_| {
+            eprintln!("error: failed to execute \"{}\", try to execute \"cargo hfuzz build-debug\"", binary.display());
+            std::process::exit(1);
+        });
+
+    // drain stderr concurrently to not deadlock on a full pipe
+    let mut stderr = child.stderr.take().unwrap();
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stderr.read_to_end(&mut output);
+        String::from_utf8_lossy(&output).into_owned()
+    });
+
+    let start = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait().unwrap();
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    log::exited(&child, status);
+    let stderr = reader.join().unwrap_or_default();
+    // the watchdog of the harness (`set_iteration_timeout`, or the timeout of run-debug) aborts on a hang
+    let timed_out = timed_out || stderr.lines().any(|line| line.starts_with("error: timeout, "));
+
+    // the debug harness exits with 2 when the input did not trigger any panic
+    let code = status.code();
+    let crashed = !timed_out && !status.success() && code != Some(2);
+
+    Outcome {
+        code,
+        crashed,
+        timed_out,
+        panic_message: parse_panic_message(&stderr),
+        frames: parse_frames(&stderr),
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{self, IsTerminal};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::cli::{Opt, Subcommand};
+use crate::metadata;
+use crate::stats;
+use crate::workspace;
+
+pub const DASHBOARD: Subcommand = Subcommand {
+    name: "dashboard",
+    usage: "[--interval SECS] [--once] [TARGET ...]",
+    about: "shows how the fuzzing of the targets goes, all their honggfuzz instances together, refreshed until interrupted",
+    options: &[
+        Opt { name: "--interval", value: Some("SECS"), help: "refreshes every SECS seconds, 2 by default" },
+        Opt { name: "--once", value: None, help: "prints the table once and exits" },
+    ],
+    passthrough: None,
+};
+
+struct Row {
+    target: String,
+    running: usize,
+    instances: usize,
+    // only known from the summaries honggfuzz logs when its instances stop
+    exec_per_sec: Option<u64>,
+    total_exec: Option<u64>,
+    edge_cov: u64,
+    last_cov_update: u64,
+    crash_files: usize,
+    corpus: usize,
+}
+
+fn row(target: &str) -> Row {
+    let logs: Vec<stats::Stats> = stats::log_files(target).iter().filter_map(|f| stats::read_log(target, f)).collect();
+    // an instance fuzzes until it logs its summary
+    let finished: Vec<&stats::Stats> = logs.iter().filter(|s| s.finished).collect();
+    let summed = |value: fn(&stats::Stats) -> u64| Some(finished.iter().map(|s| value(s)).sum()).filter(|_| !finished.is_empty());
+    Row {
+        target: target.to_string(),
+        running: logs.len() - finished.len(),
+        instances: logs.len(),
+        exec_per_sec: summed(|s| s.exec_per_sec),
+        total_exec: summed(|s| s.total_exec),
+        // the instances share the corpus, each one knows about the coverage the others found
+        edge_cov: logs.iter().map(|s| s.edge_cov).max().unwrap_or(0),
+        last_cov_update: logs.iter().map(|s| s.last_cov_update).max().unwrap_or(0),
+        crash_files: workspace::crash_files(target).len(),
+        corpus: workspace::corpus_files(target).len(),
+    }
+}
+
+// like "3m" or "2h", for how long ago things happened
+fn ago(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+fn draw(rows: &[Row], corpus_at_start: &[usize], started: Instant, now: u64) {
+    let width = rows.iter().map(|r| r.target.len()).chain(Some("TARGET".len())).max().unwrap_or(0);
+    println!("{:width$}  {:>9}  {:>10}  {:>14}  {:>8}  {:>8}  {:>8}  {:>13}  {:>7}",
+        "TARGET", "INSTANCES", "EXECS/SEC", "EXECS", "EDGES", "NEW PATH", "CRASHES", "CORPUS", "GROWTH", width = width);
+    let minutes = (started.elapsed().as_secs() as f64 / 60.0).max(1.0);
+    let known = |value: Option<u64>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    for (row, before) in rows.iter().zip(corpus_at_start) {
+        let new_path = match row.last_cov_update {
+            0 => "-".to_string(),
+            t => format!("{} ago", ago(now.saturating_sub(t))),
+        };
+        let grown = row.corpus.saturating_sub(*before);
+        println!("{:width$}  {:>9}  {:>10}  {:>14}  {:>8}  {:>8}  {:>8}  {:>13}  {:>7}",
+            row.target, format!("{}/{}", row.running, row.instances), known(row.exec_per_sec), known(row.total_exec), row.edge_cov,
+            new_path, row.crash_files, format!("{} (+{})", row.corpus, grown), format!("{:.1}/m", grown as f64 / minutes),
+            width = width);
+    }
+    let running: usize = rows.iter().map(|r| r.running).sum();
+    println!("{} honggfuzz instances running", running);
+}
+
+pub fn hfuzz_dashboard<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = DASHBOARD.parse(args);
+    let interval: u64 = matches.parsed(&DASHBOARD, "--interval").unwrap_or(2);
+    let once = matches.flag("--once");
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+    if targets.is_empty() {
+        DASHBOARD.error("no fuzz target in the workspace");
+    }
+
+    // the corpus growth is counted from when the dashboard started
+    let started = Instant::now();
+    let corpus_at_start: Vec<usize> = targets.iter().map(|t| workspace::corpus_files(t).len()).collect();
+    let redraw = !once && io::stdout().is_terminal();
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let rows: Vec<Row> = targets.iter().map(|t| row(t)).collect();
+        if redraw {
+            print!("\x1b[H\x1b[2J"); // redraws in place
+        }
+        draw(&rows, &corpus_at_start, started, now);
+        if once {
+            return;
+        }
+        thread::sleep(Duration::from_secs(interval.max(1)));
+    }
+}
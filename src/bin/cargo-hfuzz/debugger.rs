@@ -0,0 +1,149 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{self, Command};
+
+use crate::config;
+
+// how a debugger takes the commands to run at startup and the program to debug
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+    Lldb,
+    Gdb,
+    // cgdb gives whatever follows `--` to gdb
+    Cgdb,
+    Windbg,
+}
+
+pub const STYLES: &[&str] = &["lldb", "gdb", "cgdb", "windbg"];
+
+impl Style {
+    pub fn from_name(name: &str) -> Option<Style> {
+        match name {
+            "lldb" => Some(Style::Lldb),
+            "gdb" => Some(Style::Gdb),
+            "cgdb" => Some(Style::Cgdb),
+            "windbg" => Some(Style::Windbg),
+            _ => None,
+        }
+    }
+
+    // stopping on panics, and printing the backtrace once there
+    fn commands(self) -> &'static [&'static str] {
+        match self {
+            Style::Lldb | Style::Gdb | Style::Cgdb => &["b rust_panic", "r", "bt"],
+            Style::Windbg => &["bp rust_panic", "g", "k"],
+        }
+    }
+}
+
+pub struct Profile {
+    pub program: String,
+    pub style: Style,
+    // run before breaking on panics, like loading pretty printers or setting breakpoints
+    pub init: Vec<String>,
+}
+
+// profiles known without configuration, hfuzz.toml can override them or add others
+fn builtin(name: &str) -> Option<Profile> {
+    let (program, style) = match name {
+        "lldb" => ("lldb", Style::Lldb),
+        "gdb" => ("gdb", Style::Gdb),
+        "cgdb" => ("cgdb", Style::Cgdb),
+        // from WSL, `windbgx.exe` being on the Windows side
+        "windbg" => ("windbgx.exe", Style::Windbg),
+        _ => return None,
+    };
+    Some(Profile { program: program.into(), style, init: Vec::new() })
+}
+
+// HFUZZ_DEBUGGER and the `debug.debugger` setting name a profile or a debugger binary, whose style is
+// guessed from its name, rust-lldb by default
+pub fn profile() -> Profile {
+    let config = config::config();
+    let debugger = env::var("HFUZZ_DEBUGGER").ok()
+        .or_else(|| config.debugger.clone())
+        .unwrap_or_else(|| "rust-lldb".into());
+
+    let mut profile = match config.debuggers.iter().find(|(name, _)| *name == debugger) {
+        Some((name, settings)) => {
+            let builtin = builtin(name);
+            let style = settings.style.as_deref().and_then(Style::from_name)
+                .or(builtin.as_ref().map(|p| p.style))
+                .unwrap_or_else(|| guess_style(settings.program.as_deref().unwrap_or(name)));
+            Profile {
+                program: settings.program.clone().or(builtin.map(|p| p.program)).unwrap_or_else(|| name.clone()),
+                style,
+                init: settings.init.clone(),
+            }
+        }
+        None => builtin(&debugger).unwrap_or_else(|| Profile { style: guess_style(&debugger), program: debugger, init: Vec::new() }),
+    };
+    profile.init.splice(0..0, config.debug_init.iter().cloned());
+    profile
+}
+
+fn guess_style(program: &str) -> Style {
+    let name = Path::new(program).file_name().map(|f| f.to_string_lossy().into_owned()).unwrap_or_default();
+    if name.contains("lldb") {
+        Style::Lldb
+    } else if name.contains("cgdb") {
+        Style::Cgdb
+    } else if name.contains("windbg") {
+        Style::Windbg
+    } else {
+        Style::Gdb
+    }
+}
+
+// the debugger running `binary`, the arguments added to the command are given to the binary
+pub fn command(profile: &Profile, binary: &Path) -> Command {
+    let commands: Vec<&str> = profile.init.iter().map(String::as_str).chain(profile.style.commands().iter().copied()).collect();
+
+    let mut cmd = Command::new(&profile.program);
+    match profile.style {
+        Style::Lldb => {
+            for c in commands {
+                cmd.args(["-o", c]);
+            }
+            cmd.arg("-f").arg(binary).arg("--");
+        }
+        Style::Gdb | Style::Cgdb => {
+            if profile.style == Style::Cgdb {
+                cmd.arg("--");
+            }
+            for c in commands {
+                cmd.args(["-ex", c]);
+            }
+            cmd.arg("--args").arg(binary);
+        }
+        Style::Windbg => {
+            cmd.args(["-c", &commands.join("; ")]).arg(binary);
+        }
+    }
+    cmd
+}
+
+// records the execution of `binary` with rr in `trace_dir`, the arguments added to the command are given
+// to the binary
+pub fn rr_record(trace_dir: &Path, binary: &Path) -> Command {
+    // rr refuses to overwrite a trace
+    if trace_dir.exists() && fs::remove_dir_all(trace_dir).is_err() {
+        eprintln!("error: failed to remove the previous rr trace \"{}\"", trace_dir.display());
+        process::exit(1);
+    }
+    if let Some(parent) = trace_dir.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let mut cmd = Command::new("rr");
+    cmd.arg("record").arg("-o").arg(trace_dir).arg(binary);
+    cmd
+}
+
+// replays a trace in gdb, stopped on the panic so that `reverse-continue` and friends go back to its cause
+pub fn rr_replay(trace_dir: &Path) -> Command {
+    let mut cmd = Command::new("rr");
+    cmd.arg("replay").arg(trace_dir)
+        .args(["--", "-ex", "b rust_panic", "-ex", "c", "-ex", "bt"]);
+    cmd
+}
@@ -0,0 +1,177 @@
+use std::ffi::OsString;
+use std::fs;
+use std::process::{self, Command};
+use std::path::{Path, PathBuf};
+
+use crate::log;
+use crate::workspace;
+
+// the marker of the tokens of `fuzz_dictionary!` in the binaries, the `honggfuzz::dictionary::MAGIC`
+// of the library
+const EMBEDDED_MAGIC: &[u8] = b"\xffhfuzz-dictionary\xff";
+
+// the dictionary honggfuzz gets, in the workspace of the target
+const EMBEDDED_DICT: &str = "embedded.dict";
+
+// checks a token of a dictionary line, the part between the double quotes
+fn check_token(token: &str) -> Result<(), String> {
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err("unescaped '\"' in the token".into()),
+            '\\' => match chars.next() {
+                Some('\\') | Some('"') => {}
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    if hex.len() != 2 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(format!("invalid escape \"\\x{}\", expected 2 hexadecimal digits", hex));
+                    }
+                }
+                Some(other) => return Err(format!("invalid escape \"\\{}\"", other)),
+                None => return Err("escape at the end of the token".into()),
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// checks a line of a dictionary, like `"token"` or `name="token"` (or `name@1="token"` for AFL)
+fn check_line(line: &str) -> Result<(), String> {
+    let start = line.find('"').ok_or("expected a token between double quotes")?;
+    let name = line[..start].trim_end();
+    let name = name.strip_suffix('=').unwrap_or(name).trim_end();
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@') {
+        return Err(format!("invalid token name \"{}\"", name));
+    }
+    let token = line[start + 1..].strip_suffix('"').ok_or("expected the token to end with a double quote")?;
+    check_token(token)
+}
+
+// dictionaries use the libFuzzer/AFL format honggfuzz reads with `-w`, returns the number of tokens
+pub fn validate(path: &Path) -> Result<usize, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("failed to read the dictionary \"{}\" ({})", path.display(), e))?;
+    let mut tokens = 0;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        check_line(line).map_err(|e| format!("invalid dictionary line {}:{}: {}", path.display(), i + 1, e))?;
+        tokens += 1;
+    }
+    if tokens == 0 {
+        return Err(format!("the dictionary \"{}\" doesn't contain any token", path.display()));
+    }
+    Ok(tokens)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<usize> {
+    let b = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+// the tokens following a marker, none if they don't fit in the binary
+fn parse_tokens(bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let count = read_u32(bytes, 0)?;
+    let mut at = 4;
+    let mut tokens = Vec::new();
+    for _ in 0..count {
+        let len = read_u32(bytes, at)?;
+        tokens.push(bytes.get(at + 4..at + 4 + len)?.to_vec());
+        at += 4 + len;
+    }
+    Some(tokens)
+}
+
+// the tokens of every `fuzz_dictionary!` of a binary, in their order, without the repeated ones
+pub fn embedded_tokens(binary: &Path) -> Vec<Vec<u8>> {
+    let Ok(content) = fs::read(binary) else { return Vec::new() };
+    let mut tokens: Vec<Vec<u8>> = Vec::new();
+    let mut at = 0;
+    while let Some(offset) = content[at..].windows(EMBEDDED_MAGIC.len()).position(|w| w == EMBEDDED_MAGIC) {
+        at += offset + EMBEDDED_MAGIC.len();
+        for token in parse_tokens(&content[at..]).unwrap_or_default() {
+            if !token.is_empty() && !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+// a token as a dictionary line, what isn't printable being escaped
+fn token_line(token: &[u8]) -> String {
+    let escaped: String = token.iter().map(|&b| match b {
+        b'"' | b'\\' => format!("\\{}", b as char),
+        b' '..=b'~' => (b as char).to_string(),
+        _ => format!("\\x{:02x}", b),
+    }).collect();
+    format!("\"{}\"\n", escaped)
+}
+
+// gives honggfuzz the tokens embedded in the target with `fuzz_dictionary!`, honggfuzz reading a
+// single dictionary: the one of `-w` (or `--dict`) if any is copied along with them
+pub fn with_embedded_tokens(target: &str, binary: &Path, command: Command) -> Command {
+    // nothing is written with `--dry-run`
+    let tokens = match log::dry_run() {
+        true => Vec::new(),
+        false => embedded_tokens(binary),
+    };
+    if tokens.is_empty() {
+        return command;
+    }
+    let mut args: Vec<OsString> = command.get_args().map(OsString::from).collect();
+    let separator = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let mut honggfuzz_args = Vec::new();
+    let mut dict: Option<PathBuf> = None;
+    let mut i = 0;
+    while i < separator {
+        let arg = args[i].to_string_lossy();
+        if let Some(path) = arg.strip_prefix("--dict=") {
+            dict = Some(path.into());
+        } else if (arg == "-w" || arg == "--dict") && i + 1 < separator {
+            dict = Some(PathBuf::from(&args[i + 1]));
+            i += 1;
+        } else {
+            honggfuzz_args.push(args[i].clone());
+        }
+        i += 1;
+    }
+
+    let mut content = String::new();
+    if let Some(dict) = &dict {
+        content = fs::read_to_string(dict).unwrap_or_else(|_| {
+            eprintln!("error: failed to read the dictionary \"{}\"", dict.display());
+            process::exit(1);
+        });
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+    }
+    content.push_str(&format!("# the fuzz_dictionary! tokens of \"{}\"\n", target));
+    content.extend(tokens.iter().map(|t| token_line(t)));
+    let path = workspace::target_workspace(target).join(EMBEDDED_DICT);
+    if let Err(error) = fs::create_dir_all(workspace::target_workspace(target)).and_then(|_| fs::write(&path, content)) {
+        eprintln!("error: failed to write \"{}\" ({})", path.display(), error);
+        process::exit(1);
+    }
+    log::note(&format!("using the {} tokens embedded in \"{}\"", tokens.len(), target));
+
+    let mut embedded = Command::new(command.get_program());
+    honggfuzz_args.extend(["-w".into(), path.into_os_string()]);
+    honggfuzz_args.extend(args.drain(separator..));
+    embedded.args(honggfuzz_args)
+        .envs(command.get_envs().filter_map(|(key, value)| Some((key, value?))));
+    embedded
+}
+
+pub fn check_dict(path: &Path) {
+    match validate(path) {
+        Ok(tokens) => log::note(&format!("using {} tokens of the dictionary \"{}\"", tokens, path.display())),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use crate::{llvm_major, rustc_version, sancov_rustflags};
+use crate::cli::Subcommand;
+
+pub const DOCTOR: Subcommand = Subcommand {
+    name: "doctor",
+    usage: "",
+    about: "checks that the system can build and run honggfuzz, telling how to fix what it can't",
+    options: &[],
+    passthrough: None,
+};
+
+#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd", target_os = "netbsd")))]
+const GNU_MAKE: &str = "make";
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd", target_os = "netbsd"))]
+const GNU_MAKE: &str = "gmake";
+
+// the libraries the engine links on Linux, built without them when they aren't found: its header, its linker
+// flags, its Debian package and the feature of the library building the engine without it
+const LIBRARIES: &[(&str, &str, &str, &str, Option<&str>)] = &[
+    ("BFD", "bfd.h", "-lbfd -lopcodes", "binutils-dev", Some("engine-bfd")),
+    ("libunwind", "libunwind-ptrace.h", "-lunwind-ptrace -lunwind-generic", "libunwind-dev", None),
+    ("liblzma", "lzma.h", "-llzma", "liblzma-dev", None),
+];
+
+#[derive(Default)]
+struct Report {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn ok(&mut self, message: &str) {
+        println!("ok:      {}", message);
+    }
+
+    fn warning(&mut self, message: &str, fix: &str) {
+        println!("warning: {}\n         fix: {}", message, fix);
+        self.warnings += 1;
+    }
+
+    fn error(&mut self, message: &str, fix: &str) {
+        println!("error:   {}\n         fix: {}", message, fix);
+        self.errors += 1;
+    }
+}
+
+// the first line of `PROGRAM --version`, when it runs
+fn version(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("--version").stderr(Stdio::null()).output().ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or_default().trim().to_string())
+}
+
+// like 17 for "clang version 17.0.6 (...)" or "Apple clang version 15.0.0 (...)"
+fn clang_major(version: &str) -> Option<u32> {
+    let version = version.split("clang version ").nth(1)?;
+    version.split('.').next()?.parse().ok()
+}
+
+fn scratch_dir() -> PathBuf {
+    let dir = env::temp_dir().join(format!("cargo-hfuzz-doctor-{}", process::id()));
+    let _ = fs::create_dir_all(&dir);
+    dir
+}
+
+// builds `source` read from stdin, returning what the compiler printed when it failed
+fn compiles(command: &mut Command, source: &str) -> Result<(), String> {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn().map_err(|e| e.to_string())?;
+    let _ = child.stdin.take().unwrap().write_all(source.as_bytes());
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(String::from_utf8_lossy(&output.stderr).lines().find(|l| l.contains("error")).unwrap_or_default().trim().to_string()),
+    }
+}
+
+fn check_toolchain(report: &mut Report, cc: &str) {
+    match version(cc) {
+        Some(version) => report.ok(&format!("C compiler \"{}\": {}", cc, version)),
+        None => report.error(&format!("C compiler \"{}\" not found, the build script compiles honggfuzz with it", cc),
+            "install one, on Debian and its derivatives with \"sudo apt install build-essential\", or point CC to it"),
+    }
+    match version(GNU_MAKE).filter(|v| v.contains("GNU Make")) {
+        Some(version) => report.ok(&format!("\"{}\": {}", GNU_MAKE, version)),
+        None => report.error(&format!("GNU Make not found as \"{}\", the build script builds honggfuzz with it", GNU_MAKE),
+            &format!("install it, on Debian and its derivatives with \"sudo apt install make\", as \"{}\"", GNU_MAKE)),
+    }
+}
+
+fn check_libraries(report: &mut Report, cc: &str, dir: &Path) {
+    for (name, header, flags, package, feature) in LIBRARIES {
+        // bfd.h refuses to be included outside of a configured build
+        let source = format!("#define PACKAGE\n#define PACKAGE_VERSION\n#include <{}>\nint main(void) {{ return 0; }}\n", header);
+        let mut command = Command::new(cc);
+        command.args(["-x", "c", "-o"]).arg(dir.join("library")).arg("-").args(flags.split(' '));
+        match compiles(&mut command, &source) {
+            Ok(()) => report.ok(&format!("{} development files ({}, {})", name, header, flags)),
+            Err(error) => {
+                let without = feature.map(|f| format!(", or disable the \"{}\" feature of honggfuzz not to use {}", f, name));
+                report.warning(&format!("{} development files not found ({}), the engine is built without the parts using them", name, error),
+                    &format!("install them, on Debian and its derivatives with \"sudo apt install {}\", then run \"cargo hfuzz clean\"{}", package, without.unwrap_or_default()))
+            }
+        }
+    }
+}
+
+fn check_llvm(report: &mut Report, cc: &str, dir: &Path) {
+    let release = rustc_version().lines().find_map(|l| l.strip_prefix("release: ")).unwrap_or("unknown").to_string();
+    let rustc_llvm = llvm_major();
+    match rustc_llvm {
+        Some(major) => report.ok(&format!("rustc {}, with LLVM {}", release, major)),
+        None => report.warning(&format!("rustc {} doesn't tell its LLVM version", release),
+            "check that \"rustc -vV\" runs the toolchain used to build the targets"),
+    }
+
+    // the instrumentation of "cargo hfuzz build", tried on an empty crate
+    let flags = sancov_rustflags();
+    let mut command = Command::new("rustc");
+    command.args(["--crate-type", "lib", "--crate-name", "doctor", "--emit", "obj", "-o"]).arg(dir.join("doctor.o"))
+        .args(flags.split_whitespace()).arg("-");
+    match compiles(&mut command, "pub fn doctor() {}\n") {
+        Ok(()) => report.ok(&format!("rustc instruments the targets with \"{}\"", flags.split_whitespace().collect::<Vec<_>>().join(" "))),
+        Err(error) => report.error(&format!("rustc fails to instrument the targets ({})", error),
+            "update the toolchain with \"rustup update\" and cargo-hfuzz with \"cargo install honggfuzz\""),
+    }
+
+    // clang builds honggfuzz when it's the C compiler, and the sanitizer runtimes have to match rustc's LLVM
+    let clang = if version(cc).is_some_and(|v| v.contains("clang")) { cc } else { "clang" };
+    match (version(clang), rustc_llvm) {
+        (None, _) => report.ok("clang not found, not needed unless building honggfuzz or the sanitizer runtimes with it"),
+        (Some(version), Some(rustc)) => match clang_major(&version) {
+            Some(major) if major != rustc => report.warning(
+                &format!("\"{}\" is clang {} while rustc uses LLVM {}, mixing their instrumentation may fail to link", clang, major, rustc),
+                &format!("install clang {} (like \"sudo apt install clang-{}\" on Debian) and point CC to it", rustc, rustc)),
+            _ => report.ok(&format!("\"{}\": {}", clang, version)),
+        },
+        (Some(version), None) => report.ok(&format!("\"{}\": {}", clang, version)),
+    }
+    if clang == cc {
+        let mut command = Command::new(cc);
+        command.args(["-x", "c", "-o"]).arg(dir.join("blocks")).args(["-", "-lBlocksRuntime"]);
+        if let Err(error) = compiles(&mut command, "int main(void) { return 0; }\n") {
+            report.error(&format!("the Blocks runtime clang builds honggfuzz with isn't found ({})", error),
+                "install it, on Debian and its derivatives with \"sudo apt install libblocksruntime-dev\"");
+        }
+    }
+}
+
+// the `Uid:` and `Seccomp:` lines of /proc/self/status
+fn status_field(field: &str) -> Option<String> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status.lines().find_map(|l| l.strip_prefix(field)).map(|v| v.trim().to_string())
+}
+
+// honggfuzz traces the targets it runs with ptrace, to analyze the crashes
+fn check_ptrace(report: &mut Report) {
+    let root = status_field("Uid:").is_some_and(|uid| uid.split_whitespace().nth(1) == Some("0"));
+    match fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope").map(|s| s.trim().to_string()) {
+        Err(_) => report.ok("ptrace isn't restricted by Yama"),
+        Ok(scope) if scope == "0" || scope == "1" => report.ok(&format!("Yama allows ptrace on child processes (ptrace_scope {})", scope)),
+        Ok(scope) if scope == "2" && root => report.ok("Yama allows ptrace to root only (ptrace_scope 2), which cargo-hfuzz runs as"),
+        Ok(scope) => report.error(&format!("Yama forbids honggfuzz to ptrace its targets (ptrace_scope {})", scope),
+            match scope.as_str() {
+                "2" => "run as root, or allow it with \"echo 1 | sudo tee /proc/sys/kernel/yama/ptrace_scope\"",
+                _ => "ptrace_scope 3 can only be lowered by rebooting, with \"kernel.yama.ptrace_scope = 1\" in /etc/sysctl.d",
+            }),
+    }
+    // seccomp filters of containers, like the ones of older versions of Docker, deny ptrace
+    if status_field("Seccomp:").is_some_and(|mode| mode == "2") {
+        report.warning("a seccomp filter applies to the processes, it may deny ptrace to honggfuzz",
+            "in Docker, run the container with \"--cap-add=SYS_PTRACE\" or \"--security-opt seccomp=unconfined\"");
+    }
+}
+
+fn check_core_pattern(report: &mut Report) {
+    let pattern = match fs::read_to_string("/proc/sys/kernel/core_pattern") {
+        Ok(pattern) => pattern.trim().to_string(),
+        Err(_) => return,
+    };
+    // the kernel starts the program for every crash, whatever the core size limit
+    match pattern.strip_prefix('|') {
+        Some(program) => report.warning(
+            &format!("crashes are piped to \"{}\", which slows down fuzzing for every crash", program.split_whitespace().next().unwrap_or_default()),
+            "have the kernel write the core files itself with \"echo core | sudo tee /proc/sys/kernel/core_pattern\""),
+        None => report.ok(&format!("core files are written by the kernel (core_pattern \"{}\")", pattern)),
+    }
+}
+
+pub fn hfuzz_doctor<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    if let Some(arg) = DOCTOR.parse(args).args.first() {
+        DOCTOR.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    let cc = env::var("CC").ok().filter(|c| !c.is_empty()).unwrap_or_else(|| "cc".to_string());
+    let dir = scratch_dir();
+    let mut report = Report::default();
+
+    check_toolchain(&mut report, &cc);
+    // the engine only needs these libraries on Linux
+    if cfg!(target_os = "linux") {
+        check_libraries(&mut report, &cc, &dir);
+    }
+    check_llvm(&mut report, &cc, &dir);
+    if cfg!(target_os = "linux") {
+        check_ptrace(&mut report);
+        check_core_pattern(&mut report);
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    println!("{} errors, {} warnings", report.errors, report.warnings);
+    if report.errors > 0 {
+        process::exit(1);
+    }
+}
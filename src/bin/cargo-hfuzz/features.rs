@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::cli::{self, Matches, Opt, Subcommand};
+use crate::json::Json;
+use crate::metadata;
+use crate::resume;
+
+// accepted by the commands building the targets, given to cargo build
+pub const FEATURES: Opt = Opt { name: "--features", value: Some("LIST"), help: "builds with these cargo features, separated by commas or spaces, repeatable" };
+pub const ALL_FEATURES: Opt = Opt { name: "--all-features", value: None, help: "builds with all the features of the package" };
+pub const NO_DEFAULT_FEATURES: Opt = Opt { name: "--no-default-features", value: None, help: "builds without the default features of the package" };
+
+// the cargo features a target is built with, recorded with the sessions so that the crashes are replayed
+// with the ones they were found with
+#[derive(Default, PartialEq)]
+pub struct Features {
+    // sorted, the order of `--features` changing nothing
+    pub list: Vec<String>,
+    pub all: bool,
+    pub no_default: bool,
+}
+
+impl Features {
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.list.is_empty() {
+            args.extend(["--features".to_string(), self.list.join(",")]);
+        }
+        if self.all {
+            args.push("--all-features".to_string());
+        }
+        if self.no_default {
+            args.push("--no-default-features".to_string());
+        }
+        args
+    }
+
+    // like `--features a,b --no-default-features`
+    pub fn describe(&self) -> String {
+        match self.cargo_args().is_empty() {
+            true => "the default features".to_string(),
+            false => format!("\"{}\"", self.cargo_args().join(" ")),
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("features", self.list.clone().into()),
+            ("all_features", self.all.into()),
+            ("no_default_features", self.no_default.into()),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Features {
+        Features {
+            list: json.get("features").map(Json::as_array).unwrap_or_default().iter().filter_map(Json::as_str).map(String::from).collect(),
+            all: matches!(json.get("all_features"), Some(Json::Bool(true))),
+            no_default: matches!(json.get("no_default_features"), Some(Json::Bool(true))),
+        }
+    }
+}
+
+static SELECTED: OnceLock<Features> = OnceLock::new();
+
+// a feature of another package, like `serde/std`, is left to cargo, the ones of the packages of the fuzz
+// targets are checked against their manifests
+fn check(command: &Subcommand, feature: &str) {
+    if feature.contains('/') {
+        return;
+    }
+    let packages: Vec<&metadata::Package> = metadata::metadata().fuzz_packages()
+        .filter(|p| metadata::selected_package().is_none_or(|selected| p.name == selected))
+        .collect();
+    if packages.iter().any(|p| p.features.iter().any(|f| f == feature)) {
+        return;
+    }
+    let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+    let known = packages.iter().flat_map(|p| p.features.iter().map(String::as_str));
+    match cli::suggestion(feature, known) {
+        Some(known) => command.error(&format!("unknown feature \"{}\" of {}, did you mean \"{}\"?", feature, names.join(", "), known)),
+        None => command.error(&format!("unknown feature \"{}\" of {}", feature, names.join(", "))),
+    }
+}
+
+pub fn select(command: &Subcommand, matches: &Matches) {
+    let mut list: Vec<String> = matches.values("--features").iter()
+        .flat_map(|v| v.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|f| !f.is_empty())
+        .map(String::from)
+        .collect();
+    list.sort();
+    list.dedup();
+    for feature in &list {
+        check(command, feature);
+    }
+    let _ = SELECTED.set(Features { list, all: matches.flag("--all-features"), no_default: matches.flag("--no-default-features") });
+}
+
+pub fn selected() -> &'static Features {
+    SELECTED.get_or_init(Features::default)
+}
+
+// warns when crash files of `target` were found by sessions fuzzing other features than the ones it is
+// built with now, a common reason for a crash not to reproduce
+pub fn check_replay(target: &str, crash_files: &[PathBuf]) {
+    let mut warned: Vec<Features> = Vec::new();
+    for crash_file in crash_files {
+        let written = crash_file.metadata().and_then(|m| m.modified()).ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+        // sessions recorded before the features were don't tell
+        let Some(recorded) = resume::session_at(target, written).and_then(|s| s.get("features").map(Features::from_json)) else { continue };
+        if recorded == *selected() || warned.contains(&recorded) {
+            continue;
+        }
+        eprintln!("warning: \"{}\" is built with {}, but the session which found \"{}\" fuzzed it with {}, the crashes may not reproduce",
+            target, selected().describe(), crash_file.display(), recorded.describe());
+        warned.push(recorded);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs;
+use std::process;
+use std::path::Path;
+
+use crate::cli::Subcommand;
+use crate::metadata;
+
+pub const GEN_TEST: Subcommand = Subcommand {
+    name: "gen-test",
+    usage: "TARGET CRASH_FILE",
+    about: "prints a regression test running the body of the harness of a target on a crash",
+    options: &[],
+    passthrough: None,
+};
+
+// longest line of the embedded input, in bytes
+const BYTES_PER_LINE: usize = 32;
+
+// the closure given to `fuzz!`, which is `|data| { ... }`, `|data: &[u8]| { ... }`, `|data: Type| { ... }`
+// or `|a: Type, b: Other| { ... }`,
+// or to the `fuzz_target!` of the cargo-fuzz harnesses, which may follow an `init: ...` expression and
+// return a `Corpus`
+struct Harness {
+    init: Option<String>,
+    input: String,
+    input_type: Option<String>,
+    output_type: Option<String>,
+    body: String,
+}
+
+// end of the block starting at `source[0]`, braces in string and char literals or comments don't count
+fn block_end(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            // char literals, like `'\''` or `'{'`, any other quote starts a lifetime
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => {
+                i += source[i + 3..].find('\'').map_or(0, |end| end + 3);
+            }
+            b'\'' if bytes.get(i + 2) == Some(&b'\'') => i += 2,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i += source[i..].find('\n').unwrap_or(bytes.len() - i);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// the parameters of the closure, split at the commas which aren't in a type like `(u8, u16)`
+fn split_parameters(parameters: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in parameters.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(&parameters[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(&parameters[start..]);
+    split.into_iter().map(str::trim).filter(|p| !p.is_empty()).collect()
+}
+
+fn parse_harness(source: &str) -> Option<Harness> {
+    let start = ["fuzz!(", "fuzz_target!("].iter().filter_map(|m| source.find(m).map(|i| i + m.len())).min()?;
+    let mut closure = source[start..].trim_start();
+    let mut init = None;
+    if let Some(rest) = closure.strip_prefix("init:") {
+        let rest = rest.trim_start();
+        let end = match rest.starts_with('{') {
+            true => block_end(rest)?,
+            false => rest.find(',')?,
+        };
+        init = Some(rest[..end].trim());
+        closure = rest[end..].trim_start().strip_prefix(',')?.trim_start();
+    }
+    let (parameters, rest) = closure.strip_prefix('|')?.split_once('|')?;
+    let parameters = split_parameters(parameters);
+    let (input, input_type) = match parameters.as_slice() {
+        [parameter] => match parameter.split_once(':') {
+            Some((input, input_type)) => (input.trim().to_string(), Some(input_type.trim().to_string())),
+            None => (parameter.to_string(), None),
+        },
+        // `|a: u32, b: &[u8]|`, decoded as a tuple by `fuzz!`
+        _ => {
+            let typed: Vec<(&str, &str)> = parameters.iter().map(|p| p.split_once(':').map(|(i, t)| (i.trim(), t.trim()))).collect::<Option<_>>()?;
+            (format!("({})", typed.iter().map(|(i, _)| *i).collect::<Vec<_>>().join(", ")),
+                Some(format!("({},)", typed.iter().map(|(_, t)| *t).collect::<Vec<_>>().join(", "))))
+        }
+    };
+
+    let mut rest = rest.trim_start();
+    let mut output_type = None;
+    if let Some(output) = rest.strip_prefix("->") {
+        let open = output.find('{')?;
+        output_type = Some(output[..open].trim());
+        rest = &output[open..];
+    }
+    if !rest.starts_with('{') {
+        return None;
+    }
+    let body = &rest[..block_end(rest)?];
+    Some(Harness {
+        init: init.map(String::from),
+        input,
+        input_type: input_type.filter(|t| t.replace(' ', "") != "&[u8]"),
+        output_type: output_type.map(String::from),
+        body: body.to_string(),
+    })
+}
+
+// the crash as a byte string literal, split in lines to stay readable
+fn byte_string(bytes: &[u8]) -> String {
+    let lines: Vec<String> = bytes.chunks(BYTES_PER_LINE)
+        .map(|chunk| chunk.iter().enumerate().map(|(i, &b)| match b {
+            // a line continuation would eat the leading spaces
+            b' ' if i == 0 => "\\x20".to_string(),
+            b'"' => "\\\"".to_string(),
+            b'\\' => "\\\\".to_string(),
+            b' '..=b'~' => (b as char).to_string(),
+            _ => format!("\\x{:02x}", b),
+        }).collect())
+        .collect();
+    match lines.len() {
+        0 => "b\"\"".to_string(),
+        1 => format!("b\"{}\"", lines[0]),
+        _ => format!("b\"\\\n{}\"", lines.iter().map(|l| format!("        {}", l)).collect::<Vec<_>>().join("\\\n")),
+    }
+}
+
+// stable (FNV-1a) hash of the crash, so that regenerating a test gives the same name
+fn content_hash(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for &b in bytes {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+// the statements of a block, or an expression, as lines of the test starting with `prefix`
+fn reindent(code: &str, prefix: &str) -> String {
+    let statements = match code.starts_with('{') {
+        true => &code[1..code.len() - 1],
+        false => code,
+    };
+    let lines: Vec<&str> = statements.trim_end().lines().skip_while(|l| l.trim().is_empty()).collect();
+    let indent = lines.iter().filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min().unwrap_or(0);
+    lines.iter()
+        .map(|l| if l.trim().is_empty() { "\n".to_string() } else { format!("{}{}\n", prefix, l[indent..].trim_end()) })
+        .collect()
+}
+
+fn generate_test(target: &str, crash_file: &Path, crash: &[u8], harness: &Harness) -> String {
+    let name = format!("{}_crash_{:08x}", target.replace('-', "_"), content_hash(crash));
+    let data = byte_string(crash);
+
+    // `honggfuzz::rng()` has to be seeded from the crash as it was from the input when fuzzing
+    let uses_rng = harness.body.contains("rng()");
+
+    // the same conversion as `fuzz!` does for typed inputs
+    let input = match &harness.input_type {
+        None if uses_rng => format!("    let {input}: &[u8] = {data};\n    honggfuzz::seed_rng({input});\n", input = harness.input, data = data),
+        None => format!("    let {}: &[u8] = {};\n", harness.input, data),
+        Some(input_type) => format!(
+            "    let {input}: {input_type} = {{\n        use honggfuzz::arbitrary::{{Arbitrary, Unstructured}};\n\n        let data: &[u8] = {data};\n{seed}        Arbitrary::arbitrary_take_rest(Unstructured::new(data)).expect(\"the crash doesn't convert anymore to `{input_type}`\")\n    }};\n",
+            input = harness.input, input_type = input_type, data = data, seed = if uses_rng { "        honggfuzz::seed_rng(data);\n" } else { "" }),
+    };
+
+    // the statements of the harness body become the ones of the test, in a closure when they return a value
+    let body = match &harness.output_type {
+        None => reindent(&harness.body, "    "),
+        Some(output_type) => format!("    let _ = (move || -> {} {{\n{}    }})();\n", output_type, reindent(&harness.body, "        ")),
+    };
+    let init = match &harness.init {
+        Some(init) if init.starts_with('{') => reindent(init, "    "),
+        Some(init) => format!("    {};\n", init),
+        None => String::new(),
+    };
+
+    format!(
+        "// regression test generated by \"cargo hfuzz gen-test\" from \"{}\"\n#[test]\nfn {}() {{\n{}{}{}}}\n",
+        crash_file.display(), name, init, input, body)
+}
+
+pub fn hfuzz_gen_test<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let mut args = GEN_TEST.parse(args).args.into_iter();
+    let (target, crash_file) = match (args.next(), args.next(), args.next()) {
+        (Some(target), Some(crash_file), None) => (target, crash_file),
+        _ => GEN_TEST.error("please specify the target and the crash file"),
+    };
+
+    let crash = fs::read(&crash_file).unwrap_or_else(|_| {
+        eprintln!("error: failed to read \"{}\"", crash_file);
+        process::exit(1);
+    });
+
+    let src_path = &metadata::find_target(&target).unwrap_or_else(|| {
+        eprintln!("error: no fuzz target named \"{}\"", target);
+        process::exit(1);
+    }).src_path;
+    let source = fs::read_to_string(src_path).unwrap_or_else(|_| {
+        eprintln!("error: failed to read \"{}\"", src_path.display());
+        process::exit(1);
+    });
+    let harness = parse_harness(&source).unwrap_or_else(|| {
+        eprintln!("error: no `fuzz!(|data| {{ ... }})` or `fuzz_target!(|data| {{ ... }})` harness found in \"{}\"", src_path.display());
+        process::exit(1);
+    });
+
+    // the test goes where what the harness body uses is in scope, e.g. a `#[cfg(test)]` module of the target
+    print!("{}", generate_test(&target, Path::new(&crash_file), &crash, &harness));
+}
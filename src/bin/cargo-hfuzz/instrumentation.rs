@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use crate::cli::{Matches, Opt, Subcommand};
+use crate::sanitizer;
+
+// what the instrumented builds trace besides the edges, comparisons helping the most on targets
+// parsing magic values and checksums, at the cost of throughput
+struct Trace {
+    name: &'static str,
+    llvm_arg: &'static str,
+}
+
+const CMP: Trace = Trace { name: "cmp", llvm_arg: "-sanitizer-coverage-trace-compares" };
+const DIV: Trace = Trace { name: "div", llvm_arg: "-sanitizer-coverage-trace-divs" };
+const GEP: Trace = Trace { name: "gep", llvm_arg: "-sanitizer-coverage-trace-geps" };
+
+// accepted by the build commands and by the ones building before fuzzing
+pub const CMP_COVERAGE: Opt = Opt { name: "--cmp-coverage", value: None, help: "traces comparisons, on by default except on macOS without a sanitizer" };
+pub const NO_CMP_COVERAGE: Opt = Opt { name: "--no-cmp-coverage", value: None, help: "doesn't trace comparisons" };
+pub const DIV_COVERAGE: Opt = Opt { name: "--div-coverage", value: None, help: "traces divisions, on by default" };
+pub const NO_DIV_COVERAGE: Opt = Opt { name: "--no-div-coverage", value: None, help: "doesn't trace divisions" };
+pub const GEP_COVERAGE: Opt = Opt { name: "--gep-coverage", value: None, help: "traces array indices (getelementptr), off by default" };
+pub const NO_GEP_COVERAGE: Opt = Opt { name: "--no-gep-coverage", value: None, help: "doesn't trace array indices" };
+
+// the traces turned on or off on the command line
+static CHOSEN: OnceLock<Vec<(&'static str, bool)>> = OnceLock::new();
+
+pub fn select(command: &Subcommand, matches: &Matches) {
+    let mut chosen = Vec::new();
+    for trace in [&CMP, &DIV, &GEP] {
+        let on = matches.flag(&format!("--{}-coverage", trace.name));
+        let off = matches.flag(&format!("--no-{}-coverage", trace.name));
+        if on && off {
+            command.error(&format!("\"--{0}-coverage\" and \"--no-{0}-coverage\" contradict each other", trace.name));
+        }
+        if on || off {
+            chosen.push((trace.name, on));
+        }
+    }
+    let _ = CHOSEN.set(chosen);
+}
+
+fn enabled(trace: &Trace) -> bool {
+    let chosen = CHOSEN.get().and_then(|c| c.iter().find(|(name, _)| *name == trace.name)).map(|(_, on)| *on);
+    chosen.unwrap_or_else(|| match trace.name {
+        // trace-compares doesn't work on macOS without a sanitizer
+        "cmp" => cfg!(not(target_os="macos")) || sanitizer::selected().is_some(),
+        "div" => true,
+        _ => false,
+    })
+}
+
+pub fn rustflags() -> String {
+    [&CMP, &DIV, &GEP].iter()
+        .filter(|t| enabled(t))
+        .map(|t| format!("-C llvm-args={} ", t.llvm_arg))
+        .collect()
+}
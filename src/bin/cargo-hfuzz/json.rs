@@ -0,0 +1,305 @@
+use std::fmt::{self, Write};
+
+// Minimal JSON support, enough to read `cargo metadata` and to produce
+// machine-readable outputs without pulling a dependency in `cargo install`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(input: &str) -> Result<Json, String> {
+        let mut parser = Parser { input: input.as_bytes(), pos: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(format!("unexpected trailing characters at offset {}", parser.pos));
+        }
+        Ok(value)
+    }
+
+    pub fn object<K: Into<String>>(fields: Vec<(K, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if !n.is_finite() => f.write_str("null"),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => {
+                let mut out = String::new();
+                write_string(&mut out, s);
+                f.write_str(&out)
+            }
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 { f.write_str(",")?; }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 { f.write_str(",")?; }
+                    let mut out = String::new();
+                    write_string(&mut out, key);
+                    write!(f, "{}:{}", out, value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json { Json::Bool(b) }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json { Json::String(s.into()) }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json { Json::String(s) }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Json { Json::Number(n as f64) }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json { Json::Number(n as f64) }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Json { Json::Number(n.into()) }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Json { Json::Number(n) }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Json { Json::Array(items.into_iter().map(Into::into).collect()) }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json { value.map_or(Json::Null, Into::into) }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(format!("expected `{}` at offset {}", literal, self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.input.get(self.pos) {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.input.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => { self.pos += 1; return Ok(Json::Array(items)); }
+                        _ => return Err(format!("expected `,` or `]` at offset {}", self.pos)),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if self.input.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    self.expect(":")?;
+                    fields.push((key, self.value()?));
+                    self.whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => { self.pos += 1; return Ok(Json::Object(fields)); }
+                        _ => return Err(format!("expected `,` or `}}` at offset {}", self.pos)),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.input.get(self.pos) {
+                    self.pos += 1;
+                }
+                let number = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+                number.parse().map(Json::Number).map_err(|_| format!("invalid number `{}` at offset {}", number, start))
+            }
+            _ => Err(format!("unexpected character at offset {}", self.pos)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut bytes = Vec::new();
+        loop {
+            match self.input.get(self.pos) {
+                None => return Err("unterminated string".into()),
+                Some(b'"') => { self.pos += 1; break; }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.input.get(self.pos) {
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'u') => {
+                            let code = self.hex4()?;
+                            let code = if (0xd800..0xdc00).contains(&code) {
+                                // surrogate pair
+                                self.pos += 1;
+                                self.expect("\\")?;
+                                let low = self.hex4()?;
+                                0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+                            } else {
+                                code
+                            };
+                            char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        Some(&c) => c as char,
+                        None => return Err("unterminated string".into()),
+                    };
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(&c) => { bytes.push(c); self.pos += 1; }
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "invalid utf-8 in string".into())
+    }
+
+    // parses the 4 hex digits following the current `u`, leaves `pos` on the last one
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.input.get(self.pos + 1..self.pos + 5).ok_or("truncated unicode escape")?;
+        let code = u32::from_str_radix(std::str::from_utf8(digits).unwrap_or(""), 16)
+            .map_err(|_| format!("invalid unicode escape at offset {}", self.pos))?;
+        self.pos += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let value = Json::object(vec![
+            ("null", Json::Null),
+            ("bools", vec![true, false].into()),
+            ("numbers", vec![0.0, -42.0, 1.5, 1e20, 6.02e-23].into()),
+            ("string", "quotes \" backslash \\ newline \n tab \t bell \u{7} crab \u{1f980}".into()),
+            ("nested", Json::object(vec![("empty array", Json::Array(vec![])), ("empty object", Json::Object(vec![]))])),
+            ("", "an empty key".into()),
+        ]);
+        let text = value.to_string();
+        assert_eq!(Json::parse(&text), Ok(value));
+        assert!(text.contains(r#""string":"quotes \" backslash \\ newline \n tab \t bell \u0007 crab "#));
+        assert!(text.contains(r#""numbers":[0,-42,1.5,100000000000000000000,0.0000000000000000000000602]"#));
+    }
+
+    #[test]
+    fn parse_whitespace_and_escapes() {
+        let value = Json::parse(" {\n\t\"a\" : [ 1 , \"\\u00e9\\ud83e\\udd80\\/\" ] ,\"b\":{ } }\r\n").unwrap();
+        assert_eq!(value.get("a").map(Json::as_array), Some(&[Json::Number(1.0), "é\u{1f980}/".into()][..]));
+        assert_eq!(value.get("b"), Some(&Json::Object(vec![])));
+        assert_eq!(value.get("c"), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Json::parse("[1,]"), Err("unexpected character at offset 3".to_string()));
+        assert_eq!(Json::parse("{\"a\" 1}"), Err("expected `:` at offset 5".to_string()));
+        assert_eq!(Json::parse("\"open"), Err("unterminated string".to_string()));
+        assert_eq!(Json::parse("1 2"), Err("unexpected trailing characters at offset 2".to_string()));
+    }
+}
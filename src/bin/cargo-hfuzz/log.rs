@@ -0,0 +1,250 @@
+use std::env;
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, AtomicI8, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::VERSION;
+use crate::json::Json;
+use crate::workspace;
+
+// -1 with `-q`, 1 with `-v` and 2 with `-vv`
+static VERBOSITY: AtomicI8 = AtomicI8::new(0);
+
+// `--log-format json`: what cargo-hfuzz tells is printed as JSON lines on stderr, with the lifecycle
+// events of the builds and the sessions
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// `--dry-run`: the builds and honggfuzz are printed instead of run, and nothing is written
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+// the commands taking `--dry-run`
+pub const DRY_RUN_COMMANDS: &[&str] = &["build", "build-no-instr", "build-debug", "build-grcov", "run", "run-no-instr", "run-debug", "run-binary", "migrate"];
+
+// the log of the target being worked on, what is logged before one is known waiting for it
+struct Log {
+    file: Option<PathBuf>,
+    pending: Vec<String>,
+}
+
+static LOG: Mutex<Log> = Mutex::new(Log { file: None, pending: Vec::new() });
+
+pub const FILE_NAME: &str = "cargo-hfuzz.log";
+
+// `-q`, `--quiet`, `-v`, `--verbose` and `-vv`, given before the command
+pub fn parse_verbosity(arg: &str) -> bool {
+    let verbosity = match arg {
+        "-q" | "--quiet" => -1,
+        "-v" | "--verbose" => VERBOSITY.load(Ordering::Relaxed).max(0) + 1,
+        "-vv" => 2,
+        _ => return false,
+    };
+    VERBOSITY.store(verbosity.min(2), Ordering::Relaxed);
+    true
+}
+
+pub fn select_format(format: Option<&str>) {
+    match format {
+        Some("text") => JSON.store(false, Ordering::Relaxed),
+        Some("json") => JSON.store(true, Ordering::Relaxed),
+        Some(format) => {
+            eprintln!("error: unknown log format \"{}\", expected \"text\" or \"json\"", format);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("error: \"--log-format\" expects a value, like \"--log-format json\"");
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// one line like `{"time":"...","event":"build_started",...}`, only with `--log-format json`
+pub fn event(event: &str, mut fields: Vec<(&str, Json)>) {
+    if json() {
+        let mut line = vec![("time", timestamp().into()), ("event", event.into())];
+        line.append(&mut fields);
+        eprintln!("{}", Json::object(line));
+    }
+}
+
+pub fn enable_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+// what would be run with `--dry-run`, on stdout, the environment variables it would set first
+pub fn preview(command: &Command) {
+    println!("{}", describe(command));
+}
+
+pub fn quiet() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) < 0
+}
+
+pub fn verbose() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) > 0
+}
+
+// like "2024-05-17T09:03:12Z", from the days since the epoch to the civil calendar
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, time) = ((secs / 86400) as i64, secs % 86400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn write(file: &PathBuf, lines: &[String]) {
+    let written = OpenOptions::new().create(true).append(true).open(file)
+        .and_then(|mut f| f.write_all(lines.iter().map(|l| format!("{}\n", l)).collect::<String>().as_bytes()));
+    if written.is_err() {
+        eprintln!("warning: failed to write \"{}\"", file.display());
+    }
+}
+
+fn append(line: String) {
+    let mut log = LOG.lock().unwrap();
+    let line = format!("[{}] {}", timestamp(), line);
+    match &log.file {
+        Some(file) => write(file, &[line]),
+        None => log.pending.push(line),
+    }
+}
+
+// the commands run from now on are logged in the workspace of `target`, with the ones run before
+pub fn select_target(target: &str) {
+    if dry_run() {
+        return;
+    }
+    let dir = workspace::log_dir(target);
+    let _ = fs::create_dir_all(&dir);
+    let file = dir.join(FILE_NAME);
+    let mut log = LOG.lock().unwrap();
+    if log.file.as_ref() == Some(&file) {
+        return;
+    }
+    // every invocation starts with how cargo-hfuzz itself was run
+    let invocation = format!("[{}] cargo-hfuzz {}: {}", timestamp(), VERSION, shell_line(env::args_os().skip(1)));
+    let lines: Vec<String> = Some(invocation).into_iter().chain(log.pending.drain(..)).collect();
+    write(&file, &lines);
+    log.file = Some(file);
+}
+
+// an argument as a shell would take it back, quoted when it has to
+pub fn quoted(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    match !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c)) {
+        true => arg.into_owned(),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+fn shell_line<T, S>(args: T) -> String where T: Iterator<Item=S>, S: AsRef<OsStr> {
+    args.map(|a| quoted(a.as_ref())).collect::<Vec<_>>().join(" ")
+}
+
+// the command line, after the environment variables the command sets, or removes with `-u`
+fn describe(command: &Command) -> String {
+    let env = command.get_envs().map(|(key, value)| match value {
+        Some(value) => format!("{}={}", key.to_string_lossy(), quoted(value)),
+        None => format!("-u {}", key.to_string_lossy()),
+    });
+    env.chain(Some(shell_line(Some(command.get_program()).into_iter().chain(command.get_args())))).collect::<Vec<_>>().join(" ")
+}
+
+fn started(command: &Command, pid: Option<u32>) {
+    let line = match pid {
+        Some(pid) => format!("$ {} (pid {})", describe(command), pid),
+        None => format!("$ {}", describe(command)),
+    };
+    let command_line = match VERBOSITY.load(Ordering::Relaxed) {
+        2 => describe(command),
+        _ => shell_line(Some(command.get_program()).into_iter().chain(command.get_args())),
+    };
+    match (verbose(), json()) {
+        (true, true) => event("command_started", vec![("command", command_line.into()), ("pid", pid.map(u64::from).into())]),
+        (true, false) => eprintln!("running: {}", command_line),
+        _ => {}
+    }
+    append(line);
+}
+
+fn ended(line: String) {
+    match (verbose(), json()) {
+        (true, true) => event("command_exited", vec![("status", line.as_str().into())]),
+        (true, false) => eprintln!("{}", line),
+        _ => {}
+    }
+    append(line);
+}
+
+fn failed(command: &Command, error: &io::Error) {
+    ended(format!("failed to run \"{}\": {}", command.get_program().to_string_lossy(), error));
+}
+
+// how a command started by `spawn` ended
+pub fn exited(child: &Child, status: ExitStatus) {
+    ended(format!("pid {} {}", child.id(), status));
+}
+
+// `Command::status`, logged
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    started(command, None);
+    let status = command.status();
+    match &status {
+        Ok(status) => ended(status.to_string()),
+        Err(error) => failed(command, error),
+    }
+    status
+}
+
+// `Command::output`, logged
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    started(command, None);
+    let output = command.output();
+    match &output {
+        Ok(output) => ended(output.status.to_string()),
+        Err(error) => failed(command, error),
+    }
+    output
+}
+
+// `Command::spawn`, logged, what the child exits with going to `exited`
+pub fn spawn(command: &mut Command) -> io::Result<Child> {
+    let child = command.spawn();
+    match &child {
+        Ok(child) => started(command, Some(child.id())),
+        Err(error) => {
+            started(command, None);
+            failed(command, error);
+        }
+    }
+    child
+}
+
+// what cargo-hfuzz tells about its progress, which `-q` hides
+pub fn note(message: &str) {
+    match (quiet(), json()) {
+        (true, _) => {}
+        (false, true) => event("note", vec![("message", message.into())]),
+        (false, false) => eprintln!("{}", message),
+    }
+}
@@ -0,0 +1,1465 @@
+use std::fs;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{self, Command, ExitStatus, Stdio};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod android;
+mod annotate;
+mod bench;
+mod binary;
+mod ci;
+mod cli;
+mod completions;
+mod config;
+mod corpus;
+mod coverage;
+mod crash;
+mod dashboard;
+mod debugger;
+mod dict;
+mod doctor;
+mod features;
+mod gen_test;
+mod instrumentation;
+mod json;
+mod log;
+mod merge;
+mod metadata;
+mod migrate;
+mod notify;
+mod parallel;
+mod policy;
+mod print_env;
+mod reject;
+mod remote;
+mod replay;
+mod resume;
+mod run_all;
+mod sanitizer;
+mod sarif;
+mod scaffold;
+mod session;
+mod socket_fuzzer;
+mod stats;
+mod summary;
+mod toml;
+mod tmin;
+mod triage;
+mod verify;
+mod watch;
+mod workspace;
+
+use cli::{Matches, Opt, Subcommand};
+use json::Json;
+use policy::ExitPolicy;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const HONGGFUZZ_TARGET: &str = "hfuzz_target";
+const HONGGFUZZ_WORKSPACE: &str = "hfuzz_workspace";
+
+#[cfg(target_family="windows")]
+compile_error!("honggfuzz-rs does not currently support Windows but works well under WSL (Windows Subsystem for Linux)");
+
+#[derive(PartialEq)]
+enum BuildType {
+    ReleaseInstrumented,
+    ReleaseNotInstrumented,
+    ProfileWithGrcov,
+    Coverage,
+    Debug,
+    // instrumented like ReleaseInstrumented, the harness replaying the corpus for "cargo hfuzz bench"
+    Bench,
+}
+
+// TODO: maybe use `rustc_version` crate
+fn rustc_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| {
+        let output = log::output(Command::new("rustc").args(["-v", "-V"])).unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    })
+}
+
+fn host_triple() -> &'static str {
+    rustc_version().lines().find(|l|{l.starts_with("host: ")}).unwrap().get(6..).unwrap()
+}
+
+// the major version of the LLVM rustc is built with, like 17 for "LLVM version: 17.0.6"
+fn llvm_major() -> Option<u32> {
+    let version = rustc_version().lines().find_map(|l| l.strip_prefix("LLVM version: "))?;
+    version.split('.').next()?.trim().parse().ok()
+}
+
+// the coverage instrumentation, whose pass was renamed when LLVM 13 moved to its new pass manager
+fn sancov_rustflags() -> String {
+    let pass = match llvm_major() {
+        Some(major) if major < 13 => "sancov",
+        _ => "sancov-module",
+    };
+    format!("\
+    -C passes={} \
+    -C llvm-args=-sanitizer-coverage-level=4 \
+    -C llvm-args=-sanitizer-coverage-trace-pc-guard \
+    ", pass)
+}
+
+// set by `--target`, which takes precedence over hfuzz.toml
+static TARGET_TRIPLE: OnceLock<String> = OnceLock::new();
+
+// the triple the fuzz targets and the engine are built for, the host's unless cross-compiling
+fn target_triple() -> String {
+    TARGET_TRIPLE.get().or(config::config().target.as_ref()).cloned().unwrap_or_else(|| host_triple().into())
+}
+
+// set by `--no-default-rustflags`, or HFUZZ_NO_DEFAULT_RUSTFLAGS being set
+static NO_DEFAULT_RUSTFLAGS: AtomicBool = AtomicBool::new(false);
+
+const NO_DEFAULT_RUSTFLAGS_OPT: Opt = Opt {
+    name: "--no-default-rustflags",
+    value: None,
+    help: "builds with the cfgs of cargo-hfuzz and RUSTFLAGS only, without its codegen and coverage flags",
+};
+
+// the codegen flags and the coverage passes being left to RUSTFLAGS, for custom flag sets
+fn no_default_rustflags() -> bool {
+    NO_DEFAULT_RUSTFLAGS.load(Ordering::Relaxed) || env::var_os("HFUZZ_NO_DEFAULT_RUSTFLAGS").filter(|v| !v.is_empty()).is_some()
+}
+
+fn cross_compiling() -> bool {
+    target_triple() != host_triple()
+}
+
+// a musl target of the architecture of the host, like `x86_64-unknown-linux-musl` on `x86_64-unknown-linux-gnu`,
+// runs on the host, fuzzed by an engine built for the host
+fn runs_on_host() -> bool {
+    let (host, target) = (host_triple(), target_triple());
+    let arch = |triple: &str| triple.split('-').next().unwrap_or_default().to_string();
+    !cross_compiling() || (target.ends_with("-linux-musl") && host.contains("-linux-") && arch(host) == arch(&target))
+}
+
+// where build.rs puts the engine, cross-compiled ones living next to the targets they fuzz
+fn engine_dir() -> PathBuf {
+    match cross_compiling() {
+        true => Path::new(&workspace::target_dir()).join(target_triple()),
+        false => PathBuf::from(workspace::target_dir()),
+    }
+}
+
+// set by `--engine`, which takes precedence over HFUZZ_ENGINE_PATH
+static ENGINE: OnceLock<PathBuf> = OnceLock::new();
+
+// the engine installed next to cargo-hfuzz, like `~/.cargo/bin/hfuzz-engine`, for the targets whose build
+// directory has none: its name neither shadows the honggfuzz of a distribution nor is a cargo subcommand
+fn installed_engine() -> Option<PathBuf> {
+    Some(env::current_exe().ok()?.parent()?.join("hfuzz-engine"))
+}
+
+// installs the engine built with the targets next to cargo-hfuzz, the first time one is built and after
+// cargo-hfuzz was reinstalled, nothing being said when its directory isn't writable
+fn install_engine() {
+    let (built, installed) = match installed_engine() {
+        Some(installed) if runs_on_host() => (engine_dir().join("honggfuzz"), installed),
+        _ => return,
+    };
+    let exe = env::current_exe().ok().and_then(|exe| modified(&exe));
+    if !built.exists() || modified(&installed).zip(exe).is_some_and(|(installed, exe)| installed >= exe) {
+        return;
+    }
+    // copied aside then renamed, for a concurrent run never to start half of it
+    let partial = installed.with_extension(process::id().to_string());
+    if fs::copy(&built, &partial).is_ok() && fs::rename(&partial, &installed).is_ok() {
+        println!("installed the honggfuzz engine in \"{}\", for the targets whose build directory has none", installed.display());
+    }
+    let _ = fs::remove_file(&partial);
+}
+
+// the honggfuzz executable fuzzing the targets, the one built with them unless an installed one is given
+fn engine() -> PathBuf {
+    static CHECKED: OnceLock<PathBuf> = OnceLock::new();
+    CHECKED.get_or_init(|| {
+        let installed = ENGINE.get().cloned().or_else(|| env::var_os("HFUZZ_ENGINE_PATH").filter(|p| !p.is_empty()).map(PathBuf::from));
+        match installed {
+            Some(engine) => {
+                check_engine(&engine);
+                engine
+            }
+            None => {
+                let engine = engine_dir().join("honggfuzz");
+                if engine.exists() || log::dry_run() {
+                    return engine;
+                }
+                match installed_engine().filter(|installed| runs_on_host() && installed.exists()) {
+                    Some(installed) => {
+                        check_engine(&installed);
+                        installed
+                    }
+                    None => {
+                        eprintln!("error: no honggfuzz engine in \"{}\", which the honggfuzz library doesn't build with its \"no-engine\" feature, \
+                                   nor next to cargo-hfuzz: give an installed one with \"--engine PATH\" or HFUZZ_ENGINE_PATH", engine_dir().display());
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+    }).clone()
+}
+
+// like "2.6", from the first line of the version file build.rs writes next to the engine it built
+fn bundled_engine_version() -> Option<String> {
+    let version = fs::read_to_string(engine_dir().join("honggfuzz.version")).ok()?;
+    version.lines().next().map(String::from)
+}
+
+// like "without libbfd: ...", the libraries build.rs didn't find, written after the versions in the version file
+fn engine_limitations() -> Vec<String> {
+    let version = fs::read_to_string(engine_dir().join("honggfuzz.version")).unwrap_or_default();
+    version.lines().skip(3).map(String::from).collect()
+}
+
+// like "2.6", from the "honggfuzz version 2.6 ..." honggfuzz prints first in its help
+fn installed_engine_version(engine: &Path) -> Result<Option<String>, std::io::Error> {
+    let output = log::output(Command::new(engine).arg("--help"))?;
+    let help = String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr);
+    Ok(help.split("honggfuzz version ").nth(1).and_then(|v| v.split_whitespace().next()).map(String::from))
+}
+
+// the targets link the libhfuzz of the bundled sources, which has to speak the protocol of the engine
+fn check_engine(engine: &Path) {
+    // a cross-compiled engine only runs through HFUZZ_RUNNER
+    if !runs_on_host() {
+        return;
+    }
+    let installed = installed_engine_version(engine).unwrap_or_else(|error| {
+        eprintln!("error: failed to run the honggfuzz engine \"{}\" ({})", engine.display(), error);
+        process::exit(1);
+    });
+    let installed = match installed {
+        Some(installed) => installed,
+        None => return eprintln!("warning: \"{}\" doesn't tell its version, it may not be honggfuzz", engine.display()),
+    };
+    let bundled = match bundled_engine_version() {
+        Some(bundled) => bundled,
+        None => return, // not built yet
+    };
+    let major_minor = |v: &str| v.split('.').take(2).map(String::from).collect::<Vec<_>>();
+    if major_minor(&installed)[0] != major_minor(&bundled)[0] {
+        eprintln!("error: the honggfuzz engine \"{}\" is version {}, incompatible with the version {} of the library the targets link", engine.display(), installed, bundled);
+        process::exit(1);
+    }
+    if major_minor(&installed) != major_minor(&bundled) {
+        eprintln!("warning: the honggfuzz engine \"{}\" is version {} and the library the targets link version {}", engine.display(), installed, bundled);
+    }
+}
+
+fn find_crate_root() -> Option<PathBuf> {
+    let mut path = env::current_dir().unwrap();
+
+    while !path.join("Cargo.toml").is_file() {
+        // move to parent path
+        path = match path.parent() {
+            Some(parent) => parent.into(),
+            None => return None, // early return
+        };
+    }
+
+    Some(path)
+}
+
+fn release_binary(target: &str) -> PathBuf {
+    Path::new(&workspace::target_dir()).join(target_triple()).join("release").join(target)
+}
+
+fn debug_binary(target: &str) -> PathBuf {
+    Path::new(&workspace::target_dir()).join(target_triple()).join("debug").join(target)
+}
+
+// coverage builds live in their own target directory to not invalidate the other builds
+fn coverage_target_dir() -> PathBuf {
+    Path::new(&workspace::target_dir()).join("coverage")
+}
+
+// so do the builds of "cargo hfuzz bench"
+fn bench_target_dir() -> PathBuf {
+    Path::new(&workspace::target_dir()).join("bench")
+}
+
+fn bench_binary(target: &str) -> PathBuf {
+    bench_target_dir().join(target_triple()).join("release").join(target)
+}
+
+fn coverage_binary(target: &str) -> PathBuf {
+    coverage_target_dir().join(target_triple()).join("debug").join(target)
+}
+
+// `cargo build` arguments selecting the given targets
+fn bin_args(targets: &[String]) -> Vec<String> {
+    targets.iter().flat_map(|t| vec!["--bin".to_string(), t.clone()]).collect()
+}
+
+fn debugger_command(target: &str) -> Command {
+    debugger::command(&debugger::profile(), &debug_binary(target))
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn hfuzz_version<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    if let Some(arg) = VERSION_COMMAND.parse(args).args.first() {
+        VERSION_COMMAND.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    println!("cargo-hfuzz {}", VERSION);
+
+    // written by the build script of the honggfuzz library along with the honggfuzz binary
+    let honggfuzz_binary = engine_dir().join("honggfuzz");
+    let version_file = engine_dir().join("honggfuzz.version");
+    let version = fs::read_to_string(&version_file).unwrap_or_default();
+    let mut version = version.lines();
+    match (version.next(), version.next(), version.next()) {
+        (Some(engine), Some(commit), Some(library)) => {
+            println!("honggfuzz library {}", library);
+            println!("honggfuzz engine {} (commit {}), in \"{}\"", engine, commit, honggfuzz_binary.display());
+            for limitation in version {
+                println!("honggfuzz engine built {}", limitation);
+            }
+            if library != VERSION {
+                eprintln!("warning: the honggfuzz engine was built by another version of the honggfuzz library ({}), rebuild it with \"cargo hfuzz build\"", library);
+            }
+        }
+        _ if honggfuzz_binary.exists() => println!("honggfuzz engine of unknown version, in \"{}\"", honggfuzz_binary.display()),
+        _ => println!("honggfuzz engine not built yet, run \"cargo hfuzz build\" first"),
+    }
+    if let Some(installed) = env::var_os("HFUZZ_ENGINE_PATH").filter(|p| !p.is_empty()).map(PathBuf::from) {
+        match installed_engine_version(&installed) {
+            Ok(version) => println!("HFUZZ_ENGINE_PATH: honggfuzz engine {}, in \"{}\"", version.as_deref().unwrap_or("of unknown version"), installed.display()),
+            Err(error) => eprintln!("warning: failed to run the honggfuzz engine of HFUZZ_ENGINE_PATH \"{}\" ({})", installed.display(), error),
+        }
+    }
+
+    let installed = env::current_exe().ok().and_then(|exe| modified(&exe));
+    if let (Some(built), Some(installed)) = (modified(&honggfuzz_binary), installed) {
+        if built < installed {
+            eprintln!("warning: the honggfuzz engine was built before cargo-hfuzz was installed, rebuild it with \"cargo hfuzz build\"");
+        }
+    }
+}
+
+const RUN_OPTIONS: &[Opt] = &[
+    Opt { name: "--timeout", value: Some("SECS"), help: "timeout of a single input, honggfuzz's `-t` (also enforced by run-debug)" },
+    Opt { name: "--jobs", value: Some("N"), help: "number of fuzzing threads and of build jobs, one per CPU by default" },
+    Opt { name: "--pin-cpus", value: Some("LIST"), help: "fuzzes on these CPUs only, like \"0-3,8\" (with `taskset`)" },
+    Opt { name: "--max-time", value: Some("SECS"), help: "stops fuzzing after this time, honggfuzz's `--run_time`" },
+    Opt { name: "--iterations", value: Some("N"), help: "stops fuzzing after N executions of the target, by all the threads, honggfuzz's `-N`" },
+    Opt { name: "--ci", value: None, help: "prints a JSON summary and exits with 1 if any crash was found, needs \"--max-time\" or \"--iterations\"" },
+    Opt { name: "--dict", value: Some("FILE"), help: "mutates with the tokens of a libFuzzer/AFL dictionary, honggfuzz's `-w`" },
+    Opt { name: "--max-len", value: Some("BYTES"), help: "longest input, honggfuzz's `-F`, longer ones are truncated by the harness" },
+    Opt { name: "--mutations", value: Some("N"), help: "mutates an input at most N times per run, honggfuzz's `-r`, or `run.mutations`" },
+    Opt { name: "--base-files-only", value: None, help: "runs the inputs of the corpus as they are, without mutating them, honggfuzz's `-r 0`" },
+    Opt { name: "--sanitizer", value: Some("NAME"), help: "builds with `-Z sanitizer=NAME`: address, leak, memory or thread (needs nightly)" },
+    Opt { name: "--keep-going", value: Some("[N]"), help: "goes on fuzzing after crashes, even with `--exit_upon_crash`, until N unique ones" },
+    Opt { name: "--target", value: Some("TRIPLE"), help: "cross-compiles the target and honggfuzz, run through HFUZZ_RUNNER or `run.runner`" },
+    android::ANDROID,
+    Opt { name: "--rr", value: None, help: "run-debug only, records the crash with rr and replays it in gdb for reverse debugging" },
+    Opt { name: "--watch", value: None, help: "rebuilds the target and restarts honggfuzz whenever the sources change, keeping the corpus" },
+    Opt { name: "--minimize", value: None, help: "minimizes the corpus with honggfuzz's `-M` instead of fuzzing, the inputs of the other \"--input\" directories joining it" },
+    Opt { name: "--post-process", value: None, help: "then replays the crashes, buckets them by stack and minimizes one of each in crashes/triaged" },
+    Opt { name: "--input", value: Some("DIR"), help: "draws inputs from this directory, repeatable, the first one being the corpus the new ones are saved in" },
+    Opt { name: "--engine", value: Some("PATH"), help: "fuzzes with this honggfuzz executable instead of the one built with the target" },
+    Opt { name: "--crash-dir", value: Some("DIR"), help: "saves the crashes in DIR/TARGET instead of the workspace, or HFUZZ_CRASH_DIR or `workspace.crash_dir`" },
+    Opt { name: "--report-dir", value: Some("DIR"), help: "writes the reports and the session summary in DIR/TARGET, or HFUZZ_REPORT_DIR or `workspace.report_dir`" },
+    Opt { name: "--log-dir", value: Some("DIR"), help: "writes the logs in DIR/TARGET, or HFUZZ_LOG_DIR or `workspace.log_dir`" },
+    Opt { name: "--net-port", value: Some("PORT"), help: "the fixed port of the server of a `fuzz_net!` harness (HFND_TCP_PORT), fuzzed by one job" },
+    socket_fuzzer::SOCKET_FUZZER,
+    Opt { name: "--custom-mutator", value: None, help: "rewrites the mutated inputs with the `honggfuzz::custom_mutator` of the target, honggfuzz's `--pprocess_cmd`" },
+    NO_DEFAULT_RUSTFLAGS_OPT,
+    features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    policy::EXIT_CODE_ON_CRASH, policy::EXIT_ZERO_ON_TIMEOUT, policy::FAIL_ON_HANG,
+    instrumentation::CMP_COVERAGE, instrumentation::NO_CMP_COVERAGE,
+    instrumentation::DIV_COVERAGE, instrumentation::NO_DIV_COVERAGE,
+    instrumentation::GEP_COVERAGE, instrumentation::NO_GEP_COVERAGE,
+];
+
+const RUN: Subcommand = Subcommand {
+    name: "run",
+    usage: "[OPTIONS] TARGET [ARGS ...]",
+    about: "builds a target with instrumentation and fuzzes it with honggfuzz",
+    options: RUN_OPTIONS,
+    passthrough: Some("the target"),
+};
+
+const RUN_NO_INSTR: Subcommand = Subcommand {
+    name: "run-no-instr",
+    usage: "[OPTIONS] TARGET [ARGS ...]",
+    about: "builds a target without instrumentation and fuzzes it with honggfuzz",
+    options: RUN_OPTIONS,
+    passthrough: Some("the target"),
+};
+
+const RUN_DEBUG: Subcommand = Subcommand {
+    name: "run-debug",
+    usage: "[OPTIONS] TARGET [CRASH_FILENAME [ARGS ...]]",
+    about: "builds a target in debug mode and replays a crash (the most recent one by default, every file of CRASH_FILENAME if it is a directory) in a debugger (HFUZZ_DEBUGGER, rust-lldb by default)",
+    options: RUN_OPTIONS,
+    passthrough: Some("the target, in the debugger"),
+};
+
+const BUILD_OPTIONS: &[Opt] = &[
+    Opt { name: "--all-targets", value: None, help: "builds all the fuzz targets, not cargo's tests, benches and examples" },
+    Opt { name: "--profile", value: Some("NAME"), help: "with `coverage`, builds with source-based coverage instead of sancov, into hfuzz_target/coverage (\"cargo hfuzz build\" only)" },
+    Opt { name: "--sanitizer", value: Some("NAME"), help: "builds with `-Z sanitizer=NAME`: address, leak, memory or thread (needs nightly)" },
+    Opt { name: "--target", value: Some("TRIPLE"), help: "cross-compiles the targets and honggfuzz for another platform" },
+    NO_DEFAULT_RUSTFLAGS_OPT,
+    features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    instrumentation::CMP_COVERAGE, instrumentation::NO_CMP_COVERAGE,
+    instrumentation::DIV_COVERAGE, instrumentation::NO_DIV_COVERAGE,
+    instrumentation::GEP_COVERAGE, instrumentation::NO_GEP_COVERAGE,
+];
+
+const fn build_command(name: &'static str, about: &'static str) -> Subcommand {
+    Subcommand {
+        name,
+        usage: "[OPTIONS] [CARGO BUILD OPTIONS]",
+        about,
+        options: BUILD_OPTIONS,
+        passthrough: Some("cargo build"),
+    }
+}
+
+const BUILD: Subcommand = build_command("build", "builds the fuzz targets with instrumentation, and the honggfuzz engine");
+const BUILD_NO_INSTR: Subcommand = build_command("build-no-instr", "builds the fuzz targets without instrumentation, and the honggfuzz engine");
+const BUILD_DEBUG: Subcommand = build_command("build-debug", "builds the fuzz targets in debug mode, to replay crashes");
+const BUILD_GRCOV: Subcommand = build_command("build-grcov", "builds the fuzz targets with `-Zprofile`, for grcov");
+
+const CLEAN: Subcommand = Subcommand {
+    name: "clean",
+    usage: "[TARGET [--workspace] | CARGO CLEAN OPTIONS]",
+    about: "removes the fuzzing builds, or only the binaries of a target",
+    options: &[Opt { name: "--workspace", value: None, help: "with a TARGET, also removes its corpus, crashes and logs" }],
+    passthrough: Some("cargo clean"),
+};
+
+const VERSION_COMMAND: Subcommand = Subcommand {
+    name: "version",
+    usage: "",
+    about: "prints the versions of cargo-hfuzz, of the honggfuzz library and of the engine",
+    options: &[],
+    passthrough: None,
+};
+
+const LIST: Subcommand = Subcommand {
+    name: "list",
+    usage: "[--json]",
+    about: "lists the fuzz targets of the workspace with their corpus and crash counts",
+    options: &[Opt { name: "--json", value: None, help: "prints them as JSON" }],
+    passthrough: None,
+};
+
+const COMMANDS: &[&Subcommand] = &[
+    &scaffold::INIT, &scaffold::ADD, &LIST, &tmin::TMIN, &triage::TRIAGE, &gen_test::GEN_TEST, &replay::REPLAY,
+    &verify::VERIFY, &merge::MERGE, &corpus::CORPUS, &stats::STATS, &dashboard::DASHBOARD, &coverage::COVERAGE, &bench::BENCH, &RUN, &resume::RESUME, &run_all::RUN_ALL, &parallel::RUN_PARALLEL, &binary::RUN_BINARY,
+    &RUN_NO_INSTR, &RUN_DEBUG, &BUILD, &BUILD_NO_INSTR, &BUILD_GRCOV, &BUILD_DEBUG, &print_env::PRINT_ENV, &CLEAN, &migrate::MIGRATE, &doctor::DOCTOR, &completions::COMPLETIONS, &VERSION_COMMAND,
+];
+
+// options of "cargo hfuzz run", the other arguments being given to the target
+struct RunOptions {
+    timeout: Option<u64>,
+    jobs: Option<usize>,
+    pin_cpus: Option<String>,
+    max_time: Option<u64>,
+    iterations: Option<u64>,
+    ci: bool,
+    dict: Option<PathBuf>,
+    max_len: Option<usize>,
+    mutations: Option<u32>,
+    base_files_only: bool,
+    rr: bool,
+    keep_going: bool,
+    // unique crashes after which `--keep-going` stops
+    max_crashes: Option<usize>,
+    watch: bool,
+    // or `run.post_process`
+    post_process: bool,
+    custom_mutator: bool,
+    // the processes of honggfuzz would all listen on it, there is only one
+    net_port: Option<u16>,
+    // honggfuzz only watching the target, its inputs coming from a driver
+    socket_fuzzer: bool,
+    minimize: bool,
+    // the command line of the driver cargo-hfuzz runs, if any
+    driver: Option<String>,
+    android: bool,
+    // crashes only make a session fail with `--ci`, hangs never unless asked to
+    policy: ExitPolicy,
+}
+
+impl RunOptions {
+    fn parse(command: &Subcommand, matches: &Matches) -> RunOptions {
+        RunOptions {
+            timeout: matches.parsed(command, "--timeout"),
+            jobs: matches.parsed(command, "--jobs"),
+            pin_cpus: matches.value("--pin-cpus").map(String::from),
+            max_time: matches.parsed(command, "--max-time"),
+            iterations: matches.parsed(command, "--iterations"),
+            ci: matches.flag("--ci"),
+            dict: matches.value("--dict").map(PathBuf::from),
+            max_len: matches.parsed(command, "--max-len"),
+            mutations: matches.value("--mutations").map(|n| match n.parse() {
+                Ok(n) if n > 0 => n,
+                _ => command.error(&format!("invalid value \"{}\" for \"--mutations N\", expected a positive number (\"--base-files-only\" runs the inputs without mutating them)", n)),
+            }),
+            base_files_only: matches.flag("--base-files-only"),
+            rr: matches.flag("--rr"),
+            keep_going: matches.flag("--keep-going"),
+            max_crashes: matches.value("--keep-going").filter(|n| !n.is_empty()).map(|n| match n.parse() {
+                Ok(n) if n > 0 => n,
+                _ => command.error(&format!("invalid value \"{}\" for \"--keep-going=N\", expected a positive number", n)),
+            }),
+            watch: matches.flag("--watch"),
+            post_process: matches.flag("--post-process") || config::config().post_process,
+            custom_mutator: matches.flag("--custom-mutator"),
+            net_port: matches.parsed(command, "--net-port"),
+            socket_fuzzer: matches.flag("--socket-fuzzer"),
+            minimize: matches.flag("--minimize"),
+            driver: matches.value("--socket-fuzzer").filter(|d| !d.is_empty()).map(String::from),
+            android: matches.flag("--android"),
+            policy: ExitPolicy::parse(command, matches, if matches.flag("--ci") { Some(1) } else { None }, false),
+        }
+    }
+
+    fn honggfuzz_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(timeout) = self.timeout {
+            args.extend(["-t".to_string(), timeout.to_string()]);
+        }
+        if let Some(jobs) = self.jobs {
+            args.extend(["-n".to_string(), jobs.to_string()]);
+        }
+        if let Some(max_time) = self.max_time {
+            args.extend(["--run_time".to_string(), max_time.to_string()]);
+        }
+        if let Some(iterations) = self.iterations {
+            args.extend(["-N".to_string(), iterations.to_string()]);
+        }
+        if self.ci {
+            args.push("-v".to_string()); // CI logs aren't terminals
+        }
+        if let Some(dict) = &self.dict {
+            args.extend(["-w".to_string(), dict.to_string_lossy().into_owned()]);
+        }
+        if let Some(max_len) = self.max_len {
+            args.extend(["-F".to_string(), max_len.to_string()]);
+        }
+        match (self.base_files_only, self.mutations) {
+            (true, _) => args.extend(["-r".to_string(), "0".to_string()]),
+            (false, Some(mutations)) => args.extend(["-r".to_string(), mutations.to_string()]),
+            (false, None) => {}
+        }
+        if self.net_port.is_some() {
+            args.extend(["-n".to_string(), "1".to_string()]);
+        }
+        if self.socket_fuzzer {
+            args.extend(socket_fuzzer::honggfuzz_args());
+        }
+        args
+    }
+
+    // nothing is mutated with `-r 0`, nor minimized otherwise
+    fn check_mutations(&self, command: &Subcommand) {
+        if self.base_files_only && (self.mutations.is_some() || self.dict.is_some() || self.custom_mutator) {
+            command.error("\"--base-files-only\" doesn't mutate the inputs, it doesn't work with \"--mutations\", \"--dict\" or \"--custom-mutator\"");
+        }
+        if (self.base_files_only || self.mutations.is_some()) && self.minimize {
+            command.error("\"--minimize\" doesn't mutate the inputs, it doesn't work with \"--mutations\" or \"--base-files-only\"");
+        }
+    }
+
+    // read by the harness of `fuzz_net!`
+    fn set_net_port(&self, command: &mut Command) {
+        if let Some(port) = self.net_port {
+            command.env("HFND_TCP_PORT", port.to_string());
+        }
+    }
+
+    fn build_args(&self) -> Vec<String> {
+        match self.jobs.or(config::config().jobs) {
+            Some(jobs) => vec!["-j".to_string(), jobs.to_string()],
+            None => vec![],
+        }
+    }
+}
+
+// options of the commands building the targets, read by hfuzz_build
+fn select_build_options(command: &Subcommand, matches: &Matches) {
+    if let Some(sanitizer) = matches.value("--sanitizer") {
+        if let Err(error) = sanitizer::check(sanitizer) {
+            command.error(&error);
+        }
+        sanitizer::select(sanitizer);
+    }
+    if let Some(triple) = matches.value("--target") {
+        let _ = TARGET_TRIPLE.set(triple.to_string());
+    }
+    if matches.flag("--no-default-rustflags") {
+        NO_DEFAULT_RUSTFLAGS.store(true, Ordering::Relaxed);
+    }
+    instrumentation::select(command, matches);
+    features::select(command, matches);
+}
+
+// the same command without some of its arguments, the ones after `--` being left alone
+fn without_args(command: &Command, args: &[&str]) -> Command {
+    let mut filtered = Command::new(command.get_program());
+    let mut after_separator = false;
+    filtered.args(command.get_args().filter(|a| {
+        after_separator |= *a == "--";
+        after_separator || !args.iter().any(|r| a == r)
+    }))
+        .envs(command.get_envs().filter_map(|(key, value)| Some((key, value?))));
+    filtered
+}
+
+// the `--crash-dir`, `--report-dir` and `--log-dir` of the commands fuzzing
+fn select_layout(matches: &Matches) {
+    let dir = |name| matches.value(name).map(PathBuf::from);
+    workspace::select_layout(dir("--crash-dir"), dir("--report-dir"), dir("--log-dir"));
+}
+
+// runs a command on the given CPUs only, `taskset` being given the same list syntax
+fn pinned(command: &Command, cpus: &str) -> Command {
+    let mut pinned = Command::new("taskset");
+    pinned.args(["-c", cpus])
+        .arg(command.get_program())
+        .args(command.get_args())
+        .envs(command.get_envs().filter_map(|(key, value)| Some((key, value?))));
+    pinned
+}
+
+fn hfuzz_run<T>(args: T, crate_root: &Path, build_type: &BuildType) where T: std::iter::Iterator<Item=String> {
+    let command = match *build_type {
+        BuildType::Debug => &RUN_DEBUG,
+        BuildType::ReleaseNotInstrumented => &RUN_NO_INSTR,
+        _ => &RUN,
+    };
+    let run_args: Vec<String> = args.collect();
+    let matches = command.parse(run_args.iter().cloned());
+    let options = RunOptions::parse(command, &matches);
+    select_build_options(command, &matches);
+    if let Some(engine) = matches.value("--engine") {
+        let _ = ENGINE.set(PathBuf::from(engine));
+    }
+    workspace::select_input_dirs(matches.values("--input").into_iter().map(PathBuf::from).collect());
+    select_layout(&matches);
+    let mut args = matches.args.into_iter();
+    let target = args.next().unwrap_or_else(|| command.error("please specify the name of the target"));
+    log::select_target(&target);
+
+    if options.ci && options.max_time.is_none() && options.iterations.is_none() {
+        command.error("\"--ci\" needs a limit like \"--max-time 600\" or \"--iterations 1000000\"");
+    }
+    if options.net_port.is_some() && options.jobs.is_some_and(|jobs| jobs > 1) {
+        command.error("\"--net-port\" fuzzes with one job, every process of honggfuzz would listen on the port");
+    }
+    if options.socket_fuzzer {
+        if *build_type == BuildType::Debug {
+            command.error("\"--socket-fuzzer\" only works when fuzzing");
+        }
+        if options.jobs.is_some_and(|jobs| jobs > 1) || options.android || options.custom_mutator {
+            command.error("\"--socket-fuzzer\" fuzzes with one job, without \"--android\" or \"--custom-mutator\"");
+        }
+        if let Some(driver) = &options.driver {
+            socket_fuzzer::select_driver(driver);
+        }
+    }
+    if options.iterations == Some(0) {
+        command.error("\"--iterations\" needs a positive number, honggfuzz taking 0 as no limit");
+    }
+    if (options.base_files_only || options.mutations.is_some()) && *build_type == BuildType::Debug {
+        command.error("\"--mutations\" and \"--base-files-only\" only work when fuzzing");
+    }
+    options.check_mutations(command);
+    if let Some(dict) = &options.dict {
+        dict::check_dict(dict);
+    }
+    if options.rr && *build_type != BuildType::Debug {
+        command.error("\"--rr\" only works with run-debug");
+    }
+    if options.watch && (*build_type == BuildType::Debug || options.ci) {
+        command.error("\"--watch\" only works when fuzzing, without \"--ci\"");
+    }
+    if options.custom_mutator && *build_type == BuildType::Debug {
+        command.error("\"--custom-mutator\" only works when fuzzing");
+    }
+    if options.minimize {
+        if *build_type == BuildType::Debug {
+            command.error("\"--minimize\" only works with run and run-no-instr");
+        }
+        if options.ci || options.watch || options.android || options.socket_fuzzer || options.custom_mutator {
+            command.error("\"--minimize\" doesn't work with \"--ci\", \"--watch\", \"--android\", \"--socket-fuzzer\" or \"--custom-mutator\"");
+        }
+    }
+    if options.android {
+        if *build_type == BuildType::Debug {
+            command.error("\"--android\" only works when fuzzing");
+        }
+        if options.ci || options.watch || options.custom_mutator || options.pin_cpus.is_some() {
+            command.error("\"--android\" doesn't work with \"--ci\", \"--watch\", \"--custom-mutator\" or \"--pin-cpus\"");
+        }
+        // only the workspace is pulled back from the device
+        if !workspace::layout_dirs(&target).is_empty() {
+            command.error("\"--android\" keeps the crashes, reports and logs in the workspace, without other directories for them");
+        }
+        if TARGET_TRIPLE.get().is_none() && config::config().target.is_none() {
+            let _ = TARGET_TRIPLE.set(android::DEFAULT_TRIPLE.to_string());
+        }
+        if !android::is_android(&target_triple()) {
+            command.error(&format!("\"--android\" needs an Android target, not \"{}\"", target_triple()));
+        }
+    }
+
+    let build_args: Vec<String> = vec!["--bin".to_string(), target.clone()].into_iter().chain(options.build_args()).collect();
+    // in watch mode the builds come with the restarts
+    if !options.watch || log::dry_run() {
+        hfuzz_build(build_args.iter().cloned(), crate_root, build_type);
+    }
+
+    match *build_type {
+        BuildType::Debug => {
+            let crash_filename = args.next().map(PathBuf::from).unwrap_or_else(|| {
+                let newest = workspace::newest_crash_file(&target).unwrap_or_else(|| {
+                    command.error(&format!("no crash file in \"{}\", please specify the crash filename", workspace::target_workspace(&target).display()))
+                });
+                println!("replaying the most recent crash file \"{}\"", newest.display());
+                newest
+            });
+            // a directory is triaged file by file by the debug harness, in a single run
+            match crash_filename.is_dir() {
+                true => features::check_replay(&target, &workspace::files_in(&crash_filename)),
+                false => features::check_replay(&target, std::slice::from_ref(&crash_filename)),
+            }
+
+            // rr records the crash first, the debugger comes with the replay
+            let trace_dir = workspace::target_workspace(&target).join("rr").join(crash_filename.file_name().unwrap_or_default());
+            let mut command = match options.rr {
+                true => debugger::rr_record(&trace_dir, &debug_binary(&target)),
+                false => debugger_command(&target),
+            };
+            command.args(args)
+                .env("CARGO_HONGGFUZZ_CRASH_FILENAME", crash_filename)
+                .env("RUST_BACKTRACE", env::var("RUST_BACKTRACE").unwrap_or_else(|_| "1".into()));
+            if let Some(timeout) = options.timeout.or(config::timeout(&target)) {
+                command.env("CARGO_HONGGFUZZ_TIMEOUT", timeout.to_string()); // enforced by the debug harness
+            }
+            if let Some(max_len) = options.max_len.or(config::max_len(&target)) {
+                command.env("CARGO_HONGGFUZZ_MAX_LEN", max_len.to_string());
+            }
+            options.set_net_port(&mut command);
+            sanitizer::set_options(&mut command, true);
+            if log::dry_run() {
+                return log::preview(&command);
+            }
+            let status = log::status(&mut command).unwrap_or_else(|error| {
+                eprintln!("error: failed to run \"{}\" ({})", command.get_program().to_string_lossy(), error);
+                process::exit(1);
+            });
+            if options.rr {
+                println!("recorded the execution in \"{}\", replaying it", trace_dir.display());
+                let status = log::status(&mut debugger::rr_replay(&trace_dir)).unwrap_or_else(|error| {
+                    eprintln!("error: failed to run \"rr replay\" ({})", error);
+                    process::exit(1);
+                });
+                process::exit(status.code().unwrap_or(1));
+            }
+            if !status.success() {
+                 process::exit(status.code().unwrap_or(1));
+            }
+        }
+        _ => {
+            let args: Vec<String> = args.collect();
+            let mut honggfuzz_args = options.honggfuzz_args();
+            if options.custom_mutator {
+                honggfuzz_args.extend(["--pprocess_cmd".to_string(), custom_mutator_script(&target).to_string_lossy().into_owned()]);
+            }
+            // one fuzzing thread per CPU of the device, not of the host
+            if options.android && options.jobs.is_none() && config::config().jobs.is_none() && !log::dry_run() {
+                honggfuzz_args.extend(android::device_jobs());
+            }
+            if options.minimize {
+                honggfuzz_args.extend(merge::minimize_args(&target));
+            }
+            let honggfuzz = || {
+                let mut command = honggfuzz_command(&target, &honggfuzz_args, args.iter().cloned());
+                if options.keep_going {
+                    command = without_args(&command, &["--exit_upon_crash"]);
+                }
+                // the target is a server run once, not a persistent harness
+                if options.socket_fuzzer {
+                    command = without_args(&command, &["-P"]);
+                }
+                if let Some(max_len) = options.max_len {
+                    command.env("CARGO_HONGGFUZZ_MAX_LEN", max_len.to_string()); // read by the harness
+                }
+                options.set_net_port(&mut command);
+                if let Some(cpus) = &options.pin_cpus {
+                    command = pinned(&command, cpus);
+                }
+                command
+            };
+            if log::dry_run() {
+                return match options.android {
+                    true => log::preview(&android::shell(&target, &honggfuzz())),
+                    false => log::preview(&honggfuzz()),
+                };
+            }
+            if options.minimize {
+                return merge::minimize(&target, honggfuzz());
+            }
+            resume::record(command.name, &target, &run_args);
+            if options.android {
+                let status = android::fuzz(&target, &honggfuzz());
+                process::exit(status.code().unwrap_or(1));
+            }
+            if options.ci {
+                ci::run(&target, honggfuzz(), options.max_crashes, &options.policy);
+            }
+            let session = match options.watch {
+                true => watch::fuzz(&target, || build(build_args.iter().cloned(), crate_root, build_type).success(), honggfuzz, options.max_crashes),
+                false => session::fuzz(&target, honggfuzz(), options.max_crashes, None),
+            };
+            // interrupted, the session is over and so is the run
+            if let Some(signal) = session::stopped_by() {
+                process::exit(128 + signal);
+            }
+            if options.post_process && !session.new_crash_files.is_empty() {
+                triage::post_process(&target, crate_root);
+            }
+            if !session.status.success() {
+                process::exit(session.status.code().unwrap_or(1));
+            }
+            options.policy.exit(session.crashes(), session.hangs);
+        }
+    }
+}
+
+// cross-compiled engines run through an emulator like `qemu-aarch64`, or whatever the user wants
+fn runner() -> Vec<String> {
+    match env::var_os("HFUZZ_RUNNER") {
+        Some(_) => cli::env_args("HFUZZ_RUNNER"),
+        None => config::config().runner.clone(),
+    }
+}
+
+// honggfuzz runs `--pprocess_cmd` with only the path of a file holding the mutated input, which the
+// script gives the target with CARGO_HONGGFUZZ_MUTATE set, for its `custom_mutator` to rewrite it
+fn custom_mutator_script(target: &str) -> PathBuf {
+    let script = workspace::target_workspace(target).join("custom-mutator.sh");
+    if log::dry_run() {
+        return script;
+    }
+    let binary = release_binary(target);
+    let command: Vec<String> = runner().iter().map(|a| log::quoted(a.as_ref()))
+        .chain([log::quoted(binary.as_os_str())])
+        .collect();
+    let written = fs::create_dir_all(workspace::target_workspace(target))
+        .and_then(|_| fs::write(&script, format!("#!/bin/sh\nCARGO_HONGGFUZZ_MUTATE=1 exec {} \"$@\"\n", command.join(" "))))
+        .and_then(|_| fs::set_permissions(&script, fs::Permissions::from_mode(0o755)));
+    if let Err(error) = written {
+        eprintln!("error: failed to write \"{}\" ({})", script.display(), error);
+        process::exit(1);
+    }
+    script
+}
+
+// honggfuzz fuzzing the release build of a target, `args` being given to the target
+fn honggfuzz_command<T>(target: &str, honggfuzz_args: &[String], args: T) -> Command where T: std::iter::Iterator<Item=String> {
+    engine_command(target, honggfuzz_args, &release_binary(target), args)
+}
+
+// honggfuzz fuzzing `binary` in the workspace of `target`, with its settings
+fn engine_command<T>(target: &str, honggfuzz_args: &[String], binary: &Path, args: T) -> Command where T: std::iter::Iterator<Item=String> {
+    let honggfuzz_workspace = workspace::target_workspace(target);
+    let input_dirs = workspace::input_dirs(target);
+    let config = config::config();
+    let target_config = config::target_config(target);
+
+    // get user-defined args for honggfuzz
+    let hfuzz_run_args = cli::env_args("HFUZZ_RUN_ARGS");
+
+    // one fuzzing thread per CPU by default, honggfuzz alone would only use half of them
+    let threads = config.jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+
+    // settings of the target's Cargo.toml and hfuzz.toml, HFUZZ_RUN_ARGS and the command line options
+    // come after them
+    let mut config_args = Vec::new();
+    if let Some(timeout) = config::timeout(target) {
+        config_args.extend(["-t".to_string(), timeout.to_string()]);
+    }
+    if let Some(dict) = config::dict(target) {
+        dict::check_dict(dict);
+        config_args.extend(["-w".to_string(), dict.to_string_lossy().into_owned()]);
+    }
+    let max_len = config::max_len(target);
+    if let Some(max_len) = max_len {
+        config_args.extend(["-F".to_string(), max_len.to_string()]);
+    }
+    if let Some(mutations) = config::mutations(target) {
+        config_args.extend(["-r".to_string(), mutations.to_string()]);
+    }
+
+    // the crashes and the report of honggfuzz go to the workspace unless other directories are chosen
+    let crash_dir = workspace::crash_dir(target);
+    let report_dir = workspace::report_dir(target);
+    let mut layout_args = Vec::new();
+    if crash_dir != honggfuzz_workspace {
+        layout_args.extend(["--crashdir".to_string(), crash_dir.to_string_lossy().into_owned()]);
+    }
+    if report_dir != honggfuzz_workspace {
+        layout_args.extend(["-R".to_string(), report_dir.join(workspace::REPORT_FILE).to_string_lossy().into_owned()]);
+    }
+
+    let seeds = config::seeds(target);
+    // nothing is written with `--dry-run`
+    if !log::dry_run() {
+        migrate::check_layout();
+        for dir in [&input_dirs[0], &crash_dir, &report_dir] {
+            fs::create_dir_all(dir).unwrap_or_else(|_| {
+                println!("error: failed to create \"{}\"", dir.display());
+            });
+        }
+        if !seeds.is_empty() {
+            let seeded = workspace::seed_corpus(target, &seeds, config::link_seeds(target));
+            if seeded > 0 {
+                println!("{}: added {} seed inputs to the corpus", target, seeded);
+            }
+        }
+        // the ones of a session which didn't end well
+        let added = workspace::import_added(target);
+        if added > 0 {
+            println!("{}: added {} inputs of add_to_corpus to the corpus", target, added);
+        }
+        let _ = fs::create_dir_all(workspace::added_dir(target));
+    }
+
+    let runner = runner();
+    let engine = engine();
+    let mut command = match runner.split_first() {
+        Some((program, runner_args)) => {
+            let mut command = Command::new(program);
+            command.args(runner_args).arg(engine);
+            command
+        }
+        None => Command::new(engine),
+    };
+    // with several input directories the new inputs are saved in the first one, the corpus
+    let corpus = input_dirs[0].as_os_str();
+    let input_args = match input_dirs.len() {
+        1 => vec!["-f".into(), corpus.to_os_string()],
+        _ => vec!["-f".into(), workspace::stage_inputs(target, &input_dirs).into_os_string(), "--output".into(), corpus.to_os_string()],
+    };
+    command.args(["-W", &honggfuzz_workspace.to_string_lossy()]).args(input_args).arg("-P")
+        .args(["-n", &threads.to_string()])
+        .args(layout_args)
+        .args(config_args)
+        .args(&config.run_args)
+        .args(&target_config.run_args)
+        .args(hfuzz_run_args) // allows user-specified arguments to be given to honggfuzz
+        .args(honggfuzz_args) // set by cargo hfuzz's own options, they take precedence
+        .arg("--").arg(binary)
+        .args(args);
+    sanitizer::set_options(&mut command, false);
+    if let Some(max_len) = max_len {
+        command.env("CARGO_HONGGFUZZ_MAX_LEN", max_len.to_string()); // read by the harness
+    }
+    // for `honggfuzz::add_to_corpus`, wherever the target runs from
+    let added_dir = workspace::added_dir(target);
+    command.env("CARGO_HONGGFUZZ_ADDED_DIR", env::current_dir().map_or(added_dir.clone(), |dir| dir.join(&added_dir)));
+    dict::with_embedded_tokens(target, binary, command)
+}
+
+// `cargo build` arguments selecting the packages of the targets, which can be other workspace members
+// than the one of the current directory
+fn package_args(args: &[String]) -> Vec<String> {
+    let mut packages: Vec<&str> = args.windows(2)
+        .filter(|w| w[0] == "--bin")
+        .filter_map(|w| metadata::find_target(&w[1]))
+        .map(|t| t.package.as_str())
+        .collect();
+    if packages.is_empty() {
+        packages.extend(metadata::selected_package());
+    }
+    packages.sort();
+    packages.dedup();
+    packages.into_iter().flat_map(|p| ["-p".to_string(), p.to_string()]).collect()
+}
+
+fn hfuzz_build<T>(args: T, crate_root: &Path, build_type: &BuildType) where T: std::iter::Iterator<Item=String> {
+    let status = build(args, crate_root, build_type);
+    if !status.success() {
+         process::exit(status.code().unwrap_or(1));
+    }
+}
+
+// builds like hfuzz_build, telling how it went instead of exiting when it fails
+fn build<T>(args: T, crate_root: &Path, build_type: &BuildType) -> ExitStatus where T: std::iter::Iterator<Item=String> {
+    let mut command = cargo_build(args, crate_root, build_type);
+    if log::dry_run() {
+        log::preview(&command);
+        return ExitStatus::default();
+    }
+    if cross_compiling() {
+        let _ = fs::create_dir_all(engine_dir());
+    }
+
+    let cargo_args: Vec<String> = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    log::event("build_started", vec![("args", cargo_args.into())]);
+    let status = log::status(&mut command).unwrap();
+    log::event("build_finished", vec![("success", status.success().into()), ("exit_code", status.code().into())]);
+    // cargo doesn't show the warnings of the build script of a dependency
+    if status.success() && *build_type == BuildType::ReleaseInstrumented {
+        for limitation in engine_limitations() {
+            eprintln!("warning: the honggfuzz engine was built {}", limitation);
+        }
+        if ENGINE.get().is_none() {
+            install_engine();
+        }
+    }
+    status
+}
+
+// the cargo build of the targets, with the RUSTFLAGS and the environment of `build_type`
+fn cargo_build<T>(args: T, crate_root: &Path, build_type: &BuildType) -> Command where T: std::iter::Iterator<Item=String> {
+    let args: Vec<String> = args.collect();
+    let honggfuzz_target = workspace::target_dir();
+    let cargo_target_dir = match *build_type {
+        BuildType::Coverage => coverage_target_dir(),
+        BuildType::Bench => bench_target_dir(),
+        _ => PathBuf::from(&honggfuzz_target),
+    };
+
+    // HACK: temporary fix, see https://github.com/rust-lang/rust/issues/53945#issuecomment-426824324
+    let use_gold_linker: bool = match log::status(Command::new("which") // check if the gold linker is available
+            .args(["ld.gold"])
+            .stdout(Stdio::null())) { // keep stdout clean for the machine-readable outputs
+        Err(_) => false,
+        Ok(status) => matches!(status.code(), Some(0))
+    };
+
+    // the cfgs tell the targets how they are built, they are kept without the default flags
+    let mut rustflags = "--cfg fuzzing ".to_string();
+    let mut defaults = "\
+    -C debug-assertions \
+    -C overflow_checks \
+    ".to_string();
+    
+    let mut cargo_incremental = "1";
+    match *build_type {
+        BuildType::Debug => {
+            rustflags.push_str("--cfg fuzzing_debug ");
+            defaults.push_str("\
+            -C opt-level=0 \
+            -C debuginfo=2 \
+            ");
+        }
+
+        BuildType::ProfileWithGrcov => {
+            rustflags.push_str("--cfg fuzzing_debug ");
+            defaults.push_str("\
+            -Zprofile \
+            -Cpanic=abort \
+            -C opt-level=0 \
+            -C debuginfo=2 \
+            -Ccodegen-units=1 \
+            -Cinline-threshold=0 \
+            -Clink-dead-code \
+            ");
+            //-Coverflow-checks=off \
+            cargo_incremental = "0";
+        }
+
+        BuildType::Coverage => {
+            rustflags.push_str("--cfg fuzzing_debug ");
+            defaults.push_str("\
+            -C instrument-coverage \
+            -C opt-level=0 \
+            -C debuginfo=2 \
+            ");
+        }
+
+        _ => {
+            defaults.push_str("\
+            -C opt-level=3 \
+            -C debuginfo=0 \
+            ");
+            // the CPU of the host says nothing about the one of another platform
+            if runs_on_host() {
+                defaults.push_str("-C target-cpu=native ");
+            }
+
+            if *build_type == BuildType::Bench {
+                rustflags.push_str("--cfg fuzzing_bench ");
+            }
+
+            if *build_type == BuildType::ReleaseInstrumented || *build_type == BuildType::Bench {
+                defaults.push_str(&sancov_rustflags());
+                defaults.push_str(&instrumentation::rustflags());
+
+                // HACK: temporary fix, see https://github.com/rust-lang/rust/issues/53945#issuecomment-426824324
+                if use_gold_linker && !cross_compiling() {
+                    defaults.push_str("-Clink-arg=-fuse-ld=gold ");
+                }
+            }
+        }
+    }
+    if !no_default_rustflags() {
+        rustflags.push_str(&defaults);
+    }
+
+    // needs a nightly toolchain
+    let sanitizer = sanitizer::selected();
+    if let Some(sanitizer) = sanitizer {
+        if !log::dry_run() {
+            sanitizer::check_toolchain(sanitizer, &target_triple());
+        }
+        rustflags.push_str(&sanitizer::rustflags(sanitizer));
+    }
+
+    // add user provided flags
+    rustflags.push_str(&env::var("RUSTFLAGS").unwrap_or_default());
+
+    // get user-defined args for building
+    let hfuzz_build_args = cli::env_args("HFUZZ_BUILD_ARGS");
+    // cargo doesn't tell build.rs that it is offline, the variable does, for it to download nothing
+    let offline = args.iter().chain(&config::config().build_args).chain(&hfuzz_build_args).any(|a| a == "--offline" || a == "--frozen");
+
+    let engine_dir = engine_dir();
+    let cargo_bin = env::var("CARGO").unwrap();
+    let mut command = Command::new(cargo_bin);
+    command.args(["build", "--target", &target_triple()]) // HACK to avoid building build scripts with rustflags
+        .args(package_args(&args))
+        .args(args)
+        .args(sanitizer.map_or(&[][..], sanitizer::cargo_args))
+        .args(features::selected().cargo_args())
+        .args(&config::config().build_args) // from hfuzz.toml
+        .args(hfuzz_build_args) // allows user-specified arguments to be given to cargo build
+        .env("RUSTFLAGS", rustflags)
+        .env("CARGO_INCREMENTAL", cargo_incremental)
+        .env("CARGO_TARGET_DIR", &cargo_target_dir) // change target_dir to not clash with regular builds
+        .env("CRATE_ROOT", crate_root);
+    // the clang of the NDK links the Android targets, unless cargo is given another linker
+    if android::is_android(&target_triple()) {
+        let linker_variable = format!("CARGO_TARGET_{}_LINKER", target_triple().to_uppercase().replace('-', "_"));
+        if let (None, Some(linker)) = (env::var_os(&linker_variable), android::linker(&target_triple())) {
+            command.env(linker_variable, linker);
+        }
+    }
+    
+    if *build_type == BuildType::ProfileWithGrcov {
+        command.env("CARGO_HONGGFUZZ_BUILD_VERSION", VERSION)   // used by build.rs to check that versions are in sync
+            .env("CARGO_HONGGFUZZ_TARGET_DIR", &engine_dir); // env variable to be read by build.rs script 
+    }                                                        // to place honggfuzz executable at a known location
+    else if *build_type != BuildType::Debug && *build_type != BuildType::Coverage {
+        command.arg("--release")
+            .env("CARGO_HONGGFUZZ_BUILD_VERSION", VERSION)   // used by build.rs to check that versions are in sync
+            .env("CARGO_HONGGFUZZ_TARGET_DIR", &engine_dir); // env variable to be read by build.rs script 
+    }                                                        // to place honggfuzz executable at a known location
+    if let Some(engine_version) = &config::config().engine_version {
+        command.env("CARGO_HONGGFUZZ_ENGINE_VERSION", engine_version); // release build.rs builds instead of the bundled one
+    }
+    if offline {
+        command.env("CARGO_NET_OFFLINE", "true");
+    }
+    if log::quiet() {
+        command.arg("--quiet");
+    }
+    command
+}
+
+// removes the binaries of a target from every build profile, leaving the dependencies built
+fn clean_target(target: &str) -> usize {
+    let deps_prefix = format!("{}-", target.replace('-', "_"));
+    let mut removed = 0;
+    for target_dir in [PathBuf::from(workspace::target_dir()), coverage_target_dir()] {
+        for profile in ["release", "debug"] {
+            let profile_dir = target_dir.join(target_triple()).join(profile);
+            let artifacts = vec![profile_dir.join(target), profile_dir.join(format!("{}.d", target))].into_iter()
+                .chain(workspace::files_in(&profile_dir.join("deps")).into_iter()
+                    .filter(|f| f.file_name().is_some_and(|n| n.to_string_lossy().starts_with(&deps_prefix))));
+            for artifact in artifacts {
+                if fs::remove_file(&artifact).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+    }
+    removed
+}
+
+// "cargo hfuzz build" and its variants, the other arguments being given to cargo
+fn hfuzz_build_command<T>(command: &Subcommand, args: T, crate_root: &Path, build_type: &BuildType) where T: std::iter::Iterator<Item=String> {
+    let matches = command.parse(args);
+    select_build_options(command, &matches);
+    // the build of "cargo hfuzz coverage", in its own target directory not to invalidate the fuzzing one
+    let build_type = match matches.value("--profile") {
+        Some("coverage") if command.name == BUILD.name => &BuildType::Coverage,
+        Some("coverage") => command.error("\"--profile\" only applies to \"cargo hfuzz build\""),
+        Some(profile) => command.error(&format!("unknown profile \"{}\", expected \"coverage\"", profile)),
+        None => build_type,
+    };
+    // `--all-targets` means all the fuzz targets, not cargo's tests, benches and examples
+    let targets = if matches.flag("--all-targets") { bin_args(&metadata::fuzz_target_names()) } else { vec![] };
+    hfuzz_build(targets.into_iter().chain(matches.args), crate_root, build_type);
+}
+
+fn hfuzz_clean<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = CLEAN.parse(args);
+    let clean_workspace = matches.flag("--workspace");
+    let mut args = matches.args.into_iter().peekable();
+    if let Some(target) = args.next_if(|a| !a.starts_with('-')) {
+        if let Some(arg) = args.next() {
+            CLEAN.error(&format!("unexpected argument \"{}\"", arg));
+        }
+
+        println!("removed {} build artifacts of \"{}\"", clean_target(&target), target);
+        // the corpus, the crashes and the logs, wherever they are
+        if clean_workspace {
+            for dir in Some(workspace::target_workspace(&target)).into_iter().chain(workspace::layout_dirs(&target)) {
+                if dir.exists() {
+                    fs::remove_dir_all(&dir).unwrap_or_else(|_| {
+                        eprintln!("error: failed to remove \"{}\"", dir.display());
+                        process::exit(1);
+                    });
+                    println!("removed \"{}\"", dir.display());
+                }
+            }
+        }
+        return;
+    }
+    if clean_workspace {
+        CLEAN.error("\"--workspace\" needs a TARGET");
+    }
+
+    let honggfuzz_target = workspace::target_dir();
+    let cargo_bin = env::var("CARGO").unwrap();
+    let status = log::status(Command::new(cargo_bin)
+        .args(["clean"])
+        .args(args)
+        .env("CARGO_TARGET_DIR", &honggfuzz_target)) // change target_dir to not clash with regular builds
+        .unwrap();
+    if !status.success() {
+         process::exit(status.code().unwrap_or(1));
+    }
+}
+
+fn hfuzz_list<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = LIST.parse(args);
+    if let Some(arg) = matches.args.first() {
+        LIST.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    let json = matches.flag("--json");
+
+    let targets: Vec<(&metadata::Target, usize, usize)> = metadata::metadata().fuzz_targets()
+        .map(|t| (t, workspace::corpus_files(&t.name).len(), workspace::crash_files(&t.name).len()))
+        .collect();
+
+    if json {
+        let targets = targets.iter().map(|(t, corpus, crashes)| Json::object(vec![
+            ("name", t.name.as_str().into()),
+            ("package", t.package.as_str().into()),
+            ("src_path", t.src_path.to_string_lossy().into_owned().into()),
+            ("workspace", workspace::target_workspace(&t.name).to_string_lossy().into_owned().into()),
+            ("corpus", (*corpus).into()),
+            ("crashes", (*crashes).into()),
+        ])).collect::<Vec<_>>();
+        println!("{}", Json::Array(targets));
+        return;
+    }
+
+    let width = targets.iter().map(|(t, _, _)| t.name.len()).chain(Some("TARGET".len())).max().unwrap();
+    let package_width = targets.iter().map(|(t, _, _)| t.package.len()).chain(Some("PACKAGE".len())).max().unwrap();
+    println!("{:width$}  {:package_width$}  {:>8}  {:>8}", "TARGET", "PACKAGE", "CORPUS", "CRASHES");
+    for (t, corpus, crashes) in &targets {
+        println!("{:width$}  {:package_width$}  {:>8}  {:>8}", t.name, t.package, corpus, crashes);
+    }
+}
+
+fn unknown_command(name: &str) -> ! {
+    match cli::suggestion(name, COMMANDS.iter().map(|c| c.name)) {
+        Some(command) => eprintln!("error: unknown command \"{}\", did you mean \"{}\"?", name, command),
+        None => eprintln!("error: unknown command \"{}\"", name),
+    }
+    eprintln!("see \"cargo hfuzz help\" for the list of commands");
+    process::exit(1);
+}
+
+fn main() {
+    // the arguments are parsed by the `cli` module, which keeps cargo-hfuzz free of dependencies
+    let mut args = env::args().skip(1);
+    if args.next() != Some("hfuzz".to_string()) {
+        eprintln!("please launch as a cargo subcommand: \"cargo hfuzz ...\"");
+        process::exit(1);
+    }
+
+    // `-v`, `-q`, `--log-format`, `--no-color` and `--dry-run`, before the command, apply to all of them
+    let mut command = args.next();
+    loop {
+        match command.as_deref() {
+            Some("--log-format") => log::select_format(args.next().as_deref()),
+            Some(arg) if arg.starts_with("--log-format=") => log::select_format(arg.strip_prefix("--log-format=")),
+            Some("--no-color") => summary::disable_colors(),
+            Some("--dry-run") => log::enable_dry_run(),
+            Some(arg) if log::parse_verbosity(arg) => {}
+            _ => break,
+        }
+        command = args.next();
+    }
+
+    // help, doctor and completions don't need a crate
+
+    match command.as_deref() {
+        None => {
+            cli::print_commands(COMMANDS);
+            process::exit(1);
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            match args.next() {
+                Some(name) => match COMMANDS.iter().find(|c| c.name == name) {
+                    Some(command) => command.print_help(),
+                    None => unknown_command(&name),
+                },
+                None => cli::print_commands(COMMANDS),
+            }
+            return;
+        }
+        Some("doctor") => {
+            doctor::hfuzz_doctor(args);
+            return;
+        }
+        Some("completions") => {
+            completions::hfuzz_completions(args, COMMANDS);
+            return;
+        }
+        Some(name) if !COMMANDS.iter().any(|c| c.name == name) => unknown_command(name),
+        Some(name) if log::dry_run() && !log::DRY_RUN_COMMANDS.contains(&name) => {
+            eprintln!("error: \"--dry-run\" only works with the {} commands", log::DRY_RUN_COMMANDS.join(", "));
+            process::exit(1);
+        }
+        _ => {}
+    }
+
+    // change to crate root to have the same behavior as cargo build/run
+    let crate_root = find_crate_root().unwrap_or_else(|| {
+        eprintln!("error: could not find `Cargo.toml` in current directory or any parent directory");
+        process::exit(1);
+    });
+    env::set_current_dir(&crate_root).unwrap();
+
+    // `-p PACKAGE` selects the workspace member of the targets, for every command
+    let mut args = args.collect::<Vec<_>>();
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    if let Some(i) = args[..end].iter().position(|a| a == "-p" || a == "--package" || a.starts_with("--package=")) {
+        let package = match args.remove(i).strip_prefix("--package=") {
+            Some(package) => package.to_string(),
+            None if i < end - 1 => args.remove(i),
+            None => {
+                eprintln!("error: \"-p\" expects the name of a package");
+                process::exit(1);
+            }
+        };
+        metadata::select_package(&package);
+    }
+    let args = args.into_iter();
+
+    match command {
+        Some(ref s) if s == "build" => {
+            hfuzz_build_command(&BUILD, args, &crate_root, &BuildType::ReleaseInstrumented);
+        }
+        Some(ref s) if s == "build-no-instr" => {
+            hfuzz_build_command(&BUILD_NO_INSTR, args, &crate_root, &BuildType::ReleaseNotInstrumented);
+        }
+        Some(ref s) if s == "build-debug" => {
+            hfuzz_build_command(&BUILD_DEBUG, args, &crate_root, &BuildType::Debug);
+        }
+        Some(ref s) if s == "build-grcov" => {
+            hfuzz_build_command(&BUILD_GRCOV, args, &crate_root, &BuildType::ProfileWithGrcov);
+        }
+        Some(ref s) if s == "run" => {
+            hfuzz_run(args, &crate_root, &BuildType::ReleaseInstrumented);
+        }
+        Some(ref s) if s == "run-all" => {
+            run_all::hfuzz_run_all(args, &crate_root);
+        }
+        Some(ref s) if s == "run-parallel" => {
+            parallel::hfuzz_run_parallel(args, &crate_root);
+        }
+        Some(ref s) if s == "run-binary" => {
+            binary::hfuzz_run_binary(args);
+        }
+        Some(ref s) if s == "run-no-instr" => {
+            hfuzz_run(args, &crate_root, &BuildType::ReleaseNotInstrumented);
+        }
+
+        Some(ref s) if s == "run-debug" => {
+            hfuzz_run(args, &crate_root, &BuildType::Debug);
+        }
+        Some(ref s) if s == "print-env" => {
+            print_env::hfuzz_print_env(args, &crate_root);
+        }
+        Some(ref s) if s == "clean" => {
+            hfuzz_clean(args);
+        }
+        Some(ref s) if s == "version" => {
+            hfuzz_version(args);
+        }
+        Some(ref s) if s == "init" => {
+            scaffold::hfuzz_init(args);
+        }
+        Some(ref s) if s == "add" => {
+            scaffold::hfuzz_add(args);
+        }
+        Some(ref s) if s == "list" => {
+            hfuzz_list(args);
+        }
+        Some(ref s) if s == "tmin" => {
+            tmin::hfuzz_tmin(args, &crate_root);
+        }
+        Some(ref s) if s == "triage" => {
+            triage::hfuzz_triage(args, &crate_root);
+        }
+        Some(ref s) if s == "gen-test" => {
+            gen_test::hfuzz_gen_test(args);
+        }
+        Some(ref s) if s == "replay" => {
+            replay::hfuzz_replay(args, &crate_root);
+        }
+        Some(ref s) if s == "migrate" => {
+            migrate::hfuzz_migrate(args);
+        }
+        Some(ref s) if s == "verify" => {
+            verify::hfuzz_verify(args, &crate_root);
+        }
+        Some(ref s) if s == "merge" => {
+            merge::hfuzz_merge(args, &crate_root);
+        }
+        Some(ref s) if s == "corpus" => {
+            corpus::hfuzz_corpus(args);
+        }
+        Some(ref s) if s == "stats" => {
+            stats::hfuzz_stats(args);
+        }
+        Some(ref s) if s == "resume" => {
+            resume::hfuzz_resume(args, &crate_root);
+        }
+        Some(ref s) if s == "bench" => {
+            bench::hfuzz_bench(args, &crate_root);
+        }
+        Some(ref s) if s == "dashboard" => {
+            dashboard::hfuzz_dashboard(args);
+        }
+        Some(ref s) if s == "coverage" => {
+            coverage::hfuzz_coverage(args, &crate_root);
+        }
+        _ => unreachable!(),
+    }
+}
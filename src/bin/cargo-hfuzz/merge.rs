@@ -0,0 +1,188 @@
+use std::fs;
+use std::process::{self, Command};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::{BuildType, hfuzz_build, honggfuzz_command};
+use crate::cli::Subcommand;
+use crate::log;
+use crate::session;
+use crate::workspace::{self, content_hash};
+
+pub const MERGE: Subcommand = Subcommand {
+    name: "merge",
+    usage: "TARGET DIR ...",
+    about: "adds to the corpus of a target the inputs of other directories bringing new coverage",
+    options: &[],
+    passthrough: None,
+};
+
+fn create_empty_dir(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", dir.display());
+        process::exit(1);
+    });
+}
+
+fn copy(from: &Path, to: &Path) {
+    fs::copy(from, to).unwrap_or_else(|_| {
+        eprintln!("error: failed to copy \"{}\" to \"{}\"", from.display(), to.display());
+        process::exit(1);
+    });
+}
+
+pub fn hfuzz_merge<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let mut args = MERGE.parse(args).args.into_iter();
+    let target = args.next().unwrap_or_else(|| MERGE.error("please specify the name of the target"));
+    let dirs: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    log::select_target(&target);
+    if dirs.is_empty() {
+        MERGE.error("please specify the directories to import");
+    }
+    if let Some(dir) = dirs.iter().find(|d| !d.is_dir()) {
+        eprintln!("error: \"{}\" is not a directory", dir.display());
+        process::exit(1);
+    }
+
+    // the corpus and the imported inputs are put together and minimized by honggfuzz,
+    // what survives and isn't already in the corpus brings new coverage
+    let corpus = workspace::corpus_files(&target);
+    let known: HashSet<u64> = corpus.iter().filter_map(|f| content_hash(f)).collect();
+
+    let merge_dir = workspace::target_workspace(&target).join(".merge");
+    let staging_dir = merge_dir.join("input");
+    let minimized_dir = merge_dir.join("output");
+    create_empty_dir(&staging_dir);
+    create_empty_dir(&minimized_dir);
+
+    for (i, file) in corpus.iter().enumerate() {
+        copy(file, &staging_dir.join(format!("corpus-{}", i)));
+    }
+    let imported: Vec<PathBuf> = dirs.iter().flat_map(|d| workspace::files_in(d)).collect();
+    for (i, file) in imported.iter().enumerate() {
+        copy(file, &staging_dir.join(format!("import-{}", i)));
+    }
+    // honggfuzz's threads can also save a few inputs of their own before the minimization ends
+    let imported_hashes: HashSet<u64> = imported.iter().filter_map(|f| content_hash(f)).collect();
+    log::note(&format!("merging {} inputs into the {} inputs of the \"{}\" corpus", imported.len(), corpus.len(), target));
+
+    hfuzz_build(vec!["--bin".to_string(), target.clone()].into_iter(), crate_root, &BuildType::ReleaseInstrumented);
+
+    let minimize_args: Vec<String> = vec![
+        "-M".into(),
+        "-i".into(), staging_dir.to_string_lossy().into_owned(),
+        "-o".into(), minimized_dir.to_string_lossy().into_owned(),
+        "--logfile".into(), merge_dir.join("honggfuzz.log").to_string_lossy().into_owned(),
+    ];
+    let mut command = honggfuzz_command(&target, &minimize_args, std::iter::empty());
+    let status = log::status(&mut command).unwrap_or_else(|error| {
+        eprintln!("cannot execute {} ({}), try to execute \"cargo hfuzz build\" from fuzzed project directory", command.get_program().to_string_lossy(), error);
+        process::exit(1);
+    });
+    let log_file = merge_dir.join("honggfuzz.log");
+    let status = session::checked(&target, status, &log_file);
+    if !status.success() {
+        eprintln!("error: honggfuzz failed to minimize the merged corpus, see \"{}\"", log_file.display());
+        process::exit(status.code().unwrap_or(1));
+    }
+    let minimized = workspace::files_in(&minimized_dir);
+    if minimized.is_empty() && corpus.len() + imported.len() > 0 {
+        eprintln!("error: honggfuzz kept none of the inputs, nothing is imported, see \"{}\"", log_file.display());
+        process::exit(1);
+    }
+
+    let input_dir = workspace::input_dir(&target);
+    let mut added = 0;
+    for file in minimized {
+        let is_new = content_hash(&file).is_some_and(|h| !known.contains(&h) && imported_hashes.contains(&h));
+        let destination = input_dir.join(file.file_name().unwrap());
+        if is_new && !destination.exists() {
+            copy(&file, &destination);
+            added += 1;
+        }
+    }
+    let _ = fs::remove_dir_all(&merge_dir);
+
+    println!("{}: {} of {} imported inputs added new coverage", target, added, imported.len());
+}
+
+// where "cargo hfuzz run --minimize" has honggfuzz save the inputs it keeps
+fn minimize_dir(target: &str) -> PathBuf {
+    workspace::target_workspace(target).join(".minimize")
+}
+
+// honggfuzz's `-M` saving the inputs bringing coverage in a fresh directory rather than deleting the
+// others from its input directory, coming after `--output` when there are several of them
+pub fn minimize_args(target: &str) -> Vec<String> {
+    let minimize_dir = minimize_dir(target);
+    vec![
+        "-M".into(),
+        "--output".into(), minimize_dir.join("output").to_string_lossy().into_owned(),
+        "--logfile".into(), minimize_dir.join("honggfuzz.log").to_string_lossy().into_owned(),
+    ]
+}
+
+// the corpus is left with the inputs honggfuzz kept, those of the other input directories added to it
+pub fn minimize(target: &str, mut command: Command) {
+    let minimize_dir = minimize_dir(target);
+    let minimized_dir = minimize_dir.join("output");
+    create_empty_dir(&minimized_dir);
+    let input_dirs = workspace::input_dirs(target);
+    let corpus = workspace::corpus_files(target);
+    let inputs = corpus.len() + input_dirs[1..].iter().map(|d| workspace::files_in(d).len()).sum::<usize>();
+    log::note(&format!("minimizing the {} inputs of \"{}\"", inputs, target));
+
+    let status = log::status(&mut command).unwrap_or_else(|error| {
+        eprintln!("cannot execute {} ({}), try to execute \"cargo hfuzz build\" from fuzzed project directory", command.get_program().to_string_lossy(), error);
+        process::exit(1);
+    });
+    let log_file = minimize_dir.join("honggfuzz.log");
+    let status = session::checked(target, status, &log_file);
+    if !status.success() {
+        eprintln!("error: honggfuzz failed to minimize the corpus, see \"{}\"", log_file.display());
+        process::exit(status.code().unwrap_or(1));
+    }
+    let minimized = workspace::files_in(&minimized_dir);
+    if minimized.is_empty() && inputs > 0 {
+        eprintln!("error: honggfuzz kept none of the inputs, the corpus is left as it is, see \"{}\"", log_file.display());
+        process::exit(1);
+    }
+
+    // honggfuzz saves the inputs it generates too while minimizing, which the corpus mustn't get
+    let input_hashes: HashSet<u64> = corpus.iter().cloned()
+        .chain(input_dirs[1..].iter().flat_map(|d| workspace::files_in(d)))
+        .filter_map(|f| content_hash(&f))
+        .collect();
+    let kept: HashSet<u64> = minimized.iter().filter_map(|f| content_hash(f)).filter(|h| input_hashes.contains(h)).collect();
+    let mut in_corpus = HashSet::new();
+    let mut removed = 0;
+    for file in &corpus {
+        match content_hash(file) {
+            Some(hash) if kept.contains(&hash) && in_corpus.insert(hash) => {}
+            _ => {
+                if fs::remove_file(file).is_err() {
+                    eprintln!("warning: failed to remove \"{}\"", file.display());
+                    continue;
+                }
+                removed += 1;
+            }
+        }
+    }
+    let input_dir = workspace::input_dir(target);
+    let mut added = 0;
+    for file in &minimized {
+        if content_hash(file).is_some_and(|h| kept.contains(&h) && in_corpus.insert(h)) {
+            copy(file, &input_dir.join(file.file_name().unwrap()));
+            added += 1;
+        }
+    }
+    let _ = fs::remove_dir_all(&minimize_dir);
+
+    let left = workspace::corpus_files(target).len();
+    match input_dirs.len() {
+        1 => println!("{}: minimized the corpus from {} to {} inputs, {} removed", target, corpus.len(), left, removed),
+        _ => println!("{}: minimized the corpus from {} to {} inputs, {} removed and {} added from the other input directories",
+            target, corpus.len(), left, removed, added),
+    }
+}
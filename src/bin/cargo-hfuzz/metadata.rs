@@ -0,0 +1,145 @@
+use std::env;
+use std::process::{self, Command};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::cli;
+use crate::config::{self, TargetConfig};
+use crate::json::Json;
+use crate::log;
+
+pub struct Target {
+    pub name: String,
+    pub package: String,
+    pub src_path: PathBuf,
+    // from `[package.metadata.hfuzz.targets.NAME]`
+    pub config: TargetConfig,
+}
+
+pub struct Package {
+    pub name: String,
+    // where its Cargo.toml is
+    pub manifest_dir: PathBuf,
+    pub bins: Vec<Target>,
+    pub depends_on_honggfuzz: bool,
+    // the ones of `[features]` and the optional dependencies
+    pub features: Vec<String>,
+}
+
+pub struct Metadata {
+    pub packages: Vec<Package>,
+}
+
+// set by `-p`/`--package`, restricting the fuzz targets to the ones of a workspace member
+static PACKAGE: OnceLock<String> = OnceLock::new();
+
+impl Metadata {
+    // fuzz targets are the binaries of the workspace members depending on honggfuzz
+    pub fn fuzz_targets(&self) -> impl Iterator<Item=&Target> {
+        self.fuzz_packages()
+            .filter(|p| PACKAGE.get().is_none_or(|selected| p.name == *selected))
+            .flat_map(|p| p.bins.iter())
+    }
+
+    pub fn fuzz_packages(&self) -> impl Iterator<Item=&Package> {
+        self.packages.iter().filter(|p| p.depends_on_honggfuzz)
+    }
+}
+
+fn parse_package(package: &Json) -> Package {
+    let name = package.get("name").and_then(Json::as_str).unwrap_or_default().to_string();
+    let manifest_path = PathBuf::from(package.get("manifest_path").and_then(Json::as_str).unwrap_or_default());
+    let targets_config = package.get("metadata").and_then(|m| m.get("hfuzz")).and_then(|h| h.get("targets"));
+    let bins = package.get("targets").map(Json::as_array).unwrap_or_default().iter()
+        .filter(|t| t.get("kind").map(Json::as_array).unwrap_or_default().iter().any(|k| k.as_str() == Some("bin")))
+        .map(|t| {
+            let target: String = t.get("name").and_then(Json::as_str).unwrap_or_default().into();
+            Target {
+                config: targets_config.and_then(|c| c.get(&target))
+                    .map(|c| config::parse_target_config(c, &manifest_path, &target))
+                    .unwrap_or_default(),
+                name: target,
+                package: name.clone(),
+                src_path: t.get("src_path").and_then(Json::as_str).unwrap_or_default().into(),
+            }
+        })
+        .collect();
+    let dependencies = package.get("dependencies").map(Json::as_array).unwrap_or_default();
+    let depends_on_honggfuzz = dependencies.iter().any(|d| d.get("name").and_then(Json::as_str) == Some("honggfuzz"));
+    let mut features: Vec<String> = match package.get("features") {
+        Some(Json::Object(features)) => features.iter().map(|(name, _)| name.clone()).collect(),
+        _ => vec![],
+    };
+    features.extend(dependencies.iter()
+        .filter(|d| matches!(d.get("optional"), Some(Json::Bool(true))))
+        .filter_map(|d| d.get("rename").and_then(Json::as_str).or(d.get("name").and_then(Json::as_str)).map(String::from)));
+
+    Package {
+        name,
+        manifest_dir: manifest_path.parent().map(PathBuf::from).unwrap_or_default(),
+        bins,
+        depends_on_honggfuzz,
+        features,
+    }
+}
+
+pub fn cargo_metadata() -> Metadata {
+    let cargo_bin = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = log::output(Command::new(cargo_bin)
+        .args(["metadata", "--format-version", "1", "--no-deps"]))
+        .unwrap_or_else(|_| {
+            eprintln!("error: failed to run \"cargo metadata\"");
+            process::exit(1);
+        });
+    if !output.status.success() {
+        eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+        process::exit(output.status.code().unwrap_or(1));
+    }
+
+    let json = Json::parse(&String::from_utf8_lossy(&output.stdout)).unwrap_or_else(|e| {
+        eprintln!("error: failed to parse \"cargo metadata\" output: {}", e);
+        process::exit(1);
+    });
+
+    Metadata {
+        packages: json.get("packages").map(Json::as_array).unwrap_or_default().iter().map(parse_package).collect(),
+    }
+}
+
+// the metadata of the workspace, read once
+pub fn metadata() -> &'static Metadata {
+    static METADATA: OnceLock<Metadata> = OnceLock::new();
+    METADATA.get_or_init(cargo_metadata)
+}
+
+pub fn fuzz_target_names() -> Vec<String> {
+    metadata().fuzz_targets().map(|t| t.name.clone()).collect()
+}
+
+pub fn select_package(package: &str) {
+    let packages = metadata().packages.iter().map(|p| p.name.as_str());
+    if !metadata().packages.iter().any(|p| p.name == package) {
+        match cli::suggestion(package, packages) {
+            Some(known) => eprintln!("error: no package \"{}\" in the workspace, did you mean \"{}\"?", package, known),
+            None => eprintln!("error: no package \"{}\" in the workspace", package),
+        }
+        process::exit(1);
+    }
+    let _ = PACKAGE.set(package.to_string());
+}
+
+pub fn selected_package() -> Option<&'static str> {
+    PACKAGE.get().map(String::as_str)
+}
+
+// a fuzz target, which needs `-p` when several workspace members have one of this name
+pub fn find_target(name: &str) -> Option<&'static Target> {
+    let mut targets = metadata().fuzz_targets().filter(|t| t.name == name);
+    let target = targets.next()?;
+    if targets.next().is_some() {
+        let packages: Vec<&str> = metadata().fuzz_targets().filter(|t| t.name == name).map(|t| t.package.as_str()).collect();
+        eprintln!("error: the packages {} all have a \"{}\" fuzz target, select one with \"-p PACKAGE\"", packages.join(", "), name);
+        process::exit(1);
+    }
+    Some(target)
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Once;
+
+use crate::cli::Subcommand;
+use crate::log;
+use crate::metadata;
+use crate::workspace;
+
+pub const MIGRATE: Subcommand = Subcommand {
+    name: "migrate",
+    usage: "",
+    about: "upgrades hfuzz_workspace to the layout of this cargo-hfuzz, moving the corpora, crashes and logs of the targets where it expects them",
+    options: &[],
+    passthrough: None,
+};
+
+// the layout of the workspaces written by this cargo-hfuzz: 1 for the unstamped ones, with a directory
+// per target, 2 with one per package too when several packages have fuzz targets
+pub const LAYOUT_VERSION: u32 = 2;
+
+// at the root of the workspace, holding the version of its layout
+const LAYOUT_FILE: &str = ".layout-version";
+
+fn layout_file() -> PathBuf {
+    Path::new(&workspace::workspace_dir()).join(LAYOUT_FILE)
+}
+
+// the version of the layout of the workspace, 1 if it wasn't stamped, which can't be a newer one
+fn layout_version() -> u32 {
+    let version = match fs::read_to_string(layout_file()) {
+        Ok(content) => content.trim().parse().unwrap_or_else(|_| {
+            eprintln!("error: invalid layout version \"{}\" in \"{}\"", content.trim(), layout_file().display());
+            process::exit(1);
+        }),
+        Err(_) => 1,
+    };
+    if version > LAYOUT_VERSION {
+        eprintln!("error: \"{}\" has the layout {} of a newer cargo-hfuzz, this one knows up to {}", workspace::workspace_dir(), version, LAYOUT_VERSION);
+        process::exit(1);
+    }
+    version
+}
+
+fn stamp() {
+    let stamped = fs::create_dir_all(workspace::workspace_dir()).and_then(|_| fs::write(layout_file(), format!("{}\n", LAYOUT_VERSION)));
+    if stamped.is_err() {
+        eprintln!("warning: failed to write \"{}\"", layout_file().display());
+    }
+}
+
+// the directories of targets fuzzed before they were namespaced by package, to move into their package's,
+// the ones of a target name several packages share being left where they are
+fn relocations() -> Vec<(PathBuf, PathBuf)> {
+    let metadata = metadata::metadata();
+    if metadata.fuzz_packages().count() < 2 {
+        return Vec::new();
+    }
+    let workspace = PathBuf::from(workspace::workspace_dir());
+    let targets: Vec<&metadata::Target> = metadata.fuzz_packages().flat_map(|p| p.bins.iter()).collect();
+    targets.iter()
+        .filter(|t| targets.iter().filter(|other| other.name == t.name).count() == 1)
+        .map(|t| (workspace.join(&t.name), workspace.join(&t.package).join(&t.name)))
+        .filter(|(legacy, _)| legacy.join("input").is_dir())
+        .collect()
+}
+
+// the namespaced directories of targets, which a legacy directory named after their package holds
+fn namespaced_dirs() -> Vec<PathBuf> {
+    let workspace = PathBuf::from(workspace::workspace_dir());
+    metadata::metadata().fuzz_packages().flat_map(|p| p.bins.iter()).map(|t| workspace.join(&t.package).join(&t.name)).collect()
+}
+
+// moves the files of `from` into `to`, merging the directories both have: a file `to` already has is
+// dropped if it is the same, kept as `NAME.v1` otherwise
+fn merge_into(from: &Path, to: &Path, skipped: &[PathBuf]) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        if skipped.contains(&path) {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_os_string();
+        let destination = to.join(&name);
+        if !destination.exists() {
+            fs::rename(&path, &destination)?;
+        } else if path.is_dir() && destination.is_dir() {
+            merge_into(&path, &destination, skipped)?;
+            let _ = fs::remove_dir(&path);
+        } else if workspace::content_hash(&path).is_some() && workspace::content_hash(&path) == workspace::content_hash(&destination) {
+            fs::remove_file(&path)?;
+        } else {
+            let mut kept = destination;
+            while kept.exists() {
+                let mut name = kept.into_os_string();
+                name.push(".v1");
+                kept = name.into();
+            }
+            fs::rename(&path, kept)?;
+        }
+    }
+    Ok(())
+}
+
+// stamps a fresh workspace, tells to migrate an older one, and stops before using a newer one
+pub fn check_layout() {
+    static CHECKED: Once = Once::new();
+    CHECKED.call_once(|| {
+        let version = layout_version();
+        match relocations().first() {
+            None if version < LAYOUT_VERSION => stamp(),
+            None => {}
+            Some((legacy, namespaced)) => eprintln!("warning: \"{}\" is where an older cargo-hfuzz kept the workspace of a target, now \"{}\", move it and the others with \"cargo hfuzz migrate\"",
+                legacy.display(), namespaced.display()),
+        }
+    });
+}
+
+pub fn hfuzz_migrate<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = MIGRATE.parse(args);
+    if let Some(arg) = matches.args.first() {
+        MIGRATE.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    if !Path::new(&workspace::workspace_dir()).is_dir() {
+        println!("no \"{}\" to migrate", workspace::workspace_dir());
+        return;
+    }
+    let version = layout_version();
+    let relocations = relocations();
+    if log::dry_run() {
+        for (legacy, namespaced) in &relocations {
+            println!("would move \"{}\" to \"{}\"", legacy.display(), namespaced.display());
+        }
+        if version < LAYOUT_VERSION {
+            println!("would upgrade \"{}\" from the layout {} to {}", workspace::workspace_dir(), version, LAYOUT_VERSION);
+        }
+        return;
+    }
+    let skipped = namespaced_dirs();
+    for (legacy, namespaced) in &relocations {
+        match merge_into(legacy, namespaced, &skipped) {
+            Ok(()) => {
+                // a legacy directory named after a package still holds the targets of that package
+                let _ = fs::remove_dir(legacy);
+                println!("moved \"{}\" to \"{}\"", legacy.display(), namespaced.display());
+            }
+            Err(error) => {
+                eprintln!("error: failed to move \"{}\" to \"{}\" ({}), what is left of it is still there", legacy.display(), namespaced.display(), error);
+                process::exit(1);
+            }
+        }
+    }
+    if version < LAYOUT_VERSION {
+        stamp();
+        println!("\"{}\" upgraded from the layout {} to {}", workspace::workspace_dir(), version, LAYOUT_VERSION);
+    } else if relocations.is_empty() {
+        println!("\"{}\" already has the layout {}", workspace::workspace_dir(), LAYOUT_VERSION);
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::json::Json;
+use crate::log;
+use crate::session::{stack_of, Session};
+use crate::stats;
+use crate::workspace;
+
+// how often the crash files and the coverage are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// without new coverage for this long, the campaign has plateaued
+const DEFAULT_PLATEAU: u64 = 3600;
+
+// the URL of HFUZZ_WEBHOOK or of the `notify.webhook` setting, notifications being off without one
+fn webhook() -> Option<String> {
+    env::var("HFUZZ_WEBHOOK").ok().filter(|w| !w.is_empty()).or_else(|| config::config().webhook.clone())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn host() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname").ok().or_else(|| env::var("HOSTNAME").ok())
+        .map_or_else(|| "unknown".to_string(), |h| h.trim().to_string())
+}
+
+// POSTs `event` with curl, a failure only being a warning since fuzzing goes on
+//
+// the `text` field is what Slack and Mattermost show of a message
+fn post(webhook: &str, target: &str, event: &str, text: String, mut fields: Vec<(&str, Json)>) {
+    let mut body = vec![
+        ("event", event.into()),
+        ("target", target.into()),
+        ("host", host().into()),
+        ("time", now().into()),
+        ("text", format!("{} on {}: {}", target, host(), text).into()),
+    ];
+    body.append(&mut fields);
+    let body = Json::object(body).to_string();
+    let curl = log::spawn(Command::new("curl")
+        .args(["-fsS", "-X", "POST", "-H", "Content-Type: application/json", "--data-binary", "@-", webhook])
+        .stdin(Stdio::piped()).stdout(Stdio::null()));
+    let sent = curl.is_ok_and(|mut curl| {
+        let written = curl.stdin.take().unwrap().write_all(body.as_bytes()).is_ok();
+        let status = curl.wait();
+        if let Ok(status) = status {
+            log::exited(&curl, status);
+        }
+        status.is_ok_and(|s| s.success()) && written
+    });
+    if !sent {
+        eprintln!("warning: failed to notify \"{}\" of the {} event of \"{}\"", webhook, event, target);
+    }
+}
+
+// notifies every new unique crash, and the coverage not growing anymore, until `done`, the new crash
+// files being `crash_detected` events of `--log-format json` too, the crashes found last being
+// notified before the thread ends
+pub fn watch(target: &str, mut known_crashes: HashSet<PathBuf>, done: Arc<AtomicBool>) -> Option<JoinHandle<()>> {
+    let webhook = webhook();
+    if webhook.is_none() && !log::json() {
+        return None;
+    }
+    let plateau = config::config().plateau.unwrap_or(DEFAULT_PLATEAU);
+    let target = target.to_string();
+    let started = now();
+    Some(thread::spawn(move || {
+        let mut stacks: HashSet<String> = known_crashes.iter().map(|f| stack_of(f)).collect();
+        let mut plateaued = false;
+        loop {
+            let finished = done.load(Ordering::Relaxed);
+            for crash_file in workspace::crash_files(&target) {
+                if !known_crashes.insert(crash_file.clone()) {
+                    continue;
+                }
+                let stack = stack_of(&crash_file);
+                let unique = stacks.insert(stack.clone());
+                log::event("crash_detected", vec![
+                    ("target", target.as_str().into()),
+                    ("crash_file", crash_file.to_string_lossy().into_owned().into()),
+                    ("stack", stack.as_str().into()),
+                    ("unique", unique.into()),
+                ]);
+                let Some(webhook) = webhook.as_deref().filter(|_| unique) else { continue };
+                let name = crash_file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                post(webhook, &target, "crash", format!("new crash {}", name), vec![
+                    ("crash_file", crash_file.to_string_lossy().into_owned().into()),
+                    ("stack", stack.into()),
+                ]);
+            }
+
+            // a plateau is notified once, until the coverage grows again, from the log of the session
+            let stats = webhook.as_ref().and_then(|_| stats::read_stats(&target));
+            if let (Some(webhook), Some(stats)) = (&webhook, stats) {
+                let since = stats.last_cov_update.max(started);
+                match now().saturating_sub(since) >= plateau {
+                    true if !plateaued => {
+                        plateaued = true;
+                        post(webhook, &target, "plateau", format!("no new coverage for {}s, {} edges", now() - since, stats.edge_cov), vec![
+                            ("edge_cov", stats.edge_cov.into()),
+                            ("last_cov_update", stats.last_cov_update.into()),
+                        ]);
+                    }
+                    true => {}
+                    false => plateaued = false,
+                }
+            }
+            if finished {
+                break;
+            }
+            let slept = Instant::now();
+            while slept.elapsed() < POLL_INTERVAL && !done.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }))
+}
+
+pub fn session_ended(target: &str, session: &Session) {
+    if let Some(webhook) = webhook() {
+        let text = format!("session ended after {}s with {} new crash files, corpus {} -> {}",
+            session.duration.as_secs(), session.new_crash_files.len(), session.corpus_before, session.corpus_after);
+        post(&webhook, target, "session_end", text, vec![("session", session.to_json(target))]);
+    }
+}
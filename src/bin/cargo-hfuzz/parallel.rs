@@ -0,0 +1,179 @@
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{BuildType, hfuzz_build, bin_args, honggfuzz_command, pinned};
+use crate::cli::{Opt, Subcommand};
+use crate::log;
+use crate::metadata;
+use crate::session;
+use crate::stats;
+use crate::workspace;
+
+pub const RUN_PARALLEL: Subcommand = Subcommand {
+    name: "run-parallel",
+    usage: "[--instances N] [--cpus LIST] [--max-time SECS] [TARGET ...]",
+    about: "builds the targets and fuzzes them all at once, sharing the CPUs between several honggfuzz instances",
+    options: &[
+        Opt { name: "--instances", value: Some("N"), help: "honggfuzz instances per target, 1 by default" },
+        Opt { name: "--cpus", value: Some("LIST"), help: "CPUs to share, like \"0-15\" or \"0-3,8\", all of them by default" },
+        Opt { name: "--max-time", value: Some("SECS"), help: "stops fuzzing after this time, runs until interrupted by default" },
+    ],
+    passthrough: None,
+};
+
+// how often the status of the instances is printed
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Instance {
+    target: String,
+    cpus: Vec<usize>,
+    log: PathBuf,
+    child: Child,
+    exit_code: Option<i32>,
+}
+
+// parses CPU lists like "0-3,8", the syntax of `taskset -c`
+fn parse_cpus(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((first, last)) => cpus.extend(first.trim().parse::<usize>().ok()?..=last.trim().parse().ok()?),
+            None => cpus.push(range.trim().parse().ok()?),
+        }
+    }
+    Some(cpus).filter(|c| !c.is_empty())
+}
+
+fn cpu_list(cpus: &[usize]) -> String {
+    cpus.iter().map(usize::to_string).collect::<Vec<_>>().join(",")
+}
+
+// splits the CPUs in as many contiguous slices as instances, instances sharing them when they outnumber them
+fn partition(cpus: &[usize], instances: usize) -> Vec<Vec<usize>> {
+    let size = (cpus.len() / instances).max(1);
+    (0..instances)
+        .map(|i| (0..size).map(|j| cpus[(i * size + j) % cpus.len()]).collect())
+        .collect()
+}
+
+// without `taskset` (not on macOS for instance) the instances only get their number of threads
+fn can_pin() -> bool {
+    static TASKSET: OnceLock<bool> = OnceLock::new();
+    *TASKSET.get_or_init(|| {
+        let found = log::status(Command::new("taskset").arg("-V").stdout(Stdio::null()).stderr(Stdio::null())).is_ok_and(|s| s.success());
+        if !found {
+            eprintln!("warning: \"taskset\" not found, the honggfuzz instances are not pinned to their CPUs");
+        }
+        found
+    })
+}
+
+fn spawn(target: &str, index: usize, cpus: Vec<usize>, max_time: Option<u64>) -> Instance {
+    // the first instance of a target logs where "cargo hfuzz stats" reads, the others next to it
+    let log = stats::instance_file(stats::log_file(target), index);
+    let _ = fs::remove_file(&log); // it would be about a previous session
+    let mut honggfuzz_args = vec![
+        "-n".to_string(), cpus.len().to_string(), "-v".to_string(),
+        "--logfile".to_string(), log.to_string_lossy().into_owned(),
+    ];
+    if let Some(max_time) = max_time {
+        honggfuzz_args.extend(["--run_time".to_string(), max_time.to_string()]);
+    }
+    let mut command = honggfuzz_command(target, &honggfuzz_args, std::iter::empty());
+    if can_pin() {
+        command = pinned(&command, &cpu_list(&cpus));
+    }
+    command.stdout(Stdio::null()).stderr(Stdio::null());
+    log::select_target(target);
+    let child = session::spawn(&mut command).unwrap_or_else(|error| {
+        eprintln!("error: failed to start honggfuzz for \"{}\" ({})", target, error);
+        process::exit(1);
+    });
+    log::event("fuzzer_started", vec![("target", target.into()), ("instance", index.into()), ("pid", u64::from(child.id()).into())]);
+    Instance { target: target.to_string(), cpus, log, child, exit_code: None }
+}
+
+fn print_status(instances: &[Instance], started: Instant) {
+    if io::stdout().is_terminal() {
+        print!("\x1b[H\x1b[2J"); // redraws in place
+    }
+    println!("{} honggfuzz instances, running for {}s", instances.len(), started.elapsed().as_secs());
+    let width = instances.iter().map(|i| i.target.len()).chain(Some("TARGET".len())).max().unwrap_or(0);
+    println!("{:width$}  {:>12}  {:>8}  {:>8}  {:>8}  STATUS", "TARGET", "CPUS", "EDGES", "CORPUS", "CRASHES", width = width);
+    let mut targets: Vec<&str> = instances.iter().map(|i| i.target.as_str()).collect();
+    targets.dedup();
+    for target in targets {
+        let of_target: Vec<&Instance> = instances.iter().filter(|i| i.target == target).collect();
+        let logs: Vec<stats::Stats> = of_target.iter().filter_map(|i| stats::read_log(target, &i.log)).collect();
+        let cpus: Vec<usize> = of_target.iter().flat_map(|i| i.cpus.iter().copied()).collect();
+        let running = of_target.iter().filter(|i| i.exit_code.is_none()).count();
+        let failed = of_target.iter().filter(|i| i.exit_code.is_some_and(|c| c != 0)).count();
+        let status = match (running, failed) {
+            (0, 0) => "done".to_string(),
+            (_, 0) => format!("{} running", running),
+            (_, _) => format!("{} running, {} failed", running, failed),
+        };
+        println!("{:width$}  {:>12}  {:>8}  {:>8}  {:>8}  {}", target, cpu_list(&cpus),
+            logs.iter().map(|s| s.edge_cov).max().unwrap_or(0),
+            workspace::corpus_files(target).len(),
+            logs.iter().map(|s| s.unique_crashes).sum::<u64>(),
+            status, width = width);
+    }
+}
+
+pub fn hfuzz_run_parallel<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = RUN_PARALLEL.parse(args);
+    let instances_per_target: usize = matches.parsed(&RUN_PARALLEL, "--instances").unwrap_or(1);
+    let max_time: Option<u64> = matches.parsed(&RUN_PARALLEL, "--max-time");
+    let cpus = match matches.value("--cpus") {
+        Some(list) => parse_cpus(list).unwrap_or_else(|| RUN_PARALLEL.error(&format!("invalid CPU list \"{}\", expected something like \"0-3,8\"", list))),
+        None => (0..thread::available_parallelism().map_or(1, |n| n.get())).collect(),
+    };
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+    if targets.is_empty() || instances_per_target == 0 {
+        RUN_PARALLEL.error("nothing to fuzz");
+    }
+
+    hfuzz_build(bin_args(&targets).into_iter(), crate_root, &BuildType::ReleaseInstrumented);
+
+    // Ctrl-C stops every honggfuzz gracefully, the final status comes afterwards
+    session::forward_signals(true);
+    let slices = partition(&cpus, targets.len() * instances_per_target);
+    let started = Instant::now();
+    let mut instances: Vec<Instance> = targets.iter()
+        .flat_map(|t| (0..instances_per_target).map(move |i| (t, i)))
+        .zip(slices)
+        .map(|((target, index), cpus)| spawn(target, index, cpus, max_time))
+        .collect();
+
+    while instances.iter().any(|i| i.exit_code.is_none()) {
+        thread::sleep(STATUS_INTERVAL);
+        for instance in instances.iter_mut().filter(|i| i.exit_code.is_none()) {
+            if let Ok(Some(status)) = instance.child.try_wait() {
+                log::exited(&instance.child, status);
+                session::reaped(&instance.child);
+                let status = session::checked(&instance.target, status, &instance.log);
+                log::event("fuzzer_exited", vec![("target", instance.target.as_str().into()), ("pid", u64::from(instance.child.id()).into()), ("exit_code", status.code().into())]);
+                instance.exit_code = Some(status.code().unwrap_or(1));
+            }
+        }
+        print_status(&instances, started);
+    }
+    session::forward_signals(false);
+    if let Some(signal) = session::stopped_by() {
+        process::exit(128 + signal);
+    }
+
+    if instances.iter().any(|i| i.exit_code != Some(0)) {
+        eprintln!("error: some honggfuzz instances failed, see their logs in {}", workspace::workspace_dir());
+        process::exit(1);
+    }
+}
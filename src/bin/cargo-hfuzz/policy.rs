@@ -0,0 +1,58 @@
+use std::process;
+
+use crate::cli::{Matches, Opt, Subcommand};
+
+pub const EXIT_CODE_ON_CRASH: Opt = Opt {
+    name: "--exit-code-on-crash",
+    value: Some("CODE"),
+    help: "exits with CODE when an input crashes, 1 by default (run only fails on crashes with it or \"--ci\")",
+};
+pub const EXIT_ZERO_ON_TIMEOUT: Opt = Opt {
+    name: "--exit-zero-on-timeout",
+    value: None,
+    help: "inputs running past the timeout don't make the command fail",
+};
+pub const FAIL_ON_HANG: Opt = Opt {
+    name: "--fail-on-hang",
+    value: None,
+    help: "inputs running past the timeout make the command fail with 1, the default of replay and verify",
+};
+
+// what makes replay, verify and run fail, and with which exit code
+pub struct ExitPolicy {
+    // crashes don't make the command fail without one
+    pub crash_code: Option<i32>,
+    pub hangs_fail: bool,
+}
+
+impl ExitPolicy {
+    // the defaults of the command are taken without options
+    pub fn parse(command: &Subcommand, matches: &Matches, crash_code: Option<i32>, hangs_fail: bool) -> ExitPolicy {
+        let code = matches.value("--exit-code-on-crash").map(|code| match code.parse() {
+            Ok(code) if (0..=255).contains(&code) => code,
+            _ => command.error(&format!("invalid value \"{}\" for \"--exit-code-on-crash CODE\", expected an exit code from 0 to 255", code)),
+        });
+        let hangs_fail = match (matches.flag("--exit-zero-on-timeout"), matches.flag("--fail-on-hang")) {
+            (true, true) => command.error("\"--exit-zero-on-timeout\" and \"--fail-on-hang\" contradict each other"),
+            (true, false) => false,
+            (false, true) => true,
+            (false, false) => hangs_fail,
+        };
+        ExitPolicy { crash_code: code.or(crash_code), hangs_fail }
+    }
+
+    // the exit code of `crashes` crashing inputs and `hangs` hanging ones, when they make the command fail
+    pub fn exit_code(&self, crashes: u64, hangs: u64) -> Option<i32> {
+        match self.crash_code {
+            Some(code) if crashes > 0 && code != 0 => Some(code),
+            _ if hangs > 0 && self.hangs_fail => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn exit(&self, crashes: u64, hangs: u64) {
+        if let Some(code) = self.exit_code(crashes, hangs) {
+            process::exit(code);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use crate::{BuildType, NO_DEFAULT_RUSTFLAGS_OPT, cargo_build, select_build_options};
+use crate::cli::{Opt, Subcommand};
+use crate::features;
+use crate::instrumentation;
+use crate::json::Json;
+use crate::log;
+
+pub const PRINT_ENV: Subcommand = Subcommand {
+    name: "print-env",
+    usage: "[OPTIONS]",
+    about: "prints the RUSTFLAGS and the environment of the instrumented builds, for other build systems",
+    options: &[
+        Opt { name: "--json", value: None, help: "prints them as a JSON object, with the arguments of cargo build" },
+        Opt { name: "--no-instr", value: None, help: "the ones of \"cargo hfuzz build-no-instr\" instead" },
+        Opt { name: "--debug", value: None, help: "the ones of \"cargo hfuzz build-debug\" instead" },
+        Opt { name: "--grcov", value: None, help: "the ones of \"cargo hfuzz build-grcov\" instead" },
+        Opt { name: "--sanitizer", value: Some("NAME"), help: "with `-Z sanitizer=NAME`: address, leak, memory or thread" },
+        Opt { name: "--target", value: Some("TRIPLE"), help: "the ones of a cross-compiled build" },
+        NO_DEFAULT_RUSTFLAGS_OPT,
+        instrumentation::CMP_COVERAGE, instrumentation::NO_CMP_COVERAGE,
+        instrumentation::DIV_COVERAGE, instrumentation::NO_DIV_COVERAGE,
+        instrumentation::GEP_COVERAGE, instrumentation::NO_GEP_COVERAGE,
+        features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    ],
+    passthrough: None,
+};
+
+pub fn hfuzz_print_env<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = PRINT_ENV.parse(args);
+    if let Some(arg) = matches.args.first() {
+        PRINT_ENV.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    let build_type = match (matches.flag("--no-instr"), matches.flag("--debug"), matches.flag("--grcov")) {
+        (false, false, false) => BuildType::ReleaseInstrumented,
+        (true, false, false) => BuildType::ReleaseNotInstrumented,
+        (false, true, false) => BuildType::Debug,
+        (false, false, true) => BuildType::ProfileWithGrcov,
+        _ => PRINT_ENV.error("\"--no-instr\", \"--debug\" and \"--grcov\" contradict each other"),
+    };
+    select_build_options(&PRINT_ENV, &matches);
+
+    // the same command as the builds of cargo-hfuzz, what it would run being what is printed
+    let command = cargo_build(std::iter::empty(), crate_root, &build_type);
+    let env: Vec<(String, String)> = command.get_envs()
+        .filter_map(|(key, value)| Some((key.to_string_lossy().into_owned(), value?.to_string_lossy().into_owned())))
+        .collect();
+    let cargo_args: Vec<String> = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+
+    if matches.flag("--json") {
+        println!("{}", Json::object(vec![
+            ("env", Json::object(env.into_iter().map(|(key, value)| (key, value.into())).collect())),
+            ("cargo_args", cargo_args.into()),
+        ]));
+        return;
+    }
+    for (key, value) in env {
+        println!("export {}={}", key, log::quoted(value.as_ref()));
+    }
+    println!("# then: cargo {}", cargo_args.iter().map(|a| log::quoted(a.as_ref())).collect::<Vec<_>>().join(" "));
+}
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::log;
+use crate::workspace;
+
+// the marker of the targets whose closure returns a `Corpus` or a `Result`, the
+// `honggfuzz::reject::MARKER` of the library
+const REJECTING_MAGIC: &[u8] = b"\xffhfuzz-rejecting\xff";
+
+// the list of inputs given to the target, in the workspace of the target
+const FILTERED_INPUTS: &str = "filtered-inputs";
+
+fn rejects_inputs(binary: &Path) -> bool {
+    fs::read(binary).is_ok_and(|content| content.windows(REJECTING_MAGIC.len()).any(|w| w == REJECTING_MAGIC))
+}
+
+// honggfuzz keeps the inputs which found new coverage, the ones the closure rejected too: the target
+// replays those saved during the session (not in `known`) with CARGO_HONGGFUZZ_FILTER_INPUTS set and
+// removes the rejected ones, returns how many were removed
+pub fn drop_rejected(target: &str, binary: &Path, known: &HashSet<PathBuf>) -> usize {
+    let inputs: Vec<PathBuf> = workspace::corpus_files(target).into_iter().filter(|f| !known.contains(f)).collect();
+    if inputs.is_empty() || !rejects_inputs(binary) {
+        return 0;
+    }
+    let current_dir = env::current_dir().unwrap_or_default();
+    let list = workspace::target_workspace(target).join(FILTERED_INPUTS);
+    let lines: String = inputs.iter().map(|f| format!("{}\n", current_dir.join(f).display())).collect();
+    if fs::write(&list, lines).is_err() {
+        eprintln!("warning: failed to write \"{}\", the rejected inputs stay in the corpus", list.display());
+        return 0;
+    }
+
+    let mut command = match crate::runner().split_first() {
+        Some((program, runner_args)) => {
+            let mut command = Command::new(program);
+            command.args(runner_args).arg(binary);
+            command
+        }
+        None => Command::new(binary),
+    };
+    command.env("CARGO_HONGGFUZZ_FILTER_INPUTS", current_dir.join(&list))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if log::status(&mut command).is_err() {
+        eprintln!("warning: failed to run \"{}\", the rejected inputs stay in the corpus", binary.display());
+    }
+    let _ = fs::remove_file(&list);
+    inputs.iter().filter(|f| !f.exists()).count()
+}
@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use crate::config;
+use crate::corpus;
+use crate::log;
+use crate::workspace::{self, content_hash};
+
+// where corpora are shared, picked from the scheme of the remote
+#[derive(Clone, Copy)]
+enum Backend {
+    // `s3://bucket/prefix`, with the AWS CLI
+    S3,
+    // `gs://bucket/prefix`, with gsutil
+    Gcs,
+    // `http://` or `https://`, any server taking PUT requests (WebDAV, nginx's dav module...) with curl
+    Http,
+    // anything else, like `host:dir`, `rsync://host/module` or a local directory
+    Rsync,
+}
+
+impl Backend {
+    fn of(remote: &str) -> Backend {
+        if remote.starts_with("s3://") {
+            Backend::S3
+        } else if remote.starts_with("gs://") {
+            Backend::Gcs
+        } else if remote.starts_with("http://") || remote.starts_with("https://") {
+            Backend::Http
+        } else {
+            Backend::Rsync
+        }
+    }
+
+    // copies the files of `from` missing in `to`, never deleting any, so that pushes from several
+    // machines add up
+    fn sync(self, from: &str, to: &str) -> Command {
+        let (from, to) = (format!("{}/", from.trim_end_matches('/')), format!("{}/", to.trim_end_matches('/')));
+        let mut command = match self {
+            Backend::S3 => Command::new("aws"),
+            Backend::Gcs => Command::new("gsutil"),
+            _ => Command::new("rsync"),
+        };
+        match self {
+            Backend::S3 => command.args(["s3", "sync", "--only-show-errors", &from, &to]),
+            Backend::Gcs => command.args(["-q", "-m", "rsync", "-r", &from, &to]),
+            _ => command.args(["-a", &from, &to]),
+        };
+        command
+    }
+}
+
+// the remote of HFUZZ_CORPUS_REMOTE or of the `corpus.remote` setting when none is given
+pub fn remote(given: Option<String>) -> String {
+    given.or_else(|| env::var("HFUZZ_CORPUS_REMOTE").ok().filter(|r| !r.is_empty()))
+        .or_else(|| config::config().corpus_remote.clone())
+        .unwrap_or_else(|| {
+            eprintln!("error: no remote to share the corpus with, give one or set HFUZZ_CORPUS_REMOTE or `corpus.remote` in {}", config::CONFIG_FILE);
+            process::exit(1);
+        })
+}
+
+// every target has its own directory in the remote
+fn target_remote(remote: &str, target: &str) -> String {
+    format!("{}/{}", remote.trim_end_matches('/'), target)
+}
+
+// pulls land here before being added to the corpus, which keeps the next pulls incremental
+fn staging_dir(target: &str) -> PathBuf {
+    workspace::target_workspace(target).join("remote")
+}
+
+fn run(command: &mut Command) -> bool {
+    match log::status(command) {
+        Ok(status) => status.success(),
+        Err(error) => {
+            eprintln!("error: failed to run \"{}\" ({})", command.get_program().to_string_lossy(), error);
+            process::exit(1);
+        }
+    }
+}
+
+fn check(succeeded: bool, what: &str) {
+    if !succeeded {
+        eprintln!("error: failed to {}", what);
+        process::exit(1);
+    }
+}
+
+// over HTTP the names of the inputs of a target are listed in its `index`, one per line
+fn http_index(target_remote: &str) -> HashSet<String> {
+    let output = log::output(Command::new("curl").args(["-fsS", &format!("{}/index", target_remote)]).stderr(Stdio::null()));
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect(),
+        Ok(_) => HashSet::new(), // nothing pushed yet
+        Err(error) => {
+            eprintln!("error: failed to run \"curl\" ({})", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn http_push(files: &[PathBuf], target_remote: &str) -> usize {
+    let mut index = http_index(target_remote);
+    let mut pushed = 0;
+    for file in files {
+        let name = file.file_name().unwrap().to_string_lossy().into_owned();
+        if index.contains(&name) {
+            continue;
+        }
+        let url = format!("{}/{}", target_remote, name);
+        check(run(Command::new("curl").args(["-fsS", "-T"]).arg(file).arg(&url)), &format!("upload \"{}\"", url));
+        index.insert(name);
+        pushed += 1;
+    }
+    if pushed > 0 {
+        let mut names: Vec<String> = index.into_iter().collect();
+        names.sort();
+        let url = format!("{}/index", target_remote);
+        let mut curl = log::spawn(Command::new("curl").args(["-fsS", "-T", "-", &url]).stdin(Stdio::piped())).unwrap_or_else(|error| {
+            eprintln!("error: failed to run \"curl\" ({})", error);
+            process::exit(1);
+        });
+        let written = curl.stdin.take().unwrap().write_all((names.join("\n") + "\n").as_bytes()).is_ok();
+        let status = curl.wait();
+        if let Ok(status) = status {
+            log::exited(&curl, status);
+        }
+        check(status.is_ok_and(|s| s.success()) && written, &format!("upload \"{}\"", url));
+    }
+    pushed
+}
+
+fn http_pull(target_remote: &str, staging_dir: &Path) {
+    for name in http_index(target_remote) {
+        // the names come from the server
+        if name.is_empty() || name.contains('/') || name.starts_with('.') || staging_dir.join(&name).exists() {
+            continue;
+        }
+        let url = format!("{}/{}", target_remote, name);
+        check(run(Command::new("curl").args(["-fsS", "-o"]).arg(staging_dir.join(&name)).arg(&url)), &format!("download \"{}\"", url));
+    }
+}
+
+pub fn push(target: &str, remote: &str) {
+    log::select_target(target);
+    let input_dir = workspace::input_dir(target);
+    let files = workspace::corpus_files(target);
+    if files.is_empty() {
+        eprintln!("error: the corpus of \"{}\" is empty, nothing to push", target);
+        process::exit(1);
+    }
+    let target_remote = target_remote(remote, target);
+    match Backend::of(remote) {
+        Backend::Http => {
+            let pushed = http_push(&files, &target_remote);
+            println!("{}: pushed {} new inputs to \"{}\"", target, pushed, target_remote);
+        }
+        backend => {
+            check(run(&mut backend.sync(&input_dir.to_string_lossy(), &target_remote)), &format!("push to \"{}\"", target_remote));
+            println!("{}: pushed the {} inputs of the corpus to \"{}\"", target, files.len(), target_remote);
+        }
+    }
+}
+
+// the inputs only come into the corpus when it doesn't have them yet, whatever their names
+pub fn pull(target: &str, remote: &str) {
+    log::select_target(target);
+    let staging_dir = staging_dir(target);
+    let input_dir = workspace::input_dir(target);
+    for dir in [&staging_dir, &input_dir] {
+        fs::create_dir_all(dir).unwrap_or_else(|_| {
+            eprintln!("error: failed to create \"{}\"", dir.display());
+            process::exit(1);
+        });
+    }
+    let target_remote = target_remote(remote, target);
+    match Backend::of(remote) {
+        Backend::Http => http_pull(&target_remote, &staging_dir),
+        backend => check(run(&mut backend.sync(&target_remote, &staging_dir.to_string_lossy())), &format!("pull from \"{}\"", target_remote)),
+    }
+
+    let mut known: HashSet<u64> = workspace::corpus_files(target).iter().filter_map(|f| content_hash(f)).collect();
+    let pulled = corpus::import(&workspace::files_in(&staging_dir), &mut known, |f| input_dir.join(f.file_name().unwrap()));
+    println!("{}: pulled {} new inputs from \"{}\"", target, pulled, target_remote);
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+use crate::{BuildType, hfuzz_build, bin_args, debug_binary};
+use crate::annotate::{self, Format};
+use crate::cli::Subcommand;
+use crate::crash;
+use crate::features;
+use crate::log;
+use crate::metadata;
+use crate::policy::{self, ExitPolicy};
+use crate::workspace;
+
+pub const REPLAY: Subcommand = Subcommand {
+    name: "replay",
+    usage: "[OPTIONS] [TARGET ...]",
+    about: "replays the corpus and the crash files of the targets, exits with 1 if any input fails",
+    options: &[
+        annotate::ANNOTATE, policy::EXIT_CODE_ON_CRASH, policy::EXIT_ZERO_ON_TIMEOUT, policy::FAIL_ON_HANG,
+        features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    ],
+    passthrough: None,
+};
+
+// replays the corpus and the crash files of a target, returns the numbers of crashing and hanging inputs
+fn replay_target(target: &str, annotate: Option<&Format>) -> (u64, u64) {
+    log::select_target(target);
+    let binary = debug_binary(target);
+    let inputs: Vec<PathBuf> = workspace::corpus_files(target).into_iter()
+        .chain(workspace::crash_files(target))
+        .collect();
+    features::check_replay(target, &workspace::crash_files(target));
+
+    let (mut crashes, mut hangs) = (0, 0);
+    for input in &inputs {
+        let outcome = crash::replay(&binary, input, crash::DEFAULT_TIMEOUT);
+        if outcome.timed_out {
+            println!("HANG   {}", input.display());
+            hangs += 1;
+        } else if outcome.crashed {
+            let message = outcome.panic_message.as_deref().unwrap_or("no panic message");
+            println!("FAIL   {}: {}", input.display(), message.lines().next().unwrap_or_default());
+            crashes += 1;
+        } else {
+            continue;
+        }
+        if let Some(format) = annotate {
+            annotate::crash(format, target, input, &outcome);
+        }
+    }
+
+    println!("{}: {} inputs replayed, {} failures", target, inputs.len(), crashes + hangs);
+    (crashes, hangs)
+}
+
+pub fn hfuzz_replay<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = REPLAY.parse(args);
+    let annotate = annotate::selected(&REPLAY, &matches);
+    let policy = ExitPolicy::parse(&REPLAY, &matches, Some(1), true);
+    features::select(&REPLAY, &matches);
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+
+    hfuzz_build(bin_args(&targets).into_iter(), crate_root, &BuildType::Debug);
+
+    let (crashes, hangs) = targets.iter().map(|t| replay_target(t, annotate.as_ref()))
+        .fold((0, 0), |(crashes, hangs), (c, h)| (crashes + c, hangs + h));
+    policy.exit(crashes, hangs);
+}
@@ -0,0 +1,171 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{BuildType, hfuzz_run};
+use crate::cli::{Opt, Subcommand};
+use crate::config;
+use crate::features;
+use crate::json::Json;
+use crate::log;
+use crate::metadata;
+use crate::workspace;
+
+pub const RESUME: Subcommand = Subcommand {
+    name: "resume",
+    usage: "[--list] TARGET",
+    about: "fuzzes a target again with the options and environment of its last \"cargo hfuzz run\"",
+    options: &[Opt { name: "--list", value: None, help: "lists the recorded sessions of the target instead" }],
+    passthrough: None,
+};
+
+// the environment variables changing what a session builds and fuzzes
+const ENV_VARS: &[&str] = &[
+    "RUSTFLAGS", "HFUZZ_BUILD_ARGS", "HFUZZ_RUN_ARGS", "HFUZZ_SANITIZER", "HFUZZ_INPUT", "HFUZZ_RUNNER", "HFUZZ_ENGINE_PATH",
+    "HFUZZ_NO_DEFAULT_RUSTFLAGS",
+];
+
+// one file per session, named after when it started
+fn sessions_dir(target: &str) -> PathBuf {
+    workspace::target_workspace(target).join("sessions")
+}
+
+// FNV-1a, the hashes being compared with the ones of sessions recorded by other builds of cargo-hfuzz
+fn hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = log::output(Command::new("git").args(args)).ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// the commit the sources are at, `+` meaning with uncommitted changes
+fn git_commit() -> Option<String> {
+    let commit = git(&["rev-parse", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain"]).is_some_and(|s| !s.is_empty());
+    Some(if dirty { commit + "+" } else { commit })
+}
+
+// what else a session depends on, the options of "cargo hfuzz run" apart
+fn config_hash() -> Option<String> {
+    fs::read(config::CONFIG_FILE).ok().map(|content| hash(&content))
+}
+
+// saves the settings of a session starting, for "cargo hfuzz resume"
+pub fn record(command: &str, target: &str, args: &[String]) {
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let record = Json::object(vec![
+        ("started", started.into()),
+        ("command", command.into()),
+        ("args", args.to_vec().into()),
+        ("package", metadata::selected_package().into()),
+        ("features", features::selected().to_json()),
+        ("env", Json::object(ENV_VARS.iter().map(|v| (*v, env::var(v).ok().into())).collect())),
+        ("git_commit", git_commit().into()),
+        ("config_hash", config_hash().into()),
+        ("corpus_files", workspace::corpus_files(target).len().into()),
+    ]);
+    let dir = sessions_dir(target);
+    let file = dir.join(format!("{}.json", started));
+    if fs::create_dir_all(&dir).and_then(|_| fs::write(&file, format!("{}\n", record))).is_err() {
+        eprintln!("warning: failed to write \"{}\"", file.display());
+    }
+}
+
+// the sessions of a target, the oldest first
+fn sessions(target: &str) -> Vec<(PathBuf, Json)> {
+    workspace::files_in(&sessions_dir(target)).into_iter()
+        .filter(|f| f.extension().is_some_and(|e| e == "json"))
+        .filter_map(|f| {
+            let record = Json::parse(&fs::read_to_string(&f).ok()?).ok()?;
+            Some((f, record))
+        })
+        .collect()
+}
+
+// the session running at `time`, the last one started before it
+pub fn session_at(target: &str, time: u64) -> Option<Json> {
+    sessions(target).into_iter().map(|(_, record)| record).rev().find(|r| number(r, "started") <= time)
+}
+
+fn number(record: &Json, key: &str) -> u64 {
+    match record.get(key) {
+        Some(Json::Number(n)) => *n as u64,
+        _ => 0,
+    }
+}
+
+fn string<'a>(record: &'a Json, key: &str) -> Option<&'a str> {
+    record.get(key).and_then(Json::as_str)
+}
+
+fn recorded_args(record: &Json) -> Vec<String> {
+    record.get("args").map(Json::as_array).unwrap_or_default().iter().filter_map(Json::as_str).map(String::from).collect()
+}
+
+fn describe(record: &Json) -> String {
+    format!("cargo hfuzz {} {}", string(record, "command").unwrap_or("run"), recorded_args(record).join(" "))
+}
+
+fn list(target: &str) {
+    let sessions = sessions(target);
+    if sessions.is_empty() {
+        println!("no session recorded for \"{}\" yet", target);
+    }
+    for (file, record) in sessions {
+        println!("{}", file.display());
+        println!("  command:    {}", describe(&record));
+        println!("  git commit: {}", string(&record, "git_commit").unwrap_or("-"));
+        println!("  corpus:     {} files", number(&record, "corpus_files"));
+    }
+}
+
+// tells what differs from when the session started, it doesn't prevent resuming
+fn check_changes(record: &Json) {
+    if let (Some(then), Some(now)) = (string(record, "git_commit"), git_commit()) {
+        if then != now {
+            eprintln!("warning: the sources changed since the session started, it was at {} and is now at {}", then, now);
+        }
+    }
+    if string(record, "config_hash").map(String::from) != config_hash() {
+        eprintln!("warning: {} changed since the session started", config::CONFIG_FILE);
+    }
+}
+
+pub fn hfuzz_resume<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = RESUME.parse(args);
+    let target = match matches.args.as_slice() {
+        [target] => target.clone(),
+        _ => RESUME.error("please specify the name of the target"),
+    };
+    if matches.flag("--list") {
+        return list(&target);
+    }
+
+    let (file, record) = sessions(&target).pop().unwrap_or_else(|| {
+        eprintln!("error: no session to resume in \"{}\", start one with \"cargo hfuzz run {}\"", sessions_dir(&target).display(), target);
+        process::exit(1);
+    });
+    println!("resuming the session of \"{}\": {}", file.display(), describe(&record));
+    check_changes(&record);
+
+    // the same environment, variables which weren't set being unset
+    for variable in ENV_VARS {
+        match record.get("env").and_then(|e| e.get(variable)).and_then(Json::as_str) {
+            Some(value) => env::set_var(variable, value),
+            None => env::remove_var(variable),
+        }
+    }
+    if let Some(package) = string(&record, "package") {
+        metadata::select_package(package);
+    }
+    let build_type = match string(&record, "command") {
+        Some("run-no-instr") => BuildType::ReleaseNotInstrumented,
+        _ => BuildType::ReleaseInstrumented,
+    };
+    hfuzz_run(recorded_args(&record).into_iter(), crate_root, &build_type);
+}
@@ -0,0 +1,92 @@
+use std::process;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::{BuildType, hfuzz_build, bin_args, honggfuzz_command};
+use crate::cli::{Matches, Opt, Subcommand};
+use crate::log;
+use crate::metadata;
+use crate::session;
+
+pub const RUN_ALL: Subcommand = Subcommand {
+    name: "run-all",
+    usage: "[--each DURATION] [--total DURATION] [TARGET ...]",
+    about: "builds the targets and fuzzes them in turn, sharing the fuzzing time",
+    options: &[
+        Opt { name: "--each", value: Some("DURATION"), help: "fuzzing time of each target in a rotation, like \"90s\", \"10m\" or \"2h\", 10m by default" },
+        Opt { name: "--total", value: Some("DURATION"), help: "total fuzzing time, rotates until interrupted by default" },
+    ],
+    passthrough: None,
+};
+
+// fuzzing time given to each target before moving on to the next one
+const DEFAULT_EACH: Duration = Duration::from_secs(10 * 60);
+
+// parses durations like "90", "90s", "10m", "2h" or "1d", a number alone being seconds
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let (value, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => duration.split_at(i),
+        None => (duration, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    value.parse::<u64>().ok().map(|v| Duration::from_secs(v * multiplier))
+}
+
+fn duration_arg(matches: &Matches, option: &str) -> Option<Duration> {
+    matches.value(option).map(|value| parse_duration(value).unwrap_or_else(|| {
+        RUN_ALL.error(&format!("\"{}\" expects a duration like \"90s\", \"10m\" or \"2h\"", option))
+    }))
+}
+
+// fuzzes a target for the given time, the corpus it grows in its input directory is picked up by the next rotation
+fn fuzz_slice(target: &str, slice: Duration) {
+    let run_time = vec!["--run_time".to_string(), slice.as_secs().max(1).to_string()];
+    let status = session::fuzz(target, honggfuzz_command(target, &run_time, std::iter::empty()), None, None).status;
+    // interrupted, the rotations stop with it
+    if let Some(signal) = session::stopped_by() {
+        log::note(&format!("interrupted while fuzzing \"{}\", stopping", target));
+        process::exit(128 + signal);
+    }
+    if !status.success() {
+        eprintln!("error: honggfuzz failed while fuzzing \"{}\"", target);
+        process::exit(status.code().unwrap_or(1));
+    }
+}
+
+pub fn hfuzz_run_all<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = RUN_ALL.parse(args);
+    let each = duration_arg(&matches, "--each").unwrap_or(DEFAULT_EACH);
+    let total = duration_arg(&matches, "--total");
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+    if targets.is_empty() || each.as_secs() == 0 {
+        RUN_ALL.error("nothing to fuzz");
+    }
+
+    hfuzz_build(bin_args(&targets).into_iter(), crate_root, &BuildType::ReleaseInstrumented);
+
+    // without a total budget, rotate until interrupted
+    let start = Instant::now();
+    for rotation in 1.. {
+        for target in &targets {
+            let slice = match total {
+                Some(total) => each.min(total.saturating_sub(start.elapsed())),
+                None => each,
+            };
+            if slice.as_secs() == 0 {
+                log::note(&format!("total fuzzing time exhausted, stopping in rotation {}", rotation));
+                return;
+            }
+            log::note(&format!("rotation {}: fuzzing \"{}\" for {}s", rotation, target, slice.as_secs()));
+            fuzz_slice(target, slice);
+        }
+    }
+}
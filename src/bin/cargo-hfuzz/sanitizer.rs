@@ -0,0 +1,115 @@
+use std::env;
+use std::path::Path;
+use std::process::{self, Command};
+use std::sync::OnceLock;
+
+use crate::config;
+use crate::json::Json;
+use crate::log;
+use crate::rustc_version;
+
+pub const SANITIZERS: &[&str] = &["address", "leak", "memory", "thread"];
+
+// set by `--sanitizer`, which takes precedence over HFUZZ_SANITIZER and hfuzz.toml
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+pub fn check(sanitizer: &str) -> Result<(), String> {
+    match SANITIZERS.contains(&sanitizer) {
+        true => Ok(()),
+        false => Err(format!("unknown sanitizer \"{}\", expected one of {}", sanitizer, SANITIZERS.join(", "))),
+    }
+}
+
+pub fn select(sanitizer: &str) {
+    let _ = SELECTED.set(sanitizer.to_string());
+}
+
+pub fn selected() -> Option<&'static str> {
+    static FROM_ENV: OnceLock<Option<String>> = OnceLock::new();
+    let from_env = FROM_ENV.get_or_init(|| {
+        let sanitizer = env::var("HFUZZ_SANITIZER").ok().filter(|s| !s.is_empty())?;
+        if let Err(error) = check(&sanitizer) {
+            eprintln!("error: invalid HFUZZ_SANITIZER, {}", error);
+            process::exit(1);
+        }
+        Some(sanitizer)
+    });
+    SELECTED.get().or(from_env.as_ref()).or(config::config().sanitizer.as_ref()).map(String::as_str)
+}
+
+// rustc links the runtime of the sanitizer itself, it only needs a nightly toolchain
+pub fn rustflags(sanitizer: &str) -> String {
+    let mut rustflags = format!("-Z sanitizer={} ", sanitizer);
+    if sanitizer == "memory" {
+        rustflags.push_str("-Z sanitizer-memory-track-origins ");
+    }
+    rustflags
+}
+
+// what the toolchain running supports, instead of a recipe of RUSTFLAGS: a nightly rustc (or RUSTC_BOOTSTRAP),
+// the sanitizer among the `supported-sanitizers` of the target spec of `triple`, and the sources of the
+// standard library when it is rebuilt
+pub fn check_toolchain(sanitizer: &str, triple: &str) {
+    let release = rustc_version().lines().find_map(|l| l.strip_prefix("release: ")).unwrap_or("unknown");
+    let output = log::output(Command::new("rustc").args(["-Z", "unstable-options", "--print", "target-spec-json", "--target", triple]))
+        .unwrap_or_else(|error| {
+            eprintln!("error: failed to run \"rustc\" ({})", error);
+            process::exit(1);
+        });
+    if !output.status.success() {
+        eprintln!("error: the sanitizers need a nightly toolchain and rustc is {}: run \"cargo +nightly hfuzz\", \
+                   or \"rustup override set nightly\" in the crate", release);
+        process::exit(1);
+    }
+    // the targets without sanitizers have no `supported-sanitizers`
+    if let Ok(spec) = Json::parse(&String::from_utf8_lossy(&output.stdout)) {
+        let supported: Vec<&str> = spec.get("supported-sanitizers").map_or(&[][..], Json::as_array).iter().filter_map(Json::as_str).collect();
+        if !supported.contains(&sanitizer) {
+            eprintln!("error: rustc {} has no {} sanitizer for {} (its sanitizers: {})", release, sanitizer, triple,
+                      match supported.is_empty() { true => "none".to_string(), false => supported.join(", ") });
+            process::exit(1);
+        }
+    }
+    if !cargo_args(sanitizer).is_empty() {
+        let sysroot = log::output(Command::new("rustc").args(["--print", "sysroot"]))
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default();
+        if !Path::new(&sysroot).join("lib/rustlib/src/rust/library").is_dir() {
+            eprintln!("error: the {} sanitizer rebuilds the standard library, whose sources are missing from \"{}\": \
+                       install them with \"rustup component add rust-src\"", sanitizer, sysroot);
+            process::exit(1);
+        }
+    }
+}
+
+// the standard library has to be instrumented too, or memory and thread sanitizers report
+// false positives in it (needs the rust-src component)
+pub fn cargo_args(sanitizer: &str) -> &'static [&'static str] {
+    match sanitizer {
+        "memory" | "thread" => &["-Z", "build-std"],
+        _ => &[],
+    }
+}
+
+// makes the sanitizers work with Rust code, the options set by the user coming last to override them,
+// in a debugger the first report aborts so that it stops there
+pub fn set_options(command: &mut Command, debugging: bool) {
+    let options = [
+        ("ASAN_OPTIONS", "address", "detect_odr_violation=0"),
+        ("TSAN_OPTIONS", "thread", "report_signal_unsafe=0"),
+        ("MSAN_OPTIONS", "memory", ""),
+        ("LSAN_OPTIONS", "leak", ""),
+    ];
+    for (variable, sanitizer, defaults) in options {
+        let mut value: Vec<String> = Some(defaults).filter(|d| !d.is_empty()).map(String::from).into_iter().collect();
+        if debugging && selected() == Some(sanitizer) {
+            value.push("abort_on_error=1".into());
+        }
+        if let Ok(user) = env::var(variable) {
+            value.push(user);
+        }
+        if !value.is_empty() {
+            command.env(variable, value.join(":"));
+        }
+    }
+}
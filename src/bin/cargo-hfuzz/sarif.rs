@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use crate::VERSION;
+use crate::crash::{self, Frame};
+use crate::json::Json;
+use crate::triage::{Bucket, Triage};
+
+// what a found crash is, panics being bugs of the code and fatal signals possibly memory corruptions
+// (a sanitizer report, a segmentation fault in unsafe code...)
+const RULES: &[(&str, &str, &str)] = &[
+    ("panic", "The fuzz target panicked on an input found by honggfuzz", "5.0"),
+    ("fatal-signal", "The fuzz target was killed by a signal on an input found by honggfuzz", "8.0"),
+];
+
+fn rule(bucket: &Bucket) -> &'static str {
+    match bucket.outcome.panic_message {
+        Some(_) => "panic",
+        None => "fatal-signal",
+    }
+}
+
+fn location(frame: &Frame, root: &Path) -> Option<Json> {
+    let (uri, line, column) = frame.source(root)?;
+    Some(Json::object(vec![
+        ("physicalLocation", Json::object(vec![
+            ("artifactLocation", Json::object(vec![("uri", uri.into())])),
+            ("region", Json::object(vec![("startLine", line.into()), ("startColumn", column.into())])),
+        ])),
+        ("message", Json::object(vec![("text", frame.function.as_str().into())])),
+    ]))
+}
+
+fn result(target: &str, bucket: &Bucket, root: &Path) -> Json {
+    let frames: Vec<&Frame> = bucket.outcome.relevant_frames().collect();
+    let locations: Vec<Json> = frames.iter().filter_map(|f| location(f, root)).collect();
+    let what = match &bucket.outcome.panic_message {
+        Some(message) => format!("panicked: {}", message),
+        None => format!("was killed by a signal (exit code {:?})", bucket.outcome.code),
+    };
+    Json::object(vec![
+        ("ruleId", rule(bucket).into()),
+        ("level", "error".into()),
+        ("message", Json::object(vec![(
+            "text",
+            format!("The fuzz target \"{}\" {}, with the input {} ({} crash files)", target, what, bucket.representative.display(), bucket.inputs.len()).into(),
+        )])),
+        // the top of the stack in the code of the workspace is where the crash is shown
+        ("locations", locations.first().cloned().map_or_else(Vec::new, |l| vec![l]).into()),
+        ("stacks", vec![Json::object(vec![
+            ("message", Json::object(vec![("text", "Stack of the crash".into())])),
+            ("frames", frames.iter().map(|f| Json::object(vec![
+                ("location", location(f, root).unwrap_or_else(|| Json::object(vec![("message", Json::object(vec![("text", f.function.as_str().into())]))]))),
+            ])).collect::<Vec<_>>().into()),
+        ])].into()),
+        // the same bug found again is the same alert
+        ("partialFingerprints", Json::object(vec![("stackHash/v1", format!("{}:{:016x}", target, bucket.stack_hash).into())])),
+        ("properties", Json::object(vec![
+            ("target", target.into()),
+            ("input", bucket.representative.to_string_lossy().into_owned().into()),
+            ("crash_files", bucket.inputs.len().into()),
+            ("stack_hash", format!("{:016x}", bucket.stack_hash).into()),
+        ])),
+    ])
+}
+
+// a SARIF 2.1.0 log of the crashes of a target, one result per bucket, which GitHub code scanning
+// and the other SARIF consumers take
+pub fn log(target: &str, triage: &Triage) -> Json {
+    let root = crash::source_root();
+    let rules: Vec<Json> = RULES.iter().map(|(id, description, severity)| Json::object(vec![
+        ("id", (*id).into()),
+        ("shortDescription", Json::object(vec![("text", (*description).into())])),
+        ("defaultConfiguration", Json::object(vec![("level", "error".into())])),
+        // read by code scanning to rank the alerts
+        ("properties", Json::object(vec![("security-severity", (*severity).into()), ("tags", vec!["security", "fuzzing"].into())])),
+    ])).collect();
+    let results: Vec<Json> = triage.buckets.iter().map(|b| result(target, b, &root)).collect();
+    Json::object(vec![
+        ("$schema", "https://json.schemastore.org/sarif-2.1.0.json".into()),
+        ("version", "2.1.0".into()),
+        ("runs", vec![Json::object(vec![
+            ("tool", Json::object(vec![("driver", Json::object(vec![
+                ("name", "cargo-hfuzz".into()),
+                ("version", VERSION.into()),
+                ("informationUri", "https://github.com/rust-fuzz/honggfuzz-rs".into()),
+                ("rules", rules.into()),
+            ]))])),
+            ("results", results.into()),
+        ])].into()),
+    ])
+}
+
+pub fn write(file: &Path, target: &str, triage: &Triage) {
+    if fs::write(file, format!("{}\n", log(target, triage))).is_err() {
+        eprintln!("error: failed to write \"{}\"", file.display());
+        process::exit(1);
+    }
+}
@@ -0,0 +1,388 @@
+use std::fs;
+use std::process;
+use std::path::Path;
+
+use crate::VERSION;
+use crate::cli::{self, Opt, Subcommand};
+use crate::workspace;
+
+pub const INIT: Subcommand = Subcommand {
+    name: "init",
+    usage: "[--dir DIR] [--template NAME] [TARGET]",
+    about: "adds the honggfuzz dependency and a first fuzz target to the crate",
+    options: &[Opt { name: "--dir", value: Some("DIR"), help: "directory of the target, `src/bin` by default" }, TEMPLATE],
+    passthrough: None,
+};
+
+pub const ADD: Subcommand = Subcommand {
+    name: "add",
+    usage: "[--dir DIR] [--template NAME] TARGET",
+    about: "adds a fuzz target to the crate",
+    options: &[Opt { name: "--dir", value: Some("DIR"), help: "directory of the target, where the others are by default" }, TEMPLATE],
+    passthrough: None,
+};
+
+pub const DEFAULT_TARGET_NAME: &str = "fuzz_target_1";
+pub const DEFAULT_TARGETS_DIR: &str = "src/bin";
+pub const FUZZ_TARGETS_DIR: &str = "fuzz_targets";
+
+// targets placed in `src/bin` are discovered automatically by cargo,
+// anywhere else they have to be registered as a `[[bin]]` in `Cargo.toml`
+const AUTO_BINS_DIR: &str = "src/bin";
+
+const TEMPLATE: Opt = Opt {
+    name: "--template",
+    value: Some("NAME"),
+    help: "starts the harness from a template: default, parser, roundtrip, arbitrary or api-sequence",
+};
+
+// the skeleton of a harness, the ones taking structured inputs deriving them with `arbitrary`
+struct Template {
+    name: &'static str,
+    source: &'static str,
+    derives_arbitrary: bool,
+}
+
+const TEMPLATES: &[Template] = &[
+    Template { name: "default", source: TARGET_TEMPLATE, derives_arbitrary: false },
+    Template { name: "parser", source: PARSER_TEMPLATE, derives_arbitrary: false },
+    Template { name: "roundtrip", source: ROUNDTRIP_TEMPLATE, derives_arbitrary: false },
+    Template { name: "arbitrary", source: ARBITRARY_TEMPLATE, derives_arbitrary: true },
+    Template { name: "api-sequence", source: API_SEQUENCE_TEMPLATE, derives_arbitrary: true },
+];
+
+// `derive(Arbitrary)` needs `arbitrary` as a dependency of the crate, with its derive feature
+const ARBITRARY_DEPENDENCY: &str = r#"{ version = "1", features = ["derive"] }"#;
+
+const TARGET_TEMPLATE: &str = r#"use honggfuzz::fuzz;
+
+fn main() {
+    // Here you can parse `std::env::args and
+    // setup / initialize your project
+
+    // You have full control over the loop but
+    // you're supposed to call `fuzz` ad vitam aeternam
+    loop {
+        // The fuzz macro gives an arbitrary object (see `arbitrary crate`)
+        // to a closure-like block of code.
+        // For performance reasons, it is recommended that you use the native type
+        // `&[u8]` when possible.
+        // Here, this slice will contain a "random" quantity of "random" data.
+        fuzz!(|data: &[u8]| {
+            // call the code you want to fuzz here
+            let _ = data;
+        });
+    }
+}
+"#;
+
+const PARSER_TEMPLATE: &str = r#"use honggfuzz::fuzz;
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            // A parser has to reject malformed inputs with an error: a panic, a hang or an
+            // unbounded allocation on any input is a bug.
+            // Most text formats reject invalid UTF-8 first, binary ones take `data` as it is.
+            if let Ok(text) = std::str::from_utf8(data) {
+                // call your parser here, like `let _ = my_crate::parse(text);`
+                let _ = text.parse::<u64>();
+            }
+        });
+    }
+}
+"#;
+
+const ROUNDTRIP_TEMPLATE: &str = r#"use honggfuzz::fuzz;
+
+// The two sides of your format, like `serde_json::from_slice` and `serde_json::to_vec`.
+fn decode(data: &[u8]) -> Result<String, std::str::Utf8Error> {
+    std::str::from_utf8(data).map(String::from)
+}
+
+fn encode(value: &str) -> Vec<u8> {
+    value.as_bytes().to_vec()
+}
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            // Whatever decodes has to encode back to something decoding to the same value.
+            // The bytes may differ (whitespace, field order...), so the values are compared.
+            let value = match decode(data) {
+                Ok(value) => value,
+                Err(_) => return,
+            };
+            let encoded = encode(&value);
+            let decoded = decode(&encoded).expect("an encoded value doesn't decode");
+            assert_eq!(value, decoded, "the value changed through encode and decode");
+        });
+    }
+}
+"#;
+
+const ARBITRARY_TEMPLATE: &str = r#"use arbitrary::Arbitrary;
+use honggfuzz::fuzz;
+
+// The input of the target, built by `arbitrary` from the bytes of the fuzzer: use the types your
+// code takes, Debug showing the input of a crash in "cargo hfuzz run-debug".
+#[derive(Arbitrary, Debug)]
+struct Input {
+    name: String,
+    values: Vec<u32>,
+    enabled: bool,
+}
+
+fn main() {
+    loop {
+        fuzz!(|input: Input| {
+            // call the code you want to fuzz with the fields of `input` here
+            let _ = (input.name, input.values, input.enabled);
+        });
+    }
+}
+"#;
+
+const API_SEQUENCE_TEMPLATE: &str = r#"use std::collections::VecDeque;
+
+use arbitrary::Arbitrary;
+use honggfuzz::fuzz;
+
+// The calls the fuzzer chains, one variant per method of the API with its arguments.
+#[derive(Arbitrary, Debug)]
+enum Call {
+    Push(u8),
+    Pop,
+    Clear,
+}
+
+fn main() {
+    loop {
+        fuzz!(|calls: Vec<Call>| {
+            // The type under test (replace the `VecDeque`) runs the same calls as a model
+            // simple enough to be obviously right, after each one they have to agree.
+            let mut tested = VecDeque::new();
+            let mut model = Vec::new();
+            for call in calls {
+                match call {
+                    Call::Push(value) => {
+                        tested.push_back(value);
+                        model.push(value);
+                    }
+                    Call::Pop => assert_eq!(tested.pop_back(), model.pop()),
+                    Call::Clear => {
+                        tested.clear();
+                        model.clear();
+                    }
+                }
+                assert_eq!(tested.len(), model.len());
+            }
+        });
+    }
+}
+"#;
+
+fn read_manifest() -> String {
+    fs::read_to_string("Cargo.toml").unwrap_or_else(|_| {
+        eprintln!("error: failed to read \"Cargo.toml\"");
+        process::exit(1);
+    })
+}
+
+fn write_file(path: &str, content: &str) {
+    fs::write(path, content).unwrap_or_else(|_| {
+        eprintln!("error: failed to write \"{}\"", path);
+        process::exit(1);
+    });
+}
+
+fn section_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with('[') && line.ends_with(']') {
+        Some(line.trim_matches(|c| c == '[' || c == ']').trim())
+    } else {
+        None
+    }
+}
+
+// very light `Cargo.toml` inspection, enough to not register things twice
+fn has_dependency(manifest: &str, name: &str) -> bool {
+    let mut in_dependencies = false;
+    for line in manifest.lines() {
+        if let Some(section) = section_name(line) {
+            if section.ends_with(&format!("dependencies.{}", name)) {
+                return true;
+            }
+            in_dependencies = section.ends_with("dependencies");
+        } else if in_dependencies {
+            let key = line.split('=').next().unwrap_or_default().trim();
+            if key == name || key.starts_with(&format!("{}.", name)) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn has_bin(manifest: &str, name: &str) -> bool {
+    let mut in_bin = false;
+    for line in manifest.lines() {
+        if let Some(section) = section_name(line) {
+            in_bin = section == "bin";
+        } else if in_bin {
+            let mut kv = line.splitn(2, '=');
+            let key = kv.next().unwrap_or_default().trim();
+            let value = kv.next().unwrap_or_default().trim().trim_matches('"');
+            if key == "name" && value == name {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn ensure_dependency(name: &str, value: &str) {
+    let manifest = read_manifest();
+    if has_dependency(&manifest, name) {
+        return;
+    }
+
+    let dependency = format!("{} = {}", name, value);
+    let mut lines: Vec<&str> = manifest.lines().collect();
+    match lines.iter().position(|l| section_name(l) == Some("dependencies")) {
+        Some(index) => lines.insert(index + 1, &dependency),
+        None => {
+            lines.push("");
+            lines.push("[dependencies]");
+            lines.push(&dependency);
+        }
+    }
+    write_file("Cargo.toml", &(lines.join("\n") + "\n"));
+    println!("added `{}` to Cargo.toml", dependency);
+}
+
+fn ensure_bin_registered(name: &str, path: &str) {
+    let mut manifest = read_manifest();
+    if has_bin(&manifest, name) {
+        return;
+    }
+
+    if !manifest.ends_with('\n') {
+        manifest.push('\n');
+    }
+    manifest.push_str(&format!("\n[[bin]]\nname = \"{}\"\npath = \"{}\"\n", name, path));
+    write_file("Cargo.toml", &manifest);
+    println!("registered `{}` as a [[bin]] in Cargo.toml", name);
+}
+
+fn ensure_gitignored(entries: &[&str]) {
+    let mut gitignore = fs::read_to_string(".gitignore").unwrap_or_default();
+    let missing: Vec<&str> = entries.iter()
+        .copied()
+        .filter(|entry| !gitignore.lines().any(|l| l.trim().trim_matches('/') == entry.trim_matches('/')))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    if !gitignore.is_empty() && !gitignore.ends_with('\n') {
+        gitignore.push('\n');
+    }
+    for entry in missing {
+        gitignore.push_str(entry);
+        gitignore.push('\n');
+        println!("added `{}` to .gitignore", entry);
+    }
+    write_file(".gitignore", &gitignore);
+}
+
+fn ensure_honggfuzz_dependency() {
+    ensure_dependency("honggfuzz", &format!("\"{}\"", VERSION));
+}
+
+pub fn template_names() -> Vec<&'static str> {
+    TEMPLATES.iter().map(|t| t.name).collect()
+}
+
+fn template(command: &Subcommand, name: Option<&str>) -> &'static Template {
+    let name = name.unwrap_or("default");
+    TEMPLATES.iter().find(|t| t.name == name).unwrap_or_else(|| {
+        let names = template_names();
+        match cli::suggestion(name, names.iter().copied()) {
+            Some(known) => command.error(&format!("unknown template \"{}\", did you mean \"{}\"?", name, known)),
+            None => command.error(&format!("unknown template \"{}\", expected one of {}", name, names.join(", "))),
+        }
+    })
+}
+
+fn create_target(name: &str, dir: &str, template: &Template) {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        eprintln!("error: invalid fuzz target name \"{}\", only alphanumeric characters, `-` and `_` are allowed", name);
+        process::exit(1);
+    }
+
+    let path = format!("{}/{}.rs", dir.trim_end_matches('/'), name);
+    if Path::new(&path).exists() {
+        eprintln!("error: fuzz target \"{}\" already exists", &path);
+        process::exit(1);
+    }
+
+    fs::create_dir_all(dir).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", dir);
+        process::exit(1);
+    });
+    write_file(&path, template.source);
+    println!("created {}", &path);
+    if template.derives_arbitrary {
+        ensure_dependency("arbitrary", ARBITRARY_DEPENDENCY);
+    }
+
+    if dir.trim_end_matches('/') != AUTO_BINS_DIR {
+        ensure_bin_registered(name, &path);
+    }
+
+    let input = workspace::target_workspace(name).join("input");
+    fs::create_dir_all(&input).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", input.display());
+        process::exit(1);
+    });
+    println!("created {}", input.display());
+}
+
+pub fn hfuzz_init<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = INIT.parse(args);
+    let dir = matches.value("--dir").unwrap_or(DEFAULT_TARGETS_DIR).to_string();
+    let template = template(&INIT, matches.value("--template"));
+    let mut args = matches.args.into_iter();
+    let name = args.next();
+    if let Some(arg) = args.next() {
+        INIT.error(&format!("unexpected argument \"{}\"", arg));
+    }
+
+    ensure_honggfuzz_dependency();
+    create_target(name.as_deref().unwrap_or(DEFAULT_TARGET_NAME), &dir, template);
+
+    ensure_gitignored(&[&format!("/{}", workspace::target_dir()), &format!("/{}", workspace::workspace_dir())]);
+}
+
+pub fn hfuzz_add<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = ADD.parse(args);
+    let template = template(&ADD, matches.value("--template"));
+    let mut args = matches.args.iter().cloned();
+    let name = args.next().unwrap_or_else(|| ADD.error("please specify the name of the new target"));
+    if let Some(arg) = args.next() {
+        ADD.error(&format!("unexpected argument \"{}\"", arg));
+    }
+
+    // follow the layout of the existing targets when none is requested
+    let dir = matches.value("--dir").map(String::from).unwrap_or_else(|| {
+        if Path::new(FUZZ_TARGETS_DIR).is_dir() {
+            FUZZ_TARGETS_DIR.into()
+        } else {
+            DEFAULT_TARGETS_DIR.into()
+        }
+    });
+
+    ensure_honggfuzz_dependency();
+    create_target(&name, &dir, template);
+}
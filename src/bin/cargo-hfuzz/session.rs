@@ -0,0 +1,375 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{self, Child, Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::json::Json;
+use crate::log;
+use crate::metadata;
+use crate::notify;
+use crate::reject;
+use crate::socket_fuzzer;
+use crate::stats::{self, Stats};
+use crate::summary;
+use crate::watch::{self, Sources};
+use crate::workspace;
+
+const SIGHUP: i32 = 1;
+const SIGINT: i32 = 2;
+const SIGTERM: i32 = 15;
+const SIG_DFL: usize = 0;
+#[cfg(target_os = "linux")]
+const PR_SET_PDEATHSIG: i32 = 1;
+
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+    fn kill(pid: i32, signum: i32) -> i32;
+    #[cfg(target_os = "linux")]
+    fn prctl(option: i32, arg: u64) -> i32;
+}
+
+// most honggfuzz processes running at once, one per instance of "cargo hfuzz run-parallel"
+const MAX_CHILDREN: usize = 256;
+
+// the honggfuzz processes the signals stopping cargo-hfuzz are forwarded to, 0 being a free slot
+static CHILDREN: [AtomicI32; MAX_CHILDREN] = [const { AtomicI32::new(0) }; MAX_CHILDREN];
+
+// the signal which stopped the fuzzing, 0 without one
+static STOPPED_BY: AtomicI32 = AtomicI32::new(0);
+
+// how often the crash files are counted when the session ends after a number of them
+const CRASH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Session {
+    pub status: ExitStatus,
+    pub started: u64,
+    pub duration: Duration,
+    pub new_crash_files: Vec<PathBuf>,
+    pub corpus_before: usize,
+    pub corpus_after: usize,
+    // only known when honggfuzz's log was saved
+    pub stats: Option<Stats>,
+    // honggfuzz was stopped to be restarted on the new sources
+    pub sources_changed: bool,
+    // inputs which ran past the timeout, from honggfuzz's summary in its log
+    pub hangs: u64,
+    // edges covered at the end, from the log too
+    pub edge_cov: Option<u64>,
+}
+
+// honggfuzz names crash files like `SIGABRT.PC.7ffff7a42e97.STACK.18a3b2c1d.ADDR.0.fuzz`
+pub fn stack_of(crash_file: &std::path::Path) -> String {
+    let name = crash_file.file_name().unwrap_or_default().to_string_lossy();
+    name.split_once(".STACK.")
+        .and_then(|(_, rest)| rest.split('.').next())
+        .unwrap_or(&name)
+        .to_string()
+}
+
+impl Session {
+    // honggfuzz doesn't save again crashes it already saved, but still counts them in its `crashes_count`
+    pub fn crashes(&self) -> u64 {
+        let logged = self.stats.as_ref().map_or(0, |s| s.crashes);
+        logged.max(self.new_crash_files.len() as u64)
+    }
+
+    pub fn unique_stacks(&self) -> usize {
+        self.new_crash_files.iter().map(|f| stack_of(f)).collect::<HashSet<_>>().len()
+    }
+
+    pub fn to_json(&self, target: &str) -> Json {
+        let target_metadata = metadata::find_target(target);
+        Json::object(vec![
+            ("target", target.into()),
+            ("package", target_metadata.map(|t| t.package.clone()).into()),
+            ("src_path", target_metadata.map(|t| t.src_path.to_string_lossy().into_owned()).into()),
+            ("started", self.started.into()),
+            ("duration", self.duration.as_secs().into()),
+            ("exit_code", self.status.code().into()),
+            ("crashes", self.crashes().into()),
+            ("unique_stacks", self.unique_stacks().into()),
+            ("new_crash_files", self.new_crash_files.iter().map(|f| f.to_string_lossy().into_owned()).collect::<Vec<_>>().into()),
+            ("corpus_before", self.corpus_before.into()),
+            ("corpus_after", self.corpus_after.into()),
+            ("hangs", self.hangs.into()),
+            ("edge_cov", self.edge_cov.into()),
+            ("total_exec", self.stats.as_ref().filter(|s| s.finished).map(|s| s.total_exec).into()),
+            ("exec_per_sec", self.stats.as_ref().filter(|s| s.finished).map(|s| s.exec_per_sec).into()),
+            ("stats", self.stats.as_ref().map_or(Json::Null, Stats::to_json)),
+        ])
+    }
+}
+
+// summary of the last session of a target, written at the end of each "cargo hfuzz run"
+pub fn summary_file(target: &str) -> PathBuf {
+    workspace::report_dir(target).join("summary.json")
+}
+
+// only async-signal-safe calls here: honggfuzz stops gracefully on Ctrl-C and SIGTERM, saving its
+// stats and corpus, and exits right away on a second signal, so each one is forwarded only once
+extern "C" fn forward_signal(signum: i32) {
+    STOPPED_BY.store(signum, Ordering::Relaxed);
+    let forwarded = if signum == SIGINT { SIGINT } else { SIGTERM };
+    for child in &CHILDREN {
+        let pid = child.load(Ordering::Relaxed);
+        if pid > 0 {
+            unsafe { kill(pid, forwarded) };
+        }
+    }
+}
+
+// while honggfuzz runs, Ctrl-C, SIGTERM and SIGHUP are forwarded to it, cargo-hfuzz finishing the
+// session (its summary, the stats, the post-processing) once honggfuzz exited instead of dying first
+pub fn forward_signals(forward: bool) {
+    let handler = if forward { forward_signal as extern "C" fn(i32) as usize } else { SIG_DFL };
+    for signum in [SIGHUP, SIGINT, SIGTERM] {
+        unsafe { signal(signum, handler) };
+    }
+}
+
+// the signal which interrupted the fuzzing, if any
+pub fn stopped_by() -> Option<i32> {
+    Some(STOPPED_BY.load(Ordering::Relaxed)).filter(|s| *s != 0)
+}
+
+// starts honggfuzz in its own process group, so that the terminal doesn't signal it on top of
+// cargo-hfuzz forwarding the signals, and on Linux stopped if cargo-hfuzz dies without doing it
+pub fn spawn(command: &mut Command) -> io::Result<Child> {
+    command.process_group(0);
+    #[cfg(target_os = "linux")]
+    unsafe {
+        command.pre_exec(|| {
+            prctl(PR_SET_PDEATHSIG, SIGTERM as u64);
+            Ok(())
+        });
+    }
+    let child = log::spawn(command)?;
+    let pid = child.id() as i32;
+    if !CHILDREN.iter().any(|c| c.compare_exchange(0, pid, Ordering::Relaxed, Ordering::Relaxed).is_ok()) {
+        eprintln!("warning: too many honggfuzz processes, {} won't be stopped with cargo-hfuzz", pid);
+    }
+    Ok(child)
+}
+
+// a process started by `spawn` which exited
+pub fn reaped(child: &Child) {
+    let pid = child.id() as i32;
+    if let Some(slot) = CHILDREN.iter().find(|c| c.load(Ordering::Relaxed) == pid) {
+        slot.store(0, Ordering::Relaxed);
+    }
+}
+
+// the `-l`/`--logfile` given to honggfuzz in HFUZZ_RUN_ARGS for instance, the last one counting, where
+// its log goes instead of the copy of cargo-hfuzz
+fn user_log(command: &Command) -> Option<PathBuf> {
+    let args: Vec<String> = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    let separator = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    args[..separator].iter().enumerate().rev().find_map(|(i, arg)| match arg.as_str() {
+        "-l" | "--logfile" => args.get(i + 1).filter(|_| i + 1 < separator).map(PathBuf::from),
+        _ => arg.strip_prefix("--logfile=").map(PathBuf::from),
+    })
+}
+
+// honggfuzz exits with 0 after printing its usage, on an option it doesn't know for instance, which
+// it does before opening its `--logfile`: a session whose log has the usage, or which has no log with
+// the `--logfile` cargo-hfuzz gave, failed, the target not having run
+pub fn checked(target: &str, status: ExitStatus, log: &Path) -> ExitStatus {
+    let usage = fs::read_to_string(log).map_or(true, |log| {
+        log.lines().any(|l| l.starts_with("Usage: ") && l.contains(" [options] -- "))
+    });
+    match status.success() && usage {
+        true => {
+            eprintln!("error: honggfuzz stopped before running \"{}\", see its log in \"{}\"", target, log.display());
+            ExitStatus::from_raw(1 << 8)
+        }
+        false => status,
+    }
+}
+
+// stops honggfuzz like Ctrl-C would
+pub fn interrupt(pid: u32) {
+    unsafe { kill(pid as i32, SIGINT) };
+}
+
+// stops honggfuzz, like Ctrl-C would, once `max_unique_crashes` crashes with different stacks were saved
+fn stop_after_crashes(target: &str, pid: u32, known_crashes: HashSet<PathBuf>, max_unique_crashes: usize, done: Arc<AtomicBool>) {
+    let target = target.to_string();
+    thread::spawn(move || {
+        while !done.load(Ordering::Relaxed) {
+            let stacks: HashSet<String> = workspace::crash_files(&target).iter()
+                .filter(|f| !known_crashes.contains(*f))
+                .map(|f| stack_of(f))
+                .collect();
+            if stacks.len() >= max_unique_crashes {
+                eprintln!("{}: {} unique crashes found, stopping", target, stacks.len());
+                unsafe { kill(pid as i32, SIGINT) };
+                return;
+            }
+            thread::sleep(CRASH_POLL_INTERVAL);
+        }
+    });
+}
+
+// stops honggfuzz, like Ctrl-C would, once the sources differ from `sources`
+fn stop_on_change(pid: u32, sources: Sources, done: Arc<AtomicBool>, changed: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        while !done.load(Ordering::Relaxed) {
+            if watch::snapshot() != sources {
+                changed.store(true, Ordering::Relaxed);
+                unsafe { kill(pid as i32, SIGINT) };
+                return;
+            }
+            thread::sleep(watch::POLL_INTERVAL);
+        }
+    });
+}
+
+// fuzzes until honggfuzz stops, until `max_unique_crashes` crashes with different stacks are found, or
+// until the `watched` sources change
+pub fn fuzz(target: &str, mut command: Command, max_unique_crashes: Option<usize>, watched: Option<Sources>) -> Session {
+    log::select_target(target);
+    let verbose = command.get_args().any(|a| a == "-v" || a == "--verbose");
+    let interactive = !verbose && io::stderr().is_terminal();
+    if interactive {
+        let _ = fs::remove_file(stats::log_file(target)); // it would be about a previous session
+    } else {
+        command.stderr(Stdio::piped());
+    }
+
+    let known_crashes: HashSet<PathBuf> = workspace::crash_files(target).into_iter().collect();
+    let known_inputs: HashSet<PathBuf> = workspace::corpus_files(target).into_iter().collect();
+    let corpus_before = known_inputs.len();
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let start = Instant::now();
+    let previous_edges = summary::previous_edges(target);
+    summary::start(target, &command);
+    let mut child = spawn(&mut command).unwrap_or_else(|error| {
+        eprintln!("cannot execute {} ({}), try to execute \"cargo hfuzz build\" from fuzzed project directory", command.get_program().to_string_lossy(), error);
+        process::exit(1);
+    });
+
+    log::event("fuzzer_started", vec![("target", target.into()), ("pid", u64::from(child.id()).into())]);
+
+    // Ctrl-C is for honggfuzz, which stops gracefully, we're still needed afterwards
+    forward_signals(true);
+
+    let done = Arc::new(AtomicBool::new(false));
+    if let Some(max_unique_crashes) = max_unique_crashes {
+        stop_after_crashes(target, child.id(), known_crashes.clone(), max_unique_crashes, done.clone());
+    }
+    let sources_changed = Arc::new(AtomicBool::new(false));
+    if let Some(sources) = watched {
+        stop_on_change(child.id(), sources, done.clone(), sources_changed.clone());
+    }
+    let notifier = notify::watch(target, known_crashes.clone(), done.clone());
+    let driver = socket_fuzzer::drive(child.id(), done.clone());
+
+    if let Some(stderr) = child.stderr.take() {
+        let log_file = stats::log_file(target);
+        let mut log = File::create(&log_file).map_err(|_| {
+            eprintln!("warning: failed to create \"{}\"", log_file.display());
+        }).ok();
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            eprintln!("{}", line);
+            if let Some(log) = &mut log {
+                let _ = writeln!(log, "{}", line);
+            }
+        }
+    }
+    let status = child.wait().unwrap();
+    log::exited(&child, status);
+    reaped(&child);
+    // without the copy of the log, when it couldn't be created, honggfuzz isn't known to have failed
+    let status = match interactive || !stats::log_file(target).is_file() {
+        true => status,
+        false => checked(target, status, &stats::log_file(target)),
+    };
+    log::event("fuzzer_exited", vec![("target", target.into()), ("exit_code", status.code().into())]);
+    done.store(true, Ordering::Relaxed);
+    if let Some(notifier) = notifier {
+        let _ = notifier.join();
+    }
+    if let Some(driver) = driver {
+        let _ = driver.join();
+    }
+
+    forward_signals(false);
+
+    let rejected = reject::drop_rejected(target, &crate::release_binary(target), &known_inputs);
+    if rejected > 0 {
+        log::note(&format!("{}: removed {} inputs rejected by the harness from the corpus", target, rejected));
+    }
+    let added = workspace::import_added(target);
+    if added > 0 {
+        log::note(&format!("{}: added {} inputs of add_to_corpus to the corpus", target, added));
+    }
+
+    let stats = match (user_log(&command), interactive) {
+        (Some(log), _) => stats::read_log(target, &log),
+        (None, false) => stats::read_stats(target),
+        (None, true) => None,
+    };
+    let hangs = stats.as_ref().filter(|s| s.finished).map_or(0, |s| s.hangs);
+    let edge_cov = stats.as_ref().filter(|s| s.finished).map(|s| s.edge_cov);
+    let session = Session {
+        status,
+        started,
+        duration: start.elapsed(),
+        new_crash_files: workspace::crash_files(target).into_iter().filter(|f| !known_crashes.contains(f)).collect(),
+        corpus_before,
+        corpus_after: workspace::corpus_files(target).len(),
+        stats,
+        sources_changed: sources_changed.load(Ordering::Relaxed),
+        hangs,
+        edge_cov,
+    };
+    summary::end(target, &session, previous_edges);
+    let summary_file = summary_file(target);
+    if fs::write(&summary_file, format!("{}\n", session.to_json(target))).is_err() {
+        eprintln!("warning: failed to write \"{}\"", summary_file.display());
+    }
+    log::event("session_ended", vec![("target", target.into()), ("session", session.to_json(target))]);
+    // with `--watch`, the session goes on with the new sources
+    if !session.sources_changed {
+        notify::session_ended(target, &session);
+    }
+    session
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_logs() {
+        let log = |args: &[&str]| user_log(Command::new("honggfuzz").args(args));
+        assert_eq!(log(&["-W", "w", "--", "target", "-l", "x"]), None);
+        assert_eq!(log(&["-l", "a.log", "-n", "1", "--logfile", "b.log", "--", "target"]), Some(PathBuf::from("b.log")));
+        assert_eq!(log(&["--logfile=c.log", "--", "target"]), Some(PathBuf::from("c.log")));
+        assert_eq!(log(&["-l", "--", "target"]), None);
+    }
+
+    #[test]
+    fn usage_fails() {
+        let dir = std::env::temp_dir().join(format!("cargo-hfuzz-session-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("honggfuzz.log");
+        let ok = ExitStatus::from_raw(0);
+
+        fs::write(&log, "hfuzz_target/honggfuzz: unrecognized option '--statsfile'\nUsage: hfuzz_target/honggfuzz [options] -- path_to_command [args]\nOptions:\n").unwrap();
+        assert_eq!(checked("example", ok, &log).code(), Some(1));
+        // with the `--logfile` of the user, the copy of cargo-hfuzz has no summary
+        fs::write(&log, "").unwrap();
+        assert!(checked("example", ok, &log).success());
+        fs::remove_file(&log).unwrap();
+        assert_eq!(checked("example", ok, &log).code(), Some(1));
+        assert_eq!(checked("example", ExitStatus::from_raw(2 << 8), &log).code(), Some(2));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
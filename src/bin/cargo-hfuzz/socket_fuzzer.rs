@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::cli::{self, Opt};
+use crate::log;
+use crate::session;
+
+pub const SOCKET_FUZZER: Opt = Opt {
+    name: "--socket-fuzzer",
+    value: Some("[DRIVER]"),
+    help: "leaves the inputs to an external driver on honggfuzz's socket (HFUZZ_SOCKET_FUZZER), run by cargo-hfuzz if given, fuzzed by one job",
+};
+
+// how often the socket of honggfuzz and the driver are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// the command line of the driver, run while honggfuzz does
+static DRIVER: OnceLock<Vec<String>> = OnceLock::new();
+
+pub fn select_driver(driver: &str) {
+    let words = cli::shell_words(driver).unwrap_or_else(|error| {
+        eprintln!("error: invalid driver \"{}\" for \"--socket-fuzzer\" ({})", driver, error);
+        std::process::exit(1);
+    });
+    if !words.is_empty() {
+        let _ = DRIVER.set(words);
+    }
+}
+
+// the socket honggfuzz listens on until a driver connects, named after its pid
+pub fn socket(pid: u32) -> PathBuf {
+    PathBuf::from(format!("/tmp/honggfuzz_socket.{}", pid))
+}
+
+// honggfuzz starts the target without an input to read, which `-s` has it accept, and not in persistent
+// mode, the target being a server which doesn't call `fuzz!`
+pub fn honggfuzz_args() -> Vec<String> {
+    ["--socket_fuzzer", "-s", "-n", "1"].iter().map(|a| a.to_string()).collect()
+}
+
+// runs the driver once honggfuzz `pid` listens on its socket, and stops honggfuzz like Ctrl-C would once
+// the driver exits, the driver being stopped if honggfuzz is `done` first
+pub fn drive(pid: u32, done: Arc<AtomicBool>) -> Option<JoinHandle<()>> {
+    let driver = DRIVER.get()?;
+    Some(thread::spawn(move || {
+        let socket = socket(pid);
+        while !socket.exists() {
+            if done.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        let mut command = Command::new(&driver[0]);
+        command.args(&driver[1..]).env("HFUZZ_SOCKET_FUZZER", &socket);
+        match log::spawn(&mut command) {
+            Ok(child) => run(child, &driver[0], pid, &done),
+            Err(error) => {
+                eprintln!("error: failed to run the driver \"{}\" ({})", driver[0], error);
+                session::interrupt(pid);
+            }
+        }
+        // honggfuzz leaves it behind when the driver halts it
+        let _ = fs::remove_file(&socket);
+    }))
+}
+
+fn run(mut child: Child, name: &str, pid: u32, done: &AtomicBool) {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                log::exited(&child, status);
+                if !status.success() {
+                    eprintln!("warning: the driver \"{}\" failed ({})", name, status);
+                }
+                if !done.load(Ordering::Relaxed) {
+                    session::interrupt(pid);
+                }
+                return;
+            }
+            Ok(None) if done.load(Ordering::Relaxed) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(_) => return,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::cli::{Opt, Subcommand};
+use crate::json::Json;
+use crate::metadata;
+use crate::workspace;
+
+pub const STATS: Subcommand = Subcommand {
+    name: "stats",
+    usage: "[--json] [TARGET ...]",
+    about: "prints the coverage, execs/sec, corpus size and crash counts of the last run of the targets",
+    options: &[Opt { name: "--json", value: None, help: "prints them as JSON" }],
+    passthrough: None,
+};
+
+pub struct Stats {
+    pub updated: u64,
+    pub last_cov_update: u64,
+    pub edge_cov: u64,
+    pub pc_cov: u64,
+    pub unique_crashes: u64,
+    pub dup_crashes: u64,
+    // only known once honggfuzz printed its summary, at the end of the session
+    pub finished: bool,
+    pub run_time: u64,
+    pub total_exec: u64,
+    pub exec_per_sec: u64,
+    pub crashes: u64,
+    pub hangs: u64,
+}
+
+// honggfuzz log of the last session of a target, saved by "cargo hfuzz run" when not in a terminal
+pub fn log_file(target: &str) -> PathBuf {
+    workspace::log_dir(target).join("honggfuzz.log")
+}
+
+// the file of one of the instances fuzzing a target at once, `honggfuzz.log` becoming
+// `honggfuzz.1.log` for the second one for instance, the first one keeping the usual name
+pub fn instance_file(file: PathBuf, index: usize) -> PathBuf {
+    match (index, file.file_stem(), file.extension()) {
+        (0, _, _) | (_, None, _) | (_, _, None) => file,
+        (_, Some(stem), Some(extension)) => {
+            let name = format!("{}.{}.{}", stem.to_string_lossy(), index, extension.to_string_lossy());
+            file.with_file_name(name)
+        }
+    }
+}
+
+// the logs of all the instances of a target
+pub fn log_files(target: &str) -> Vec<PathBuf> {
+    let file = log_file(target);
+    let (stem, extension) = (file.file_stem().unwrap().to_string_lossy(), file.extension().unwrap().to_string_lossy());
+    workspace::files_in(&workspace::log_dir(target)).into_iter()
+        .filter(|f| {
+            let name = f.file_name().unwrap_or_default().to_string_lossy();
+            let index = name.strip_prefix(&*stem).and_then(|n| n.strip_suffix(&*extension)).and_then(|n| n.strip_suffix('.'));
+            index.is_some_and(|i| i.is_empty() || i.strip_prefix('.').is_some_and(|i| i.parse::<usize>().is_ok()))
+        })
+        .collect()
+}
+
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+// `key:value` fields of a log line, e.g. `Summary iterations:42 time:1 speed:42 ...`
+fn field(line: &str, key: &str) -> u64 {
+    line.split_whitespace()
+        .find_map(|f| f.strip_prefix(key).and_then(|f| f.strip_prefix(':')))
+        .and_then(|v| v.trim_end_matches(',').parse().ok())
+        .unwrap_or(0)
+}
+
+// statistics of the last session of a target, gathered from the honggfuzz log, if any
+pub fn read_stats(target: &str) -> Option<Stats> {
+    read_log(target, &log_file(target))
+}
+
+// the same from any log of a target, like the ones of the instances of "cargo hfuzz run-parallel"
+pub fn read_log(target: &str, log: &Path) -> Option<Stats> {
+    let content = fs::read_to_string(log).ok()?;
+    Some(Stats {
+        updated: modified(log),
+        // honggfuzz adds an input to the corpus each time it finds new coverage
+        last_cov_update: workspace::corpus_files(target).iter().map(|f| modified(f)).max().unwrap_or(0),
+        ..parse_log(&content)
+    })
+}
+
+// the stats of the lines of a log, out of its times
+fn parse_log(content: &str) -> Stats {
+    let mut stats = Stats {
+        updated: 0,
+        last_cov_update: 0,
+        edge_cov: 0,
+        pc_cov: 0,
+        unique_crashes: 0,
+        dup_crashes: 0,
+        finished: false,
+        run_time: 0,
+        total_exec: 0,
+        exec_per_sec: 0,
+        crashes: 0,
+        hangs: 0,
+    };
+    for line in content.lines() {
+        if let Some((_, new)) = line.split_once(" New:") {
+            // new coverage: `Sz:1 Tm:2us (i/b/h/e/p/c) New:0/0/0/3/2/0, Cur:0/0/0/30/20/5`, `Cur` being
+            // the coverage of that input, and the new edges and pcs adding up to the ones of the corpus
+            let new: Vec<u64> = new.split(',').next().unwrap_or_default().split('/').map(|v| v.trim().parse().unwrap_or(0)).collect();
+            stats.edge_cov += new.get(3).copied().unwrap_or(0);
+            stats.pc_cov += new.get(4).copied().unwrap_or(0);
+        } else if line.contains("Crash: saved as") || line.contains("Ok, that's interesting, saving input") {
+            stats.unique_crashes += 1;
+        } else if line.contains("Crash (dup):") {
+            stats.dup_crashes += 1;
+        } else if line.contains("Summary iterations:") {
+            stats.finished = true;
+            stats.run_time = field(line, "time");
+            stats.total_exec = field(line, "iterations");
+            stats.exec_per_sec = field(line, "speed");
+            stats.crashes = field(line, "crashes_count");
+            stats.hangs = field(line, "timeout_count");
+        }
+    }
+    stats
+}
+
+impl Stats {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("updated", self.updated.into()),
+            ("last_cov_update", self.last_cov_update.into()),
+            ("edge_cov", self.edge_cov.into()),
+            ("pc_cov", self.pc_cov.into()),
+            ("unique_crashes", self.unique_crashes.into()),
+            ("dup_crashes", self.dup_crashes.into()),
+            ("finished", self.finished.into()),
+            ("run_time", self.run_time.into()),
+            ("total_exec", self.total_exec.into()),
+            ("exec_per_sec", self.exec_per_sec.into()),
+            ("crashes", self.crashes.into()),
+            ("hangs", self.hangs.into()),
+        ])
+    }
+}
+
+pub fn hfuzz_stats<T>(args: T) where T: std::iter::Iterator<Item=String> {
+    let matches = STATS.parse(args);
+    let json = matches.flag("--json");
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+
+    let mut reports = Vec::new();
+    for target in &targets {
+        let stats = read_stats(target);
+        let corpus = workspace::corpus_files(target).len();
+        let crash_files = workspace::crash_files(target).len();
+
+        if json {
+            reports.push(Json::object(vec![
+                ("target", target.as_str().into()),
+                ("corpus", corpus.into()),
+                ("crash_files", crash_files.into()),
+                ("stats", stats.as_ref().map_or(Json::Null, Stats::to_json)),
+            ]));
+            continue;
+        }
+
+        println!("{}", target);
+        println!("  corpus size:     {}", corpus);
+        println!("  crash files:     {}", crash_files);
+        match stats {
+            Some(stats) => {
+                println!("  edge coverage:   {}", stats.edge_cov);
+                println!("  pc coverage:     {}", stats.pc_cov);
+                println!("  new crashes:     {} ({} already known)", stats.unique_crashes, stats.dup_crashes);
+                if stats.finished {
+                    println!("  run time:        {}s", stats.run_time);
+                    println!("  total execs:     {}", stats.total_exec);
+                    println!("  execs/sec:       {}", stats.exec_per_sec);
+                    println!("  crashes:         {}", stats.crashes);
+                    println!("  hangs:           {}", stats.hangs);
+                } else {
+                    println!("  no summary yet, the session is still running or was interrupted");
+                }
+                if stats.last_cov_update > 0 && stats.updated >= stats.last_cov_update {
+                    println!("  no new coverage: for {}s", stats.updated - stats.last_cov_update);
+                }
+            }
+            None => println!("  no statistics yet, run \"cargo hfuzz run {}\" first", target),
+        }
+    }
+
+    if json {
+        println!("{}", Json::Array(reports));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage() {
+        let stats = parse_log("\
+Sz:17 Tm:1654us (i/b/h/e/p/c) New:0/0/0/32/1/358, Cur:0/0/0/32/1/358
+Sz:4 Tm:211us (i/b/h/e/p/c) New:0/0/0/5/0/12, Cur:0/0/0/30/1/340
+");
+        assert_eq!((stats.edge_cov, stats.pc_cov), (37, 1));
+        assert!(!stats.finished);
+    }
+
+    #[test]
+    fn crashes() {
+        let stats = parse_log("\
+Crash: saved as '/tmp/SIGABRT.PC.7ffff7a42e97.STACK.badbeef.CODE.-6.ADDR.0.INSTR.mov.fuzz'
+Crash (dup): '/tmp/SIGABRT.PC.7ffff7a42e97.STACK.badbeef.CODE.-6.ADDR.0.INSTR.mov.fuzz' already exists, skipping
+Crash (dup): '/tmp/SIGABRT.PC.7ffff7a42e97.STACK.badbeef.CODE.-6.ADDR.0.INSTR.mov.fuzz' exists and is smaller, skipping
+");
+        assert_eq!((stats.unique_crashes, stats.dup_crashes), (1, 2));
+    }
+
+    #[test]
+    fn summary() {
+        let stats = parse_log("\
+Sz:17 Tm:1654us (i/b/h/e/p/c) New:0/0/0/32/1/358, Cur:0/0/0/32/1/358
+Summary iterations:12034 time:10 speed:1203 crashes_count:3 timeout_count:1 new_units_added:5 slowest_unit_ms:41 guard_nb:1024 branch_coverage_percent:3 peak_rss_mb:38
+");
+        assert!(stats.finished);
+        assert_eq!((stats.total_exec, stats.run_time, stats.exec_per_sec), (12034, 10, 1203));
+        assert_eq!((stats.crashes, stats.hangs), (3, 1));
+        assert_eq!(stats.edge_cov, 32);
+    }
+}
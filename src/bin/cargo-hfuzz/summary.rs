@@ -0,0 +1,122 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config;
+use crate::json::Json;
+use crate::log;
+use crate::sanitizer;
+use crate::session::{self, Session};
+use crate::workspace;
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+// the arguments cargo-hfuzz always gives honggfuzz, and their values, which say nothing of the settings
+const HOUSEKEEPING: &[(&str, bool)] = &[("-W", true), ("-f", true), ("--output", true), ("-P", false)];
+
+// `--no-color`, given before the command
+pub fn disable_colors() {
+    NO_COLOR.store(true, Ordering::Relaxed);
+}
+
+// NO_COLOR (https://no-color.org) turns them off too, and so does stderr not being a terminal
+fn colors() -> bool {
+    !NO_COLOR.load(Ordering::Relaxed) && env::var_os("NO_COLOR").filter(|v| !v.is_empty()).is_none() && io::stderr().is_terminal()
+}
+
+const BOLD: &str = "1";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+
+fn paint(color: &str, text: &str) -> String {
+    match colors() {
+        true => format!("\x1b[{}m{}\x1b[0m", color, text),
+        false => text.to_string(),
+    }
+}
+
+fn shown() -> bool {
+    !log::quiet() && !log::json()
+}
+
+// the settings honggfuzz runs with, what comes from hfuzz.toml, Cargo.toml, HFUZZ_RUN_ARGS and the
+// command line put together
+fn flags(command: &Command) -> String {
+    let mut args = command.get_args().map(|a| a.to_string_lossy().into_owned()).take_while(|a| a != "--");
+    let mut flags = Vec::new();
+    while let Some(arg) = args.next() {
+        match HOUSEKEEPING.iter().find(|(name, _)| *name == arg) {
+            Some((_, true)) => {
+                args.next();
+            }
+            Some((_, false)) => {}
+            None => flags.push(arg),
+        }
+    }
+    flags.join(" ")
+}
+
+// what is about to be fuzzed, and how
+pub fn start(target: &str, command: &Command) {
+    if !shown() {
+        return;
+    }
+    let input_dirs = workspace::input_dirs(target);
+    let others: usize = input_dirs[1..].iter().map(|d| workspace::files_in(d).len()).sum();
+    let corpus = match input_dirs.len() {
+        1 => format!("{} inputs in {}", workspace::corpus_files(target).len(), input_dirs[0].display()),
+        n => format!("{} inputs in {}, and {} in {} other input directories", workspace::corpus_files(target).len(), input_dirs[0].display(), others, n - 1),
+    };
+    let seeds = config::seeds(target);
+    let seeds = match seeds.is_empty() {
+        true => "none".to_string(),
+        false => seeds.iter().map(|s| s.display().to_string()).collect::<Vec<_>>().join(", "),
+    };
+    eprintln!("{} fuzzing with honggfuzz", paint(BOLD, target));
+    eprintln!("  flags:      {}", flags(command));
+    if let Some(sanitizer) = sanitizer::selected() {
+        eprintln!("  sanitizer:  {}", sanitizer);
+    }
+    eprintln!("  corpus:     {}", corpus);
+    eprintln!("  seeds:      {}", seeds);
+}
+
+// the edges covered at the end of the previous session, from its summary
+pub fn previous_edges(target: &str) -> Option<u64> {
+    let summary = Json::parse(&fs::read_to_string(session::summary_file(target)).ok()?).ok()?;
+    match summary.get("edge_cov") {
+        Some(Json::Number(n)) => Some(*n as u64),
+        _ => None,
+    }
+}
+
+// what the session found, the coverage compared to the end of the previous one
+pub fn end(target: &str, session: &Session, previous_edges: Option<u64>) {
+    if !shown() {
+        return;
+    }
+    let new_inputs = session.corpus_after as i64 - session.corpus_before as i64;
+    let crashes = match session.new_crash_files.len() {
+        0 => paint(GREEN, "none"),
+        n => paint(RED, &format!("{} new crash files, {} unique stacks", n, session.unique_stacks())),
+    };
+    let hangs = match session.hangs {
+        0 => paint(GREEN, "none"),
+        n => paint(YELLOW, &n.to_string()),
+    };
+    let coverage = match (session.edge_cov, previous_edges) {
+        (Some(edges), Some(previous)) if edges > previous => format!("{} edges ({} since the last session)", edges, paint(GREEN, &format!("+{}", edges - previous))),
+        (Some(edges), Some(previous)) => format!("{} edges ({:+} since the last session)", edges, edges as i64 - previous as i64),
+        (Some(edges), None) => format!("{} edges", edges),
+        (None, _) => "unknown".to_string(),
+    };
+    eprintln!("{} session ended after {}s, exit code {}", paint(BOLD, target), session.duration.as_secs(),
+        session.status.code().map_or("none".to_string(), |c| c.to_string()));
+    eprintln!("  new inputs: {:+} (corpus {} -> {})", new_inputs, session.corpus_before, session.corpus_after);
+    eprintln!("  crashes:    {}", crashes);
+    eprintln!("  hangs:      {}", hangs);
+    eprintln!("  coverage:   {}", coverage);
+}
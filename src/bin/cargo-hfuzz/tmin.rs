@@ -0,0 +1,148 @@
+use std::fs;
+use std::process;
+use std::path::{Path, PathBuf};
+
+use crate::{BuildType, hfuzz_build, debug_binary};
+use crate::cli::Subcommand;
+use crate::crash::{self, Outcome};
+use crate::features;
+use crate::log;
+use crate::workspace;
+
+pub const TMIN: Subcommand = Subcommand {
+    name: "tmin",
+    usage: "[OPTIONS] TARGET CRASH_FILENAME",
+    about: "shrinks a crashing input while making sure it still crashes with the same stack",
+    options: &[features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES],
+    passthrough: None,
+};
+
+struct Minimizer {
+    binary: PathBuf,
+    candidate: PathBuf,
+    stack_hash: u64,
+    runs: usize,
+}
+
+impl Minimizer {
+    fn run(&mut self, data: &[u8]) -> Outcome {
+        fs::write(&self.candidate, data).unwrap_or_else(|_| {
+            eprintln!("error: failed to write \"{}\"", self.candidate.display());
+            process::exit(1);
+        });
+        self.runs += 1;
+        crash::replay(&self.binary, &self.candidate, crash::DEFAULT_TIMEOUT)
+    }
+
+    // a candidate is kept only if it still crashes the same way
+    fn still_crashes(&mut self, data: &[u8]) -> bool {
+        let outcome = self.run(data);
+        outcome.crashed && outcome.stack_hash() == self.stack_hash
+    }
+
+    fn minimize(&mut self, mut data: Vec<u8>) -> Vec<u8> {
+        // remove chunks of decreasing size
+        let mut chunk = data.len().div_ceil(2);
+        while chunk > 0 && !data.is_empty() {
+            let mut removed = false;
+            let mut start = 0;
+            while start < data.len() {
+                let end = (start + chunk).min(data.len());
+                let candidate: Vec<u8> = data[..start].iter().chain(&data[end..]).copied().collect();
+                if self.still_crashes(&candidate) {
+                    data = candidate;
+                    removed = true;
+                } else {
+                    start += chunk;
+                }
+            }
+            if !removed {
+                chunk /= 2;
+            }
+        }
+
+        // then simplify the remaining bytes
+        for i in 0..data.len() {
+            for simpler in [b'\0', b'0'] {
+                if data[i] == simpler {
+                    break;
+                }
+                let mut candidate = data.clone();
+                candidate[i] = simpler;
+                if self.still_crashes(&candidate) {
+                    data = candidate;
+                    break;
+                }
+            }
+        }
+
+        data
+    }
+}
+
+// the smallest input crashing `binary` with the same stack as `data`, found by writing candidates to
+// `candidate`, and the number of runs it took, or the outcome of `data` when it doesn't crash
+pub fn minimize(binary: &Path, candidate: &Path, data: &[u8]) -> Result<(Vec<u8>, usize), Outcome> {
+    let mut minimizer = Minimizer {
+        binary: binary.to_path_buf(),
+        candidate: candidate.to_path_buf(),
+        stack_hash: 0,
+        runs: 0,
+    };
+
+    let reference = minimizer.run(data);
+    if !reference.crashed {
+        let _ = fs::remove_file(candidate);
+        return Err(reference);
+    }
+    minimizer.stack_hash = reference.stack_hash();
+    log::note(&format!("minimizing {} bytes crashing with stack hash {:016x}", data.len(), minimizer.stack_hash));
+
+    let minimized = minimizer.minimize(data.to_vec());
+    let _ = fs::remove_file(candidate);
+    Ok((minimized, minimizer.runs))
+}
+
+pub fn hfuzz_tmin<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = TMIN.parse(args);
+    features::select(&TMIN, &matches);
+    let mut args = matches.args.into_iter();
+    let target = args.next().unwrap_or_else(|| TMIN.error("please specify the name of the target"));
+    let crash_filename = args.next().unwrap_or_else(|| TMIN.error("please specify the crash filename"));
+    if let Some(arg) = args.next() {
+        TMIN.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    log::select_target(&target);
+    let data = fs::read(&crash_filename).unwrap_or_else(|_| {
+        eprintln!("error: failed to read \"{}\"", &crash_filename);
+        process::exit(1);
+    });
+
+    hfuzz_build(vec!["--bin".to_string(), target.clone()].into_iter(), crate_root, &BuildType::Debug);
+    features::check_replay(&target, &[PathBuf::from(&crash_filename)]);
+
+    let target_workspace = workspace::target_workspace(&target);
+    fs::create_dir_all(&target_workspace).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", target_workspace.display());
+        process::exit(1);
+    });
+
+    let (minimized, runs) = match minimize(&debug_binary(&target), &target_workspace.join(".tmin.candidate"), &data) {
+        Ok(minimized) => minimized,
+        Err(reference) => {
+            if reference.timed_out {
+                eprintln!("error: \"{}\" makes \"{}\" hang, only crashes can be minimized", &crash_filename, &target);
+            } else {
+                eprintln!("error: \"{}\" does not crash \"{}\", nothing to minimize", &crash_filename, &target);
+            }
+            process::exit(1);
+        }
+    };
+
+    let output = format!("{}.minimized", crash_filename);
+    fs::write(&output, &minimized).unwrap_or_else(|_| {
+        eprintln!("error: failed to write \"{}\"", &output);
+        process::exit(1);
+    });
+    println!("minimized {} bytes to {} bytes in {} runs: {}", data.len(), minimized.len(), runs, &output);
+}
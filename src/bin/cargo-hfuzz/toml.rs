@@ -0,0 +1,286 @@
+// just enough TOML for the configuration files: tables, and keys set to strings, integers,
+// booleans or arrays of them
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+pub struct Entry {
+    // the table the key belongs to, e.g. `["targets", "example"]` under `[targets.example]`
+    pub table: Vec<String>,
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, message))
+    }
+
+    // skips spaces, and newlines and comments too between array values
+    fn skip(&mut self, newlines: bool) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => self.line += 1,
+                '#' => {
+                    while self.chars.peek().is_some_and(|&c| c != '\n') {
+                        self.chars.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.chars.next();
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip(false);
+        match self.chars.next() {
+            None => Ok(()),
+            Some('\n') => {
+                self.line += 1;
+                Ok(())
+            }
+            Some(c) => self.error(&format!("unexpected '{}'", c)),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        if self.chars.peek() == Some(&'"') {
+            self.chars.next();
+            return self.string('"');
+        }
+        let mut key = String::new();
+        while let Some(&c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '-') {
+            key.push(c);
+            self.chars.next();
+        }
+        if key.is_empty() {
+            return self.error("expected a key");
+        }
+        Ok(key)
+    }
+
+    // dotted keys, like `targets.example`
+    fn keys(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip(false);
+            keys.push(self.key()?);
+            self.skip(false);
+            if self.chars.peek() != Some(&'.') {
+                return Ok(keys);
+            }
+            self.chars.next();
+        }
+    }
+
+    // the rest of a string after its opening quote, `'` strings being literal
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') if quote == '"' => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('\\') => string.push('\\'),
+                    Some('"') => string.push('"'),
+                    Some(c) => return self.error(&format!("unsupported escape '\\{}'", c)),
+                    None => return self.error("unterminated string"),
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek().copied() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                self.chars.next();
+                Ok(Value::String(self.string(quote)?))
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.chars.peek() == Some(&']') {
+                        self.chars.next();
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip(true);
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(values)),
+                        _ => return self.error("expected ',' or ']' in the array"),
+                    }
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '-' || **c == '+') {
+                    word.push(c);
+                    self.chars.next();
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => word.replace('_', "").parse().map(Value::Integer)
+                        .or_else(|_| self.error(&format!("unsupported value \"{}\", expected a string, an integer, a boolean or an array", word))),
+                }
+            }
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<Entry>, String> {
+    let mut parser = Parser { chars: content.chars().peekable(), line: 1 };
+    let mut table = Vec::new();
+    let mut entries = Vec::new();
+    loop {
+        parser.skip(true);
+        match parser.chars.peek() {
+            None => return Ok(entries),
+            Some('[') => {
+                parser.chars.next();
+                table = parser.keys()?;
+                if parser.chars.next() != Some(']') {
+                    return parser.error("expected ']' at the end of the table name");
+                }
+            }
+            Some(_) => {
+                let line = parser.line;
+                let mut keys = parser.keys()?;
+                if parser.chars.next() != Some('=') {
+                    return parser.error("expected '=' after the key");
+                }
+                parser.skip(false);
+                let value = parser.value()?;
+                let key = keys.pop().unwrap();
+                entries.push(Entry { table: table.iter().cloned().chain(keys).collect(), key, value, line });
+            }
+        }
+        parser.end_of_line()?;
+    }
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_strings(&self) -> Option<Vec<String>> {
+        match self {
+            Value::Array(values) => values.iter().map(|v| v.as_str().map(String::from)).collect(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `table.key = value` for every entry
+    fn flattened(content: &str) -> Vec<(String, Value, usize)> {
+        parse(content).unwrap().into_iter()
+            .map(|e| (e.table.into_iter().chain(Some(e.key)).collect::<Vec<_>>().join("."), e.value, e.line))
+            .collect()
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn strings() {
+        let entries = flattened(r#"
+a = "tab\t \"quoted\" back\\slash\n"
+b = 'C:\no\escape'
+"quoted key" = ""
+"#);
+        assert_eq!(entries, [
+            ("a".to_string(), string("tab\t \"quoted\" back\\slash\n"), 2),
+            ("b".to_string(), string("C:\\no\\escape"), 3),
+            ("quoted key".to_string(), string(""), 4),
+        ]);
+    }
+
+    #[test]
+    fn integers_and_booleans() {
+        let entries = flattened("a = 1_000\nb = -3\nc = +4\nd = true\ne = false\n");
+        let values: Vec<Value> = entries.into_iter().map(|(_, v, _)| v).collect();
+        assert_eq!(values, [Value::Integer(1000), Value::Integer(-3), Value::Integer(4), Value::Bool(true), Value::Bool(false)]);
+    }
+
+    #[test]
+    fn comments() {
+        let entries = flattened("# a comment\n\na = \"# not a comment\" # a comment\n  # indented\n[run] # the table\nb = 1");
+        assert_eq!(entries, [
+            ("a".to_string(), string("# not a comment"), 3),
+            ("run.b".to_string(), Value::Integer(1), 6),
+        ]);
+    }
+
+    #[test]
+    fn arrays() {
+        let entries = flattened("a = []\nb = [1, \"two\", [false]]\nc = [\n    \"x\", # first\n    \"y\",\n]\nd = 1\n");
+        assert_eq!(entries, [
+            ("a".to_string(), Value::Array(vec![]), 1),
+            ("b".to_string(), Value::Array(vec![Value::Integer(1), string("two"), Value::Array(vec![Value::Bool(false)])]), 2),
+            ("c".to_string(), Value::Array(vec![string("x"), string("y")]), 3),
+            ("d".to_string(), Value::Integer(1), 7),
+        ]);
+        assert_eq!(entries[2].1.as_strings(), Some(vec!["x".to_string(), "y".to_string()]));
+        assert_eq!(entries[1].1.as_strings(), None);
+    }
+
+    #[test]
+    fn dotted_tables_and_keys() {
+        let entries = parse("[targets.example]\nargs = []\nrun.timeout = 5\n[ \"targets\" . \"other-one\" ]\ndict = 'a'\n").unwrap();
+        let tables: Vec<(Vec<String>, &str)> = entries.iter().map(|e| (e.table.clone(), e.key.as_str())).collect();
+        assert_eq!(tables, [
+            (vec!["targets".to_string(), "example".to_string()], "args"),
+            (vec!["targets".to_string(), "example".to_string(), "run".to_string()], "timeout"),
+            (vec!["targets".to_string(), "other-one".to_string()], "dict"),
+        ]);
+    }
+
+    #[test]
+    fn errors_tell_the_line() {
+        let error = |content| parse(content).err().unwrap();
+        assert_eq!(error("a = 1\n\nb = \"open\n"), "line 3: unterminated string");
+        assert_eq!(error("a = 1\nb 2"), "line 2: expected '=' after the key");
+        assert_eq!(error("a = [\n1,\n2\n3]"), "line 4: expected ',' or ']' in the array");
+        assert_eq!(error("[run\na = 1"), "line 1: expected ']' at the end of the table name");
+        assert_eq!(error("a = 1 2"), "line 1: unexpected '2'");
+        assert_eq!(error("a = 1.5"), "line 1: unexpected '.'");
+        assert_eq!(error("a = yes"), "line 1: unsupported value \"yes\", expected a string, an integer, a boolean or an array");
+        assert_eq!(error("a = \"\\x\""), "line 1: unsupported escape '\\x'");
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use crate::{BuildType, hfuzz_build, debug_binary};
+use crate::annotate;
+use crate::cli::{Opt, Subcommand};
+use crate::crash::{self, Outcome};
+use crate::features;
+use crate::log;
+use crate::sarif;
+use crate::tmin;
+use crate::workspace;
+
+pub const TRIAGE: Subcommand = Subcommand {
+    name: "triage",
+    usage: "[OPTIONS] TARGET",
+    about: "groups the crash files of a target by stack and prints one representative of each bug",
+    options: &[
+        Opt { name: "--sarif", value: Some("FILE"), help: "also writes the bugs to FILE as SARIF, for GitHub code scanning" },
+        annotate::ANNOTATE,
+        features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    ],
+    passthrough: None,
+};
+
+// number of frames shown for each bucket
+const SHOWN_FRAMES: usize = 8;
+
+pub struct Bucket {
+    pub stack_hash: u64,
+    pub representative: PathBuf,
+    pub inputs: Vec<PathBuf>,
+    pub outcome: Outcome,
+}
+
+pub struct Triage {
+    pub buckets: Vec<Bucket>,
+    pub not_reproduced: Vec<PathBuf>,
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX)
+}
+
+// replays every input and groups the crashing ones by stack hash,
+// the smallest input of each bucket being its representative
+pub fn triage(binary: &Path, inputs: &[PathBuf], timeout: Duration) -> Triage {
+    let mut buckets: Vec<Bucket> = Vec::new();
+    let mut not_reproduced = Vec::new();
+
+    for input in inputs {
+        let outcome = crash::replay(binary, input, timeout);
+        if !outcome.crashed {
+            not_reproduced.push(input.clone());
+            continue;
+        }
+
+        let stack_hash = outcome.stack_hash();
+        match buckets.iter_mut().find(|b| b.stack_hash == stack_hash) {
+            Some(bucket) => {
+                bucket.inputs.push(input.clone());
+                if file_size(input) < file_size(&bucket.representative) {
+                    bucket.representative = input.clone();
+                    bucket.outcome = outcome;
+                }
+            }
+            None => buckets.push(Bucket {
+                stack_hash,
+                representative: input.clone(),
+                inputs: vec![input.clone()],
+                outcome,
+            }),
+        }
+    }
+
+    // most frequent bugs first
+    buckets.sort_by_key(|b| std::cmp::Reverse(b.inputs.len()));
+    Triage { buckets, not_reproduced }
+}
+
+// the input, panic message and stack of a bucket, indented
+fn describe(bucket: &Bucket) -> String {
+    let mut description = format!("  input: {}\n", bucket.representative.display());
+    if let Some(message) = &bucket.outcome.panic_message {
+        let _ = writeln!(description, "  panic: {}", message.replace('\n', "\n         "));
+    }
+    description.push_str("  stack:\n");
+    for frame in bucket.outcome.relevant_frames().take(SHOWN_FRAMES) {
+        let _ = match &frame.location {
+            Some(location) => writeln!(description, "    {} at {}", frame.function, location),
+            None => writeln!(description, "    {}", frame.function),
+        };
+    }
+    description
+}
+
+// where the post-processing of "cargo hfuzz run" puts one minimized input and one report per bug
+pub fn triaged_dir(target: &str) -> PathBuf {
+    workspace::report_dir(target).join("crashes").join("triaged")
+}
+
+// replays the crash files of a target, buckets them by stack and writes the minimized representative
+// of each bucket with its report in `triaged_dir`, replacing the previous ones
+pub fn post_process(target: &str, crate_root: &Path) {
+    let crash_files = workspace::crash_files(target);
+    if crash_files.is_empty() {
+        return;
+    }
+    hfuzz_build(vec!["--bin".to_string(), target.to_string()].into_iter(), crate_root, &BuildType::Debug);
+
+    log::note(&format!("{}: replaying {} crash files", target, crash_files.len()));
+    let binary = debug_binary(target);
+    let triage = triage(&binary, &crash_files, crash::DEFAULT_TIMEOUT);
+    let dir = triaged_dir(target);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap_or_else(|_| {
+        eprintln!("error: failed to create \"{}\"", dir.display());
+        process::exit(1);
+    });
+
+    for bucket in &triage.buckets {
+        let name = format!("{:016x}", bucket.stack_hash);
+        let data = fs::read(&bucket.representative).unwrap_or_default();
+        // a crash depending on timing may not come back while minimizing, its input is kept as it is
+        let minimized = tmin::minimize(&binary, &dir.join(".tmin.candidate"), &data).map_or(data, |(minimized, _)| minimized);
+        let mut report = format!("stack {} ({} crash files)\n{}  crash files:\n", name, bucket.inputs.len(), describe(bucket));
+        for input in &bucket.inputs {
+            let _ = writeln!(report, "    {}", input.display());
+        }
+        let written = fs::write(dir.join(format!("{}.input", name)), &minimized)
+            .and_then(|_| fs::write(dir.join(format!("{}.txt", name)), report));
+        if written.is_err() {
+            eprintln!("error: failed to write the triaged crashes in \"{}\"", dir.display());
+            process::exit(1);
+        }
+    }
+    sarif::write(&dir.join("crashes.sarif"), target, &triage);
+    println!("{}: {} unique crashes out of {} crash files, minimized in \"{}\"", target, triage.buckets.len(), crash_files.len(), dir.display());
+    if !triage.not_reproduced.is_empty() {
+        println!("{}: {} crash files did not reproduce", target, triage.not_reproduced.len());
+    }
+}
+
+pub fn hfuzz_triage<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = TRIAGE.parse(args);
+    let sarif_file = matches.value("--sarif").map(PathBuf::from);
+    let annotate = annotate::selected(&TRIAGE, &matches);
+    features::select(&TRIAGE, &matches);
+    let mut args = matches.args.into_iter();
+    let target = args.next().unwrap_or_else(|| TRIAGE.error("please specify the name of the target"));
+    if let Some(arg) = args.next() {
+        TRIAGE.error(&format!("unexpected argument \"{}\"", arg));
+    }
+    log::select_target(&target);
+
+    let crash_files = workspace::crash_files(&target);
+    if crash_files.is_empty() {
+        println!("no crash files in \"{}\"", workspace::crash_dir(&target).display());
+        // an empty log tells code scanning the alerts are fixed
+        if let Some(sarif_file) = &sarif_file {
+            sarif::write(sarif_file, &target, &Triage { buckets: vec![], not_reproduced: vec![] });
+        }
+        return;
+    }
+
+    hfuzz_build(vec!["--bin".to_string(), target.clone()].into_iter(), crate_root, &BuildType::Debug);
+    features::check_replay(&target, &crash_files);
+
+    log::note(&format!("replaying {} crash files", crash_files.len()));
+    let triage = triage(&debug_binary(&target), &crash_files, crash::DEFAULT_TIMEOUT);
+
+    println!("{} unique crashes out of {} crash files", triage.buckets.len(), crash_files.len());
+    for (i, bucket) in triage.buckets.iter().enumerate() {
+        println!();
+        println!("bucket {}/{}: stack {:016x} ({} crash files)", i + 1, triage.buckets.len(), bucket.stack_hash, bucket.inputs.len());
+        print!("{}", describe(bucket));
+        if let Some(format) = &annotate {
+            annotate::crash(format, &target, &bucket.representative, &bucket.outcome);
+        }
+    }
+
+    if !triage.not_reproduced.is_empty() {
+        println!();
+        println!("{} crash files did not reproduce:", triage.not_reproduced.len());
+        for input in &triage.not_reproduced {
+            println!("  {}", input.display());
+        }
+    }
+    if let Some(sarif_file) = &sarif_file {
+        sarif::write(sarif_file, &target, &triage);
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{BuildType, hfuzz_build, bin_args, debug_binary};
+use crate::cli::{Opt, Subcommand};
+use crate::crash;
+use crate::features;
+use crate::log;
+use crate::metadata;
+use crate::policy::{self, ExitPolicy};
+use crate::workspace;
+
+pub const VERIFY: Subcommand = Subcommand {
+    name: "verify",
+    usage: "[OPTIONS] [TARGET ...]",
+    about: "replays the crash files of the targets and tells which still reproduce, are fixed or flaky",
+    options: &[
+        Opt { name: "--runs", value: Some("N"), help: "number of replays of each crash file, 3 by default" },
+        Opt { name: "--remove-fixed", value: None, help: "deletes the crash files which don't crash anymore" },
+        policy::EXIT_CODE_ON_CRASH, policy::EXIT_ZERO_ON_TIMEOUT, policy::FAIL_ON_HANG,
+        features::FEATURES, features::ALL_FEATURES, features::NO_DEFAULT_FEATURES,
+    ],
+    passthrough: None,
+};
+
+// a crash file is replayed several times to tell flaky crashes from fixed ones
+const DEFAULT_RUNS: usize = 3;
+
+// replays the crash files of a target, returns the numbers of the ones still crashing (even sometimes)
+// and of the ones only hanging anymore
+fn verify_target(target: &str, runs: usize, remove_fixed: bool) -> (u64, u64) {
+    log::select_target(target);
+    let binary = debug_binary(target);
+    let crash_files = workspace::crash_files(target);
+    features::check_replay(target, &crash_files);
+
+    let (mut reproduced, mut fixed, mut flaky, mut hanging) = (0, 0, 0, 0);
+    for crash_file in &crash_files {
+        let outcomes: Vec<crash::Outcome> = (0..runs).map(|_| crash::replay(&binary, crash_file, crash::DEFAULT_TIMEOUT)).collect();
+        let crashes = outcomes.iter().filter(|o| o.crashed || o.timed_out).count();
+        if crashes > 0 && outcomes.iter().all(|o| !o.crashed) {
+            hanging += 1;
+        }
+        if crashes == runs {
+            println!("REPRODUCED {}", crash_file.display());
+            reproduced += 1;
+        } else if crashes > 0 {
+            println!("FLAKY      {} (crashed {} times out of {})", crash_file.display(), crashes, runs);
+            flaky += 1;
+        } else {
+            println!("FIXED      {}", crash_file.display());
+            fixed += 1;
+            if remove_fixed && fs::remove_file(crash_file).is_err() {
+                eprintln!("warning: failed to remove \"{}\"", crash_file.display());
+            }
+        }
+    }
+
+    println!("{}: {} crash files, {} still reproduce, {} fixed{}, {} flaky",
+        target, crash_files.len(), reproduced, fixed, if remove_fixed { " (removed)" } else { "" }, flaky);
+    (reproduced + flaky - hanging, hanging)
+}
+
+pub fn hfuzz_verify<T>(args: T, crate_root: &Path) where T: std::iter::Iterator<Item=String> {
+    let matches = VERIFY.parse(args);
+    let runs = matches.parsed(&VERIFY, "--runs").unwrap_or(DEFAULT_RUNS);
+    if runs == 0 {
+        VERIFY.error("\"--runs\" expects a positive number");
+    }
+    let remove_fixed = matches.flag("--remove-fixed");
+    let policy = ExitPolicy::parse(&VERIFY, &matches, Some(1), true);
+    features::select(&VERIFY, &matches);
+    let mut targets = matches.args;
+    if targets.is_empty() {
+        targets = metadata::fuzz_target_names();
+    }
+
+    hfuzz_build(bin_args(&targets).into_iter(), crate_root, &BuildType::Debug);
+
+    let (crashes, hangs) = targets.iter().map(|t| verify_target(t, runs, remove_fixed))
+        .fold((0, 0), |(crashes, hangs), (c, h)| (crashes + c, hangs + h));
+    policy.exit(crashes, hangs);
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::log;
+use crate::metadata;
+use crate::session::{self, Session};
+use crate::workspace;
+
+// how often the sources are checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// modification times of the Rust sources and manifests of the workspace members
+pub type Sources = HashMap<PathBuf, SystemTime>;
+
+fn is_source(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "rs") || path.file_name().is_some_and(|n| n == "Cargo.toml" || n == "Cargo.lock")
+}
+
+fn scan(dir: &Path, skipped: &[PathBuf], sources: &mut Sources) {
+    for entry in fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok) {
+        let path = entry.path();
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if path.is_dir() {
+            // the builds and the fuzzing workspace change all the time
+            if !hidden && path.file_name() != Some("target".as_ref()) && !skipped.iter().any(|s| path.ends_with(s)) {
+                scan(&path, skipped, sources);
+            }
+        } else if is_source(&path) {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                sources.insert(path, modified);
+            }
+        }
+    }
+}
+
+pub fn snapshot() -> Sources {
+    let skipped = [PathBuf::from(workspace::target_dir()), PathBuf::from(workspace::workspace_dir())];
+    let mut sources = Sources::new();
+    for package in &metadata::metadata().packages {
+        scan(&package.manifest_dir, &skipped, &mut sources);
+    }
+    sources
+}
+
+// the sources once they differ from `sources`, files being added and removed too
+pub fn wait_for_change(sources: &Sources) -> Sources {
+    loop {
+        let current = snapshot();
+        if current != *sources {
+            return current;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// fuzzes until honggfuzz stops by itself, rebuilding the target and restarting honggfuzz whenever the
+// sources change, the corpus staying in the workspace in between
+//
+// a build failing waits for the next change instead of exiting, the fix being on its way
+pub fn fuzz<B, C>(target: &str, build: B, command: C, max_unique_crashes: Option<usize>) -> Session
+        where B: Fn() -> bool, C: Fn() -> Command {
+    let mut sources = snapshot();
+    loop {
+        while !build() {
+            log::note(&format!("{}: the build failed, waiting for the sources to change", target));
+            sources = wait_for_change(&sources);
+        }
+        // changes made during the build restart honggfuzz right away
+        let session = session::fuzz(target, command(), max_unique_crashes, Some(sources));
+        if !session.sources_changed || session::stopped_by().is_some() {
+            return session;
+        }
+        log::note(&format!("{}: the sources changed, rebuilding and restarting honggfuzz", target));
+        sources = snapshot();
+    }
+}
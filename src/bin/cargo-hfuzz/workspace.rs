@@ -0,0 +1,297 @@
+use std::fs;
+use std::env;
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{HONGGFUZZ_TARGET, HONGGFUZZ_WORKSPACE};
+use crate::config;
+use crate::log;
+use crate::metadata;
+
+pub fn target_dir() -> String {
+    env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| HONGGFUZZ_TARGET.into())
+}
+
+pub fn workspace_dir() -> String {
+    env::var("HFUZZ_WORKSPACE").unwrap_or_else(|_| HONGGFUZZ_WORKSPACE.into())
+}
+
+// honggfuzz working directory of a target, where crashes and reports are saved, in a directory named
+// after its package when several workspace members have fuzz targets
+pub fn target_workspace(target: &str) -> PathBuf {
+    let workspace = Path::new(&workspace_dir()).to_path_buf();
+    let unnamespaced = workspace.join(target);
+    match metadata::find_target(target) {
+        Some(t) if metadata::metadata().fuzz_packages().count() > 1 => {
+            let namespaced = workspace.join(&t.package).join(target);
+            // workspaces fuzzed before the namespacing keep theirs
+            if !namespaced.exists() && unnamespaced.join("input").is_dir() { unnamespaced } else { namespaced }
+        }
+        _ => unnamespaced,
+    }
+}
+
+// written by honggfuzz in its workspace, unless given `-R`
+pub const REPORT_FILE: &str = "HONGGFUZZ.REPORT.TXT";
+
+static CRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+static REPORT_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// the `--crash-dir`, `--report-dir` and `--log-dir` of "cargo hfuzz run"
+pub fn select_layout(crash_dir: Option<PathBuf>, report_dir: Option<PathBuf>, log_dir: Option<PathBuf>) {
+    for (selected, dir) in [(&CRASH_DIR, crash_dir), (&REPORT_DIR, report_dir), (&LOG_DIR, log_dir)] {
+        if let Some(dir) = dir {
+            let _ = selected.set(dir);
+        }
+    }
+}
+
+// a directory of a target kept apart from its workspace: the one named after it (and its package) in
+// the directory of the command line option, of the environment variable or of hfuzz.toml, its
+// workspace by default
+fn layout_dir(target: &str, selected: &OnceLock<PathBuf>, variable: &str, setting: &Option<PathBuf>) -> PathBuf {
+    let workspace = target_workspace(target);
+    let root = selected.get().cloned()
+        .or_else(|| env::var_os(variable).filter(|d| !d.is_empty()).map(PathBuf::from))
+        .or_else(|| setting.clone());
+    match root {
+        Some(root) => root.join(workspace.strip_prefix(workspace_dir()).unwrap_or(Path::new(target))),
+        None => workspace,
+    }
+}
+
+// where honggfuzz saves the crashes, its `--crashdir`
+pub fn crash_dir(target: &str) -> PathBuf {
+    layout_dir(target, &CRASH_DIR, "HFUZZ_CRASH_DIR", &config::config().crash_dir)
+}
+
+// where the report of honggfuzz, the summary of the sessions and the triaged crashes are written
+pub fn report_dir(target: &str) -> PathBuf {
+    layout_dir(target, &REPORT_DIR, "HFUZZ_REPORT_DIR", &config::config().report_dir)
+}
+
+// where the logs of honggfuzz and of cargo-hfuzz are written
+pub fn log_dir(target: &str) -> PathBuf {
+    layout_dir(target, &LOG_DIR, "HFUZZ_LOG_DIR", &config::config().log_dir)
+}
+
+// the directories of a target out of its workspace, which "cargo hfuzz clean TARGET --workspace" removes too
+pub fn layout_dirs(target: &str) -> Vec<PathBuf> {
+    let workspace = target_workspace(target);
+    let mut dirs = Vec::new();
+    for dir in [crash_dir(target), report_dir(target), log_dir(target)] {
+        if !dir.starts_with(&workspace) && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+static INPUT_DIRS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+// the `--input` directories of "cargo hfuzz run"
+pub fn select_input_dirs(dirs: Vec<PathBuf>) {
+    let _ = INPUT_DIRS.set(dirs);
+}
+
+// the directories honggfuzz draws inputs from: the corpus, where it saves the new ones, then the others,
+// from `--input`, HFUZZ_INPUT (separated by `:`) or `run.input`
+pub fn input_dirs(target: &str) -> Vec<PathBuf> {
+    let dirs = match (INPUT_DIRS.get(), env::var_os("HFUZZ_INPUT").filter(|i| !i.is_empty())) {
+        (Some(dirs), _) if !dirs.is_empty() => dirs.clone(),
+        (_, Some(input)) => env::split_paths(&input).collect(),
+        _ => config::config().inputs.clone(),
+    };
+    match dirs.is_empty() {
+        true => vec![target_workspace(target).join("input")],
+        false => dirs,
+    }
+}
+
+pub fn input_dir(target: &str) -> PathBuf {
+    input_dirs(target).swap_remove(0)
+}
+
+// where the harness saves the inputs of `honggfuzz::add_to_corpus`, until they join the corpus
+pub fn added_dir(target: &str) -> PathBuf {
+    target_workspace(target).join("added")
+}
+
+// moves the inputs of `honggfuzz::add_to_corpus` to the corpus, honggfuzz reading it when it starts,
+// the ones it already has being dropped, returns how many were added
+pub fn import_added(target: &str) -> usize {
+    // the harness writes them under a hidden name first
+    let added: Vec<PathBuf> = files_in(&added_dir(target)).into_iter()
+        .filter(|f| !f.file_name().unwrap_or_default().to_string_lossy().starts_with('.'))
+        .collect();
+    if added.is_empty() {
+        return 0;
+    }
+    let input_dir = input_dir(target);
+    let _ = fs::create_dir_all(&input_dir);
+    let mut known: HashSet<u64> = corpus_files(target).iter().filter_map(|f| content_hash(f)).collect();
+    let mut imported = 0;
+    for file in added {
+        let to = input_dir.join(file.file_name().unwrap());
+        let moved = match content_hash(&file) {
+            Some(hash) if known.insert(hash) => fs::rename(&file, &to).or_else(|_| fs::copy(&file, &to).map(|_| ())).is_ok(),
+            _ => false,
+        };
+        if moved {
+            imported += 1;
+        }
+        let _ = fs::remove_file(&file);
+    }
+    imported
+}
+
+// honggfuzz reads a single directory, the files of several input directories are linked in a fresh one
+// given to it instead, their names prefixed by the index of their directory
+pub fn stage_inputs(target: &str, dirs: &[PathBuf]) -> PathBuf {
+    let staging_dir = target_workspace(target).join("inputs");
+    if log::dry_run() {
+        return staging_dir;
+    }
+    let _ = fs::remove_dir_all(&staging_dir);
+    if fs::create_dir_all(&staging_dir).is_err() {
+        eprintln!("error: failed to create \"{}\"", staging_dir.display());
+        std::process::exit(1);
+    }
+    for (index, dir) in dirs.iter().enumerate() {
+        let files = files_in(dir);
+        if files.is_empty() && index > 0 {
+            eprintln!("warning: no input found in \"{}\" for \"{}\"", dir.display(), target);
+        }
+        for file in files {
+            let to = staging_dir.join(format!("{}-{}", index, file.file_name().unwrap().to_string_lossy()));
+            let _ = fs::canonicalize(&file).and_then(|file| std::os::unix::fs::symlink(file, to));
+        }
+    }
+    staging_dir
+}
+
+// regular files of a directory, sorted by name, empty if it doesn't exist
+pub fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+// tells identical inputs apart from their file names
+pub fn content_hash(path: &Path) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(path).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+pub fn corpus_files(target: &str) -> Vec<PathBuf> {
+    files_in(&input_dir(target))
+}
+
+// honggfuzz saves crashing inputs as `*.fuzz` files in the workspace, or in the crash directory
+pub fn crash_files(target: &str) -> Vec<PathBuf> {
+    files_in(&crash_dir(target)).into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "fuzz"))
+        .collect()
+}
+
+// the crash file saved last, the one being triaged most of the time
+pub fn newest_crash_file(target: &str) -> Option<PathBuf> {
+    crash_files(target).into_iter()
+        .max_by_key(|f| fs::metadata(f).and_then(|m| m.modified()).ok())
+}
+
+// `*` matching any characters and `?` any single one, in a file name
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => wildcard_match(&pattern[1..], name) || (!name.is_empty() && wildcard_match(pattern, &name[1..])),
+        (Some('?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![dir.to_path_buf()];
+    let scanned = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    for entry in fs::read_dir(scanned).into_iter().flatten().filter_map(Result::ok) {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            dirs.extend(subdirs(&dir.join(entry.file_name())));
+        }
+    }
+    dirs
+}
+
+// the files matching a pattern like `tests/fixtures/*.bin`, `**` matching any number of directories
+fn glob(pattern: &Path) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let part: Vec<char> = component.as_os_str().to_string_lossy().chars().collect();
+        matches = if part == ['*', '*'] {
+            matches.iter().flat_map(|m| subdirs(m)).collect()
+        } else if part.contains(&'*') || part.contains(&'?') {
+            matches.iter().flat_map(|m| {
+                let scanned = if m.as_os_str().is_empty() { Path::new(".") } else { m.as_path() };
+                fs::read_dir(scanned).into_iter().flatten().filter_map(Result::ok)
+                    .filter(|e| wildcard_match(&part, &e.file_name().to_string_lossy().chars().collect::<Vec<_>>()))
+                    .map(|e| m.join(e.file_name()))
+                    .collect::<Vec<_>>()
+            }).collect()
+        } else {
+            matches.iter().map(|m| m.join(component)).collect()
+        };
+    }
+    matches.retain(|m| m.is_file());
+    matches.sort();
+    matches
+}
+
+// the inputs of a seed entry: the files of a directory, a file, or the files matching a pattern
+fn seed_files(target: &str, entry: &Path) -> Vec<PathBuf> {
+    let is_pattern = entry.to_string_lossy().contains(['*', '?']);
+    let files = if is_pattern {
+        glob(entry)
+    } else if entry.is_dir() {
+        files_in(entry)
+    } else if entry.is_file() {
+        vec![entry.to_path_buf()]
+    } else {
+        Vec::new()
+    };
+    if files.is_empty() {
+        eprintln!("warning: no seed input found in \"{}\" for \"{}\"", entry.display(), target);
+    }
+    files
+}
+
+// copies (or links) the seed inputs of a target which aren't in its corpus yet, returns how many were
+// added, the ones a minimization removed coming back this way
+pub fn seed_corpus(target: &str, seeds: &[PathBuf], link: bool) -> usize {
+    let input_dir = input_dir(target);
+    let _ = fs::create_dir_all(&input_dir);
+    let mut known: HashSet<u64> = corpus_files(target).iter().filter_map(|f| content_hash(f)).collect();
+    let mut seeded = 0;
+    for seed in seeds.iter().flat_map(|entry| seed_files(target, entry)) {
+        let Some(hash) = content_hash(&seed) else { continue };
+        let to = input_dir.join(format!("seed-{:016x}", hash));
+        if !known.insert(hash) {
+            continue;
+        }
+        let added = match link {
+            // the corpus follows the seeds of the repository, which can be big
+            true => fs::canonicalize(&seed).and_then(|seed| std::os::unix::fs::symlink(seed, &to)).is_ok(),
+            false => fs::copy(&seed, &to).is_ok(),
+        };
+        if added {
+            seeded += 1;
+        }
+    }
+    seeded
+}
@@ -22,13 +22,13 @@ fn main() {
     let tsan_options = format!("report_signal_unsafe=0:{}", tsan_options);
 
     let command = format!("{}/honggfuzz", HONGGFUZZ_TARGET);
-    Command::new(&command) // exec honggfuzz replacing current process
+    let error = Command::new(&command) // exec honggfuzz replacing current process
         .args(args)
         .env("ASAN_OPTIONS", asan_options)
         .env("TSAN_OPTIONS", tsan_options)
         .exec();
 
     // code flow will only reach here if honggfuzz failed to execute
-    eprintln!("cannot execute {}, try to execute \"cargo hfuzz build\" from fuzzed project directory", &command);
+    eprintln!("cannot execute {} ({}), try to execute \"cargo hfuzz build\" from fuzzed project directory", &command, error);
     process::exit(1);
 }
@@ -0,0 +1,93 @@
+//! Coverage signals of the harness, for the targets whose progress doesn't show in their branches.
+//!
+//! A state machine or an interpreter runs the same code whatever its state is, so honggfuzz sees no
+//! new coverage when an input gets it further. Reporting that progress here makes the engine keep the
+//! inputs which made it, like the ones reaching new branches.
+//!
+//! ```rust
+//! # fn main() {
+//! # let (state, depth) = (3u64, 12u64);
+//! // every state reached is new coverage the first time
+//! honggfuzz::feedback::hit(state);
+//! // and so is every new maximum of the depth
+//! honggfuzz::feedback::max("depth", depth);
+//! # }
+//! ```
+//!
+//! The signals are guards of the coverage instrumentation of the fuzzing builds, registered with
+//! honggfuzz as those of the code are. Out of them and in the builds of run-debug and bench, they
+//! do nothing.
+
+// how many guards the signals have, the buckets of different names sharing them
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+const GUARDS: usize = 1 << 16;
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+extern "C" {
+    fn __sanitizer_cov_trace_pc_guard_init(start: *mut u32, stop: *mut u32);
+    fn __sanitizer_cov_trace_pc_guard(guard: *mut u32);
+}
+
+// numbered by honggfuzz when they are registered, then only read by it
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+struct Guards(core::cell::UnsafeCell<[u32; GUARDS]>);
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+unsafe impl Sync for Guards {}
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+static SIGNALS: Guards = Guards(core::cell::UnsafeCell::new([0; GUARDS]));
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+fn signal(key: u64) {
+    static REGISTER: crate::__Once = crate::__Once::new();
+
+    let guards = SIGNALS.0.get() as *mut u32;
+    unsafe {
+        REGISTER.call_once(|| __sanitizer_cov_trace_pc_guard_init(guards, guards.add(GUARDS)));
+        __sanitizer_cov_trace_pc_guard(guards.add(mix(key) as usize % GUARDS));
+    }
+}
+
+#[cfg(not(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench))))]
+fn signal(_key: u64) {}
+
+// the finalizer of SplitMix64, for the keys of the signals to spread over the guards
+fn mix(key: u64) -> u64 {
+    let mut z = key;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn name_key(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+// the values up to 64 have a bucket each, the larger ones four per power of two
+fn bucket(value: u64) -> u64 {
+    if value < 64 {
+        return value;
+    }
+    let magnitude = 63 - value.leading_zeros() as u64;
+    64 + (magnitude - 6) * 4 + ((value >> (magnitude - 2)) & 3)
+}
+
+/// Reports that the input reached `bucket`, a state or an event of the target: the first input
+/// reaching each of them is new coverage.
+pub fn hit(bucket: u64) {
+    signal(bucket);
+}
+
+/// Reports a value the input got to, like a depth or a number of steps: a value larger than all the
+/// ones of the previous inputs with this `name` is new coverage.
+///
+/// The values are in buckets, every one up to 64 and then four per power of two, the inputs making
+/// the value grow within a bucket not counting as new coverage.
+pub fn max(name: &str, value: u64) {
+    // every bucket up to the one of the value is hit, only a new maximum reaching one of them first
+    let key = name_key(name);
+    for bucket in 0..=bucket(value) {
+        signal(key ^ mix(bucket.wrapping_add(1)));
+    }
+}
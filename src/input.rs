@@ -0,0 +1,202 @@
+// Structured inputs decoded from the fuzzing input, without the `arbitrary` crate.
+//
+// Every input decodes: the bytes missing at its end read as zeros and the numbers are mapped in their
+// ranges, so that a mutation of honggfuzz changes the value a little instead of making the input
+// useless. The same bytes always decode to the same value, for the crashes to replay the same way.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Reads the values of a [`FuzzInput`] from the fuzzing input, in order.
+pub struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Decoder { data }
+    }
+
+    /// The next `N` bytes, zeros past the end of the input
+    pub fn array<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0; N];
+        let len = N.min(self.data.len());
+        bytes[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        bytes
+    }
+
+    /// The next `len` bytes, fewer at the end of the input
+    pub fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (bytes, rest) = self.data.split_at(len.min(self.data.len()));
+        self.data = rest;
+        bytes
+    }
+
+    /// What is left of the input
+    pub fn rest(&mut self) -> &'a [u8] {
+        self.bytes(self.data.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// A type decoded from the fuzzing input, usually with `#[derive(FuzzInput)]`.
+///
+/// The numbers are read as little endian, `bool` and `Option` from a byte whose lowest bit tells,
+/// `Vec` and `String` after their length as a `u16`, and the variant of an enum from a byte modulo
+/// the number of variants, so that adding a variant at the end keeps the inputs of the others.
+pub trait FuzzInput: Sized {
+    fn decode(decoder: &mut Decoder) -> Self;
+
+    /// Decodes a whole fuzzing input, which always succeeds.
+    fn from_fuzz_input(data: &[u8]) -> Self {
+        Self::decode(&mut Decoder::new(data))
+    }
+}
+
+/// The types decoded after their length, the `#[fuzz(len = u8)]` and `#[fuzz(rest)]` of a field
+/// changing how it is read.
+pub trait Sequence: FuzzInput {
+    /// At most `len` elements, fewer when the input ends.
+    fn decode_len(decoder: &mut Decoder, len: usize) -> Self;
+}
+
+/// The integers of `#[fuzz(range = START..END)]` and `#[fuzz(range = START..=END)]`.
+pub trait Ranged: FuzzInput + Copy {
+    /// `self` mapped in the range, `start` if it is empty.
+    fn in_range(self, start: Self, end: Self, inclusive: bool) -> Self;
+}
+
+macro_rules! integers {
+    ($($t:ty),*) => {$(
+        impl FuzzInput for $t {
+            fn decode(decoder: &mut Decoder) -> Self {
+                <$t>::from_le_bytes(decoder.array())
+            }
+        }
+
+        impl Ranged for $t {
+            fn in_range(self, start: Self, end: Self, inclusive: bool) -> Self {
+                let span = end as i128 - start as i128 + inclusive as i128;
+                if span <= 0 {
+                    return start;
+                }
+                (start as i128 + (self as i128).rem_euclid(span)) as $t
+            }
+        }
+    )*};
+}
+
+integers!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl FuzzInput for u128 {
+    fn decode(decoder: &mut Decoder) -> Self {
+        u128::from_le_bytes(decoder.array())
+    }
+}
+
+impl FuzzInput for i128 {
+    fn decode(decoder: &mut Decoder) -> Self {
+        i128::from_le_bytes(decoder.array())
+    }
+}
+
+impl FuzzInput for bool {
+    fn decode(decoder: &mut Decoder) -> Self {
+        u8::decode(decoder) & 1 == 1
+    }
+}
+
+impl FuzzInput for char {
+    fn decode(decoder: &mut Decoder) -> Self {
+        char::from_u32(u32::decode(decoder) % 0x110000).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+impl FuzzInput for f32 {
+    fn decode(decoder: &mut Decoder) -> Self {
+        f32::from_bits(u32::decode(decoder))
+    }
+}
+
+impl FuzzInput for f64 {
+    fn decode(decoder: &mut Decoder) -> Self {
+        f64::from_bits(u64::decode(decoder))
+    }
+}
+
+impl FuzzInput for () {
+    fn decode(_: &mut Decoder) -> Self {}
+}
+
+impl<T: FuzzInput> FuzzInput for Option<T> {
+    fn decode(decoder: &mut Decoder) -> Self {
+        match bool::decode(decoder) {
+            true => Some(T::decode(decoder)),
+            false => None,
+        }
+    }
+}
+
+impl<T: FuzzInput> FuzzInput for Box<T> {
+    fn decode(decoder: &mut Decoder) -> Self {
+        Box::new(T::decode(decoder))
+    }
+}
+
+impl<T: FuzzInput, const N: usize> FuzzInput for [T; N] {
+    fn decode(decoder: &mut Decoder) -> Self {
+        core::array::from_fn(|_| T::decode(decoder))
+    }
+}
+
+impl<T: FuzzInput> FuzzInput for Vec<T> {
+    fn decode(decoder: &mut Decoder) -> Self {
+        let len = u16::decode(decoder) as usize;
+        Self::decode_len(decoder, len)
+    }
+}
+
+impl<T: FuzzInput> Sequence for Vec<T> {
+    fn decode_len(decoder: &mut Decoder, len: usize) -> Self {
+        let mut elements = Vec::new();
+        while elements.len() < len && !decoder.is_empty() {
+            let left = decoder.data.len();
+            elements.push(T::decode(decoder));
+            // elements read from no byte, like `()`, would never end the input
+            if decoder.data.len() == left {
+                break;
+            }
+        }
+        elements
+    }
+}
+
+impl FuzzInput for String {
+    fn decode(decoder: &mut Decoder) -> Self {
+        let len = u16::decode(decoder) as usize;
+        Self::decode_len(decoder, len)
+    }
+}
+
+impl Sequence for String {
+    fn decode_len(decoder: &mut Decoder, len: usize) -> Self {
+        String::from_utf8_lossy(decoder.bytes(len)).into_owned()
+    }
+}
+
+macro_rules! tuples {
+    ($(($($t:ident),+))*) => {$(
+        impl<$($t: FuzzInput),+> FuzzInput for ($($t,)+) {
+            fn decode(decoder: &mut Decoder) -> Self {
+                ($($t::decode(decoder),)+)
+            }
+        }
+    )*};
+}
+
+tuples!((A) (A, B) (A, B, C) (A, B, C, D) (A, B, C, D, E) (A, B, C, D, E, F) (A, B, C, D, E, F, G) (A, B, C, D, E, F, G, H));
@@ -30,6 +30,58 @@
 //! sudo apt install build-essential binutils-dev libunwind-dev
 //! ```
 //! 
+//! Nothing else is fetched: the sources of honggfuzz are bundled with the library, so that the engine builds without
+//! network access, in an air-gapped CI too. With `cargo hfuzz build --offline` (or `--frozen`, or `CARGO_NET_OFFLINE=true`)
+//! a build which would have to download something, the release of honggfuzz of `build.engine_version` in hfuzz.toml,
+//! fails right away instead.
+//! 
+//! The optional parts of the engine are features of the library, both enabled by default. Without `engine-bfd` the
+//! engine doesn't link libbfd and libopcodes of binutils, its crash reports naming no symbol and disassembling no
+//! instruction. Without `engine-intel-pt` it doesn't link libipt, used when its headers are found, and has no Intel PT
+//! feedback (`--linux_perf_ipt_block`):
+//! 
+//! ```toml
+//! [dependencies]
+//! honggfuzz = { version = "0.5", default-features = false, features = ["std"] }
+//! ```
+//! 
+//! The perf counters of the engine (`--linux_perf_instr`, `--linux_perf_branch`) need no library and are always built,
+//! and the capstone disassembler is only used by the engines of Android and NetBSD.
+//! 
+//! When the C compiler doesn't find libbfd or libunwind (with liblzma), as on a stock container image, the engine is
+//! built without them instead of failing, and `cargo hfuzz build` warns about what it lacks: without libbfd its crash
+//! reports name no symbol, without libunwind the crashes with no sanitizer report have no stack trace, each one being
+//! saved instead of the first one of every stack. `cargo hfuzz version` tells it too.
+//! 
+//! A plain `cargo build` of a crate depending on honggfuzz compiles no C, only `cargo hfuzz` building the engine and
+//! its libraries. With the `no-engine` feature `cargo hfuzz build` only builds the libraries the targets link, which
+//! need a C compiler but none of the libraries of the engine, and the targets are fuzzed by an installed engine, given
+//! with `--engine PATH` or `HFUZZ_ENGINE_PATH`:
+//! 
+//! ```toml
+//! [dependencies]
+//! honggfuzz = { version = "0.5", features = ["no-engine"] }
+//! ```
+//! 
+//! The first engine built by `cargo hfuzz build`, and the first one after cargo-hfuzz is reinstalled, is also installed
+//! next to cargo-hfuzz as `hfuzz-engine` (like `~/.cargo/bin/hfuzz-engine`), which `cargo hfuzz run` falls back to when
+//! the build directory of the targets has no engine: with the `no-engine` feature, or in another target directory.
+//! `cargo install` running nothing but the build of cargo-hfuzz, the engine isn't there before a first build.
+//! 
+//! The engine is built with the C toolchain of `CC`, `AR`, `CFLAGS` and `LDFLAGS`, read like the `cc` crate does: from
+//! `CC_<triple>` (or `CC_<triple_with_underscores>`), then `TARGET_CC` when cross-compiling or `HOST_CC`, then `CC`. The
+//! flags of the Makefile of honggfuzz are added to `CFLAGS` and `LDFLAGS`, its default `CFLAGS` (`-O3 -mtune=native
+//! -funroll-loops`) being replaced:
+//! 
+//! ```sh
+//! CC=clang-17 CFLAGS="-O2 -march=x86-64-v2" cargo hfuzz build
+//! ```
+//! 
+//! The engine is compiled once for its sources, target, features and toolchain, kept in `hfuzz_target/honggfuzz-engine`:
+//! the builds with other profiles or sanitizers, and the later builds of the same configuration, copy it from there.
+//! 
+//! The engine compiles in parallel, sharing the jobs of cargo (`-j`, one per CPU by default) with the other crates.
+//! 
 //! ## How to use this crate
 //! 
 //! Install honggfuzz commands to build with instrumentation and fuzz
@@ -39,6 +91,32 @@
 //! cargo install honggfuzz
 //! ```
 //! 
+//! Every command has its own help
+//! 
+//! ```sh
+//! # lists the commands, then the options of one of them
+//! cargo hfuzz help
+//! cargo hfuzz run --help
+//! ```
+//! 
+//! And completes in bash, zsh and fish, the names of the fuzz targets included
+//! 
+//! ```sh
+//! # in ~/.bashrc (or ~/.zshrc after compinit, with zsh instead of bash)
+//! source <(cargo hfuzz completions bash)
+//! # for fish
+//! cargo hfuzz completions fish > ~/.config/fish/completions/cargo-hfuzz.fish
+//! ```
+//! 
+//! Check that the system can build and run honggfuzz
+//! 
+//! ```sh
+//! # checks the C toolchain, the libraries of the dependencies, that rustc instruments with the coverage
+//! # flags matching its LLVM version, which clang is around, ptrace permissions and where cores go,
+//! # telling how to fix what fails
+//! cargo hfuzz doctor
+//! ```
+//! 
 //! Add to your dependencies
 //! 
 //! ```toml
@@ -46,8 +124,38 @@
 //! honggfuzz = "0.5"
 //! ```
 //! 
+//! a `#![no_std]` harness (core and alloc) leaves out the default `std` feature: `fuzz!` over `&[u8]`, `FuzzInput`,
+//! `rng()`, `feedback`, `fuzz_assert!` and `fuzz_init!` are kept, but the `#[panic_handler]` of the harness has to abort
+//! for a panic to be a crash, and the builds of run-debug and bench, the typed closures of `arbitrary` and the helpers
+//! using the file system need std
+//! 
+//! ```toml
+//! [dependencies]
+//! honggfuzz = { version = "0.5", default-features = false }
+//! ```
+//! 
 //! Create a target to fuzz
 //! 
+//! ```sh
+//! # adds the honggfuzz dependency, creates a starter target in `src/bin/fuzz_target_1.rs`,
+//! # its input directory and the `.gitignore` entries for `hfuzz_target` and `hfuzz_workspace`
+//! cargo hfuzz init
+//! 
+//! # or, to keep fuzz targets out of `src/bin` (they get registered as `[[bin]]` in `Cargo.toml`)
+//! cargo hfuzz init --dir fuzz_targets example
+//! 
+//! # adds another target next to the existing ones
+//! cargo hfuzz add another_target
+//! 
+//! # starts the target from a template instead of the minimal one: `parser` hands the input to a parsing
+//! # function, `roundtrip` checks that decoding then encoding gives the same value back, `arbitrary` builds
+//! # a structured input with `#[derive(Arbitrary)]` and `api-sequence` runs a sequence of calls against a
+//! # model, the last two adding the `arbitrary` dependency with its `derive` feature
+//! cargo hfuzz add --template roundtrip decode_encode
+//! ```
+//! 
+//! or write it by hand
+//! 
 //! ```rust,should_panic
 //! use honggfuzz::fuzz;
 //! 
@@ -75,141 +183,901 @@
 //! 
 //! ```
 //! 
+//! or fuzz a structured input: any type implementing `Arbitrary` can be given to the closure, a struct of your
+//! own too with `#[derive(Arbitrary)]` (add `arbitrary = { version = "1", features = ["derive"] }` to your
+//! dependencies), the value being built from the whole input and the inputs which fail to decode being skipped
+//! 
+//! ```rust,ignore
+//! use arbitrary::Arbitrary;
+//! use honggfuzz::fuzz;
+//! 
+//! #[derive(Arbitrary, Debug)]
+//! struct Request {
+//!     method: u8,
+//!     path: String,
+//!     body: Vec<u8>,
+//! }
+//! 
+//! fn main() {
+//!     loop {
+//!         fuzz!(|request: Request| {
+//!             if request.method == 3 && request.path == "/" && request.body == b"hey" {
+//!                 panic!("BOOM")
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+//! 
+//! or decode it with `#[derive(FuzzInput)]`, which doesn't need `arbitrary`: every input decodes (the bytes missing
+//! at its end read as zeros) and the same bytes always give the same value, the numbers as little endian, `bool` and
+//! `Option` from a byte, `Vec` and `String` after a `u16` length and enums from a byte choosing their variant, a field
+//! taking `#[fuzz(range = 1..=3)]`, `#[fuzz(len = u8)]` (or `u16`, `u32`), `#[fuzz(rest)]` or `#[fuzz(skip)]`
+//! 
+//! ```rust,should_panic
+//! use honggfuzz::{fuzz, FuzzInput};
+//! 
+//! #[derive(FuzzInput, Debug)]
+//! enum Method {
+//!     Get,
+//!     Post,
+//!     Other(u8),
+//! }
+//! 
+//! #[derive(FuzzInput, Debug)]
+//! struct Request {
+//!     method: Method,
+//!     #[fuzz(range = 1..=3)]
+//!     version: u8,
+//!     #[fuzz(len = u8)]
+//!     path: String,
+//!     content_length: Option<u32>,
+//!     #[fuzz(rest)]
+//!     body: Vec<u8>,
+//! }
+//! 
+//! fn main() {
+//!     loop {
+//!         fuzz!(|data: &[u8]| {
+//!             let request = Request::from_fuzz_input(data);
+//!             if request.version == 2 && request.path == "/" && request.body == b"hey" {
+//!                 panic!("BOOM")
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+//! 
+//! the closure can take several typed arguments too, decoded as a tuple from the input (the last one getting what
+//! is left of it, a `&[u8]` the remaining bytes)
+//! 
+//! ```rust,ignore
+//! fuzz!(|method: u8, strict: bool, body: &[u8]| {
+//!     if method == 3 && strict && body == b"hey" {
+//!         panic!("BOOM")
+//!     }
+//! });
+//! ```
+//! 
+//! the generators of a proptest suite work too: `proptest_value!` makes the value of a `Strategy` from the input,
+//! whose bytes are the random numbers of proptest (its `PassThrough` RNG) for the coverage to guide the generation,
+//! and gives `None` when the strategy rejects it (the harness needs `proptest` in its dependencies)
+//! 
+//! ```rust,ignore
+//! fuzz!(|data: &[u8]| {
+//!     let Some(operations) = proptest_value!(prop::collection::vec((0u8..4, "[a-z]{1,8}"), 0..32), data) else { return };
+//!     run(operations);
+//! });
+//! ```
+//! 
+//! harnesses written for cargo-fuzz build unchanged with the `fuzz_target!` macro, which also takes a typed closure,
+//! a `-> Corpus` return and an `init:` expression: honggfuzz can't be told to not keep an input, but at the end of
+//! the session `cargo hfuzz run` replays the inputs it saved and removes the rejected ones from the corpus (the closure
+//! of `fuzz!` may return a `Corpus` too, or a `Result<(), E>` whose `Err` rejects the input);
+//! rename the dependency with `libfuzzer-sys = { package = "honggfuzz", version = "0.5" }` or import it from `honggfuzz`
+//! 
+//! ```rust,ignore
+//! #![no_main]
+//! use honggfuzz::fuzz_target;
+//! 
+//! fuzz_target!(|data: &[u8]| {
+//!     if data == b"hey" {
+//!         panic!("BOOM")
+//!     }
+//! });
+//! ```
+//! 
+//! a one-time setup (loading a model, warming a cache, installing a logging filter) goes in `fuzz_init!`, which runs
+//! its block the first time it is reached and never again: in the loop before `fuzz!`, it runs before the first input
+//! instead of being redone for every input or counted in the coverage of the first one (it warns when it's reached from
+//! the closure, while an input runs)
+//! 
+//! ```rust,ignore
+//! loop {
+//!     fuzz_init!({
+//!         std::env::set_var("RUST_LOG", "off");
+//!     });
+//!     fuzz!(|data: &[u8]| {
+//!         if data == b"hey" {
+//!             panic!("BOOM")
+//!         }
+//!     });
+//! }
+//! ```
+//! 
+//! an input making the target allocate without end exhausts the memory of the machine instead of being reported, unless
+//! the target's global allocator is a `LimitedAllocator` (like libFuzzer's `-malloc_limit_mb`): an input allocating
+//! more than the limit, in megabytes, aborts the process after an "error: out of memory, ..." line and is saved as a
+//! crash (leave it out of `cfg(fuzzing_bench)`, the builds of `cargo hfuzz bench` having their own allocator)
+//! 
+//! ```rust,ignore
+//! #[cfg(not(fuzzing_bench))]
+//! #[global_allocator]
+//! static ALLOCATOR: honggfuzz::LimitedAllocator = honggfuzz::LimitedAllocator::new(2048);
+//! ```
+//! 
+//! state machines and interpreters run the same branches whatever their state is, so that honggfuzz doesn't see an
+//! input getting them further: report that progress with `honggfuzz::feedback`, which honggfuzz counts as coverage
+//! (the first input reaching a state, or a new maximum of a value)
+//! 
+//! ```rust,ignore
+//! fuzz!(|data: &[u8]| {
+//!     let mut machine = Machine::new();
+//!     for &byte in data {
+//!         machine.step(byte);
+//!         honggfuzz::feedback::hit(machine.state() as u64);
+//!     }
+//!     honggfuzz::feedback::max("depth", machine.depth() as u64);
+//! });
+//! ```
+//! 
+//! a panic of the fuzzing builds prints the input it happened on (its length and a hex and ASCII dump of its first
+//! kilobyte) before aborting, for the report of honggfuzz to show it: `fuzz_assert!` and `fuzz_assert_eq!` do the same
+//! for the invariants of the harness, even when the target replaces the panic hook, and out of the fuzzing builds they
+//! behave as `assert!` and `assert_eq!`
+//! 
+//! ```rust,ignore
+//! fuzz!(|data: &[u8]| {
+//!     let decoded = decode(&encode(data));
+//!     fuzz_assert_eq!(decoded, data, "the round trip changed the input");
+//! });
+//! ```
+//! 
+//! a target logging at every input runs many times slower and fills the disk over a campaign: `fuzz_logger!(log)` (or
+//! `fuzz_logger!(tracing)`, for the crate the target logs with) installs a logger dropping the logs in the fuzzing builds
+//! and printing all of them in the builds of run-debug, and `honggfuzz::logs::set_enabled` switches between the two at
+//! any time (out of the fuzzing builds it installs nothing)
+//! 
+//! ```rust,ignore
+//! fn main() {
+//!     honggfuzz::fuzz_logger!(log);
+//!     loop {
+//!         fuzz!(|data: &[u8]| {
+//!             log::debug!("parsing {} bytes", data.len());
+//!             parse(data);
+//!         });
+//!     }
+//! }
+//! ```
+//! 
+//! a harness running its own loop, to batch the inputs or to fit the fuzzing in the event loop of the target, takes
+//! them with `honggfuzz::fetch_input()` instead of the closure of `fuzz!`, and tells when it is done with each of them
+//! with `honggfuzz::iteration_done()` (the next `fetch_input` does it otherwise): the bytes of the slice are those of
+//! the next input after the next call
+//! 
+//! ```rust,ignore
+//! loop {
+//!     let data = honggfuzz::fetch_input();
+//!     server.handle(decode(data));
+//!     honggfuzz::iteration_done();
+//!     // the work between two inputs, out of their timeouts
+//!     server.poll_timers();
+//! }
+//! ```
+//! 
+//! a TCP server reads its inputs from connections: `fuzz_net!` runs it in a thread of the target, listening on the
+//! address it is given, and sends it every input on a connection of its own like the NetDriver of honggfuzz does, its
+//! response being read until the server closes the connection (see `honggfuzz::net`). It gets a free port of the
+//! loopback, unless `cargo hfuzz run --net-port PORT` gives the fixed one of a server which can't be told, fuzzing
+//! with a single thread
+//! 
+//! ```rust,ignore
+//! fn main() {
+//!     honggfuzz::fuzz_net!(|addr| {
+//!         let listener = std::net::TcpListener::bind(addr).unwrap();
+//!         server::serve(listener);
+//!     });
+//! }
+//! ```
+//! 
+//! a target which can't be a closure at all, like a server talked to in sessions by a stateful client, is fuzzed in
+//! the socketfuzzer mode of honggfuzz: the target is the server itself, calling `honggfuzz::socket_fuzzer::init_target()`
+//! first in its `main` for its panics to be crashes, and a driver of your own sends it the inputs whenever honggfuzz
+//! asks, over the Unix socket of `HFUZZ_SOCKET_FUZZER` with a `honggfuzz::socket_fuzzer::Driver`, honggfuzz telling it
+//! which inputs reached new coverage and which ones crashed the target (its crash files are empty, the driver keeps what
+//! it sent). `cargo hfuzz run --socket-fuzzer=DRIVER` runs the driver once honggfuzz listens and stops honggfuzz when
+//! the driver exits, with a single thread, `--socket-fuzzer` alone leaving the driver to you
+//! 
+//! ```rust,ignore
+//! fn main() {
+//!     honggfuzz::socket_fuzzer::init_target();
+//!     server::serve(std::net::TcpListener::bind("127.0.0.1:5001").unwrap());
+//! }
+//! ```
+//! 
 //! Fuzz for fun and profit !
 //! 
 //! ```sh
 //! # builds with fuzzing instrumentation and then fuzz the "example" target
 //! cargo hfuzz run example
+//! 
+//! # inputs running for more than 5 seconds are reported as hangs (honggfuzz's `-t`),
+//! # the same limit makes "cargo hfuzz run-debug --timeout 5 ..." abort
+//! cargo hfuzz run --timeout 5 example
+//! 
+//! # or in the harness, with `honggfuzz::set_iteration_timeout(Duration::from_millis(500))` called before
+//! # the loop: an input running for longer aborts the process after an "error: timeout, ..." line, which
+//! # run-debug, triage and verify report as a hang, whatever honggfuzz's own timeout is
+//! 
+//! # builds and fuzzes with 4 jobs (honggfuzz's `-n`, one per CPU by default) which only run on CPUs 0 to 3
+//! cargo hfuzz run --jobs 4 --pin-cpus 0-3 example
+//! 
+//! # for CI: fuzzes for 10 minutes, prints a JSON summary and exits with 1 if any crash was found
+//! cargo hfuzz run example --ci --max-time 600
+//! 
+//! # for scripted experiments: runs the target exactly a million times (honggfuzz's `-N`, the executions of
+//! # all the threads counting) and exits with the summary of the session
+//! cargo hfuzz run --iterations 1000000 example
+//! 
+//! # fuzzes a server of `fuzz_net!` listening on the port 8080, which it can't be told, with a single thread
+//! cargo hfuzz run --net-port 8080 example
+//! 
+//! # fuzzes the server "example" with the inputs a driver sends it, run with the socket in HFUZZ_SOCKET_FUZZER
+//! cargo hfuzz run --socket-fuzzer="target/release/driver --port 5001" example
+//! 
+//! # mutates with the tokens of a libFuzzer/AFL dictionary (honggfuzz's `-w`), checked before fuzzing
+//! cargo hfuzz run --dict fuzz/http.dict example
+//! 
+//! # the tokens of `honggfuzz::fuzz_dictionary![b"\x89PNG", "Content-Length:"]`, called in the `main` of the
+//! # target, are compiled into it and given to honggfuzz along with the ones of "--dict" or `run.dict`
+//! cargo hfuzz run example
+//! 
+//! # honggfuzz generates inputs of at most 64 bytes (its `-F`) and the harness truncates
+//! # longer ones to the same length, also when replaying with "run-debug --max-len 64"
+//! cargo hfuzz run --max-len 64 example
+//! 
+//! # mutates an input at most twice per run (honggfuzz's `-r`, or `run.mutations`) for gentler changes
+//! # to structured inputs, or not at all with "--base-files-only" (`-r 0`), running the corpus as it is
+//! cargo hfuzz run --mutations 2 example
+//! cargo hfuzz run --base-files-only --iterations 100000 example
+//! 
+//! # rewrites every input mutated by honggfuzz with the `honggfuzz::custom_mutator(|data, max_size, seed| ...)`
+//! # called first in the `main` of the target (honggfuzz's `--pprocess_cmd`), for structure-aware mutations
+//! cargo hfuzz run --custom-mutator example
+//! 
+//! # draws inputs from the corpus of the workspace and from a committed seed set, without copying them,
+//! # the new inputs being saved in the first directory (HFUZZ_INPUT or `run.input` list them too)
+//! cargo hfuzz run --input hfuzz_workspace/example/input --input fuzz/seeds/example example
+//! 
+//! # keeps the crashes, the reports (honggfuzz's, summary.json, crashes/triaged) and the logs out of the
+//! # workspace, in fuzz/crashes/example and so on: the other commands find them with HFUZZ_CRASH_DIR,
+//! # HFUZZ_REPORT_DIR and HFUZZ_LOG_DIR, or the `[workspace]` table of hfuzz.toml
+//! cargo hfuzz run --crash-dir fuzz/crashes --report-dir fuzz/reports --log-dir fuzz/logs example
+//! 
+//! # the inputs the harness gives `honggfuzz::add_to_corpus(&data)`, like variants with a fixed checksum,
+//! # are saved in hfuzz_workspace/example/added and join the corpus when the session ends
+//! cargo hfuzz run example
+//! 
+//! # every session starts with the settings in effect (honggfuzz's flags, the corpus, the seeds) and ends
+//! # with what it found (new inputs, crashes, hangs, coverage since the last session), colored on a
+//! # terminal unless NO_COLOR is set or "--no-color" comes before the command
+//! cargo hfuzz --no-color run example
+//! 
+//! # saves the crashes and goes on fuzzing, even if "--exit_upon_crash" is in HFUZZ_RUN_ARGS or
+//! # hfuzz.toml, until 5 crashes with different stacks were found (or "--max-time" is reached)
+//! cargo hfuzz run --keep-going=5 --max-time 86400 example
+//! 
+//! # while working on the target, rebuilds it and restarts honggfuzz on the same corpus each time a
+//! # source file or Cargo.toml of the workspace changes (a failed build waits for the next change)
+//! cargo hfuzz run --watch example
 //! ```
 //! 
-//! Once you got a crash, replay it easily in a debug environment
+//! Got many targets? Share the fuzzing time between them
 //! 
 //! ```sh
-//! # builds the target in debug mode and replays automatically the crash in gdb
-//! cargo hfuzz run-debug example fuzzing_workspace/*.fuzz
+//! # builds every fuzz target and fuzzes them in turn, 10 minutes each, for 2 hours in total
+//! # (corpora are kept between rotations, without "--total" it rotates until interrupted)
+//! cargo hfuzz run-all --each 10m --total 2h
+//! 
+//! # or fuzzes them all at once, each with 2 honggfuzz instances pinned to its share of CPUs 0 to 15,
+//! # for an hour, the edges, corpus sizes and crashes of every target being printed every 5 seconds
+//! cargo hfuzz run-parallel --instances 2 --cpus 0-15 --max-time 3600
 //! ```
 //! 
-//! You can also build and run your project without compile-time software instrumentation (LLVM's SanCov passes)
+//! Ctrl-C (or SIGTERM, SIGHUP) is passed on once to honggfuzz, which stops gracefully: the session
+//! summary and `summary.json` are still written, "run-all" and "run-parallel" stop their other targets,
+//! and cargo-hfuzz exits with 128 plus the signal number (130 for Ctrl-C). On Linux, honggfuzz is also
+//! stopped when cargo-hfuzz itself is killed.
 //! 
-//! This allows you for example to try hardware-only feedback driven fuzzing:
+//! In a workspace, the fuzz targets of every member depending on honggfuzz are found from any of them,
+//! and each member gets its own directory in `hfuzz_workspace`
 //! 
 //! ```sh
-//! # builds without fuzzing instrumentation and then fuzz the "example" target using hardware-based feedback
-//! HFUZZ_RUN_ARGS="--linux_perf_ipt_block --linux_perf_instr --linux_perf_branch" cargo hfuzz run-no-instr example
+//! # only the targets of the "parser" member
+//! cargo hfuzz -p parser run-all --each 10m
 //! ```
 //! 
-//! Clean
+//! Every command cargo-hfuzz runs, with the environment variables it sets and how it exited, is logged with
+//! a timestamp in `hfuzz_workspace/{TARGET}/cargo-hfuzz.log`, to tell what a CI job actually executed
 //! 
 //! ```sh
-//! # a wrapper on "cargo clean" which cleans the fuzzing_target directory
-//! cargo hfuzz clean 
+//! # also prints the commands as they run ("-vv" with their environment), before the command
+//! cargo hfuzz -v run example
+//! 
+//! # hides the progress notes of cargo-hfuzz and builds with "cargo build --quiet"
+//! cargo hfuzz -q replay example
+//! 
+//! # for orchestration tools: one JSON object per line on stderr, like
+//! # {"time":"2024-05-17T09:03:12Z","event":"crash_detected","target":"example","crash_file":"...",...},
+//! # the events being build_started, build_finished, fuzzer_started, fuzzer_exited, crash_detected,
+//! # session_ended, and note (the progress notes) or with -v command_started and command_exited
+//! cargo hfuzz --log-format json run --max-time 600 example
+//! 
+//! # prints the cargo build and honggfuzz command lines, with RUSTFLAGS and the other environment
+//! # variables cargo-hfuzz sets, without running them (build, run, run-no-instr, run-debug and run-binary)
+//! cargo hfuzz --dry-run run --sanitizer address example
 //! ```
 //! 
-//! Version
+//! Once you got a crash, replay it easily in a debug environment
 //! 
 //! ```sh
-//! cargo hfuzz version
+//! # builds the target in debug mode and replays automatically the crash in gdb
+//! cargo hfuzz run-debug example fuzzing_workspace/*.fuzz
+//! 
+//! # without a crash filename, replays the most recent crash of the target
+//! cargo hfuzz run-debug example
+//! 
+//! # given a directory, runs the harness over every file of it in a single process, going on past the
+//! # panics (not the aborts) and printing which files panicked, to triage many crashes at once
+//! cargo hfuzz run-debug example hfuzz_workspace/example
+//! 
+//! # records the crash with rr (https://rr-project.org) and replays it in gdb, where
+//! # "reverse-continue" and "reverse-step" go back to what corrupted the state
+//! cargo hfuzz run-debug --rr example
 //! ```
 //! 
-//! ### Environment variables
+//! Shrink a crashing input while making sure it still crashes with the same stack
 //! 
-//! #### `RUSTFLAGS`
+//! ```sh
+//! # writes the minimized reproducer next to the original, as `CRASH_FILENAME.minimized`
+//! cargo hfuzz tmin example hfuzz_workspace/example/*.fuzz
+//! ```
 //! 
-//! You can use `RUSTFLAGS` to send additional arguments to `rustc`.
+//! After a long run, group the crash files by bug
 //! 
-//! For instance, you can enable the use of LLVM's [sanitizers](https://github.com/japaric/rust-san).
-//! This is a recommended option if you want to test your `unsafe` rust code but it will have an impact on performance.
+//! ```sh
+//! # replays every crash file of the workspace in a debug build, dedupes them by stack hash
+//! # and prints the panic message and the stack of one representative per bucket
+//! cargo hfuzz triage example
+//! 
+//! # the same, also writing the bugs as SARIF for GitHub code scanning (with the upload-sarif action)
+//! # or any other SARIF consumer, one result per bucket with its stack and panic message
+//! cargo hfuzz triage --sarif example.sarif example
+//! 
+//! # or have "cargo hfuzz run" do it when a session found new crashes, also minimizing the
+//! # representative of every bucket: hfuzz_workspace/example/crashes/triaged gets its input as
+//! # STACK_HASH.input and its panic message, stack and crash files as STACK_HASH.txt, all the
+//! # buckets going to crashes.sarif
+//! cargo hfuzz run --keep-going --max-time 86400 --post-process example
+//! ```
+//! 
+//! Use the corpus as a regression suite
 //! 
 //! ```sh
-//! RUSTFLAGS="-Z sanitizer=address" cargo hfuzz run example
+//! # replays the corpus and the crash files without mutation, exits with 1 if any of them fails
+//! cargo hfuzz replay example
+//! 
+//! # in GitHub Actions, also annotates the sources with the failures: an error on the frame of the
+//! # workspace closest to each crash and a notice on the ones calling it, shown inline on pull requests
+//! # (triage takes `--annotate github` too)
+//! cargo hfuzz replay --annotate github example
+//! 
+//! # the exit codes are a policy: `--exit-code-on-crash CODE` for the crashes (0 to ignore them),
+//! # `--exit-zero-on-timeout` not to fail on the inputs timing out, and likewise for verify, while
+//! # run only fails with `--exit-code-on-crash CODE` or `--ci`, and on hangs with `--fail-on-hang`
+//! cargo hfuzz replay --exit-code-on-crash 3 --exit-zero-on-timeout example
 //! ```
 //! 
-//! #### `HFUZZ_BUILD_ARGS`
+//! Check which crashes your fixes got rid of
 //! 
-//! You can use `HFUZZ_BUILD_ARGS` to send additional arguments to `cargo build`.
+//! ```sh
+//! # replays each crash file 3 times (`--runs N`) and reports the ones still reproducing, fixed or flaky,
+//! # "--remove-fixed" deletes the fixed ones, exits with 1 if any still crashes (even sometimes)
+//! cargo hfuzz verify example
+//! ```
 //! 
-//! #### `HFUZZ_RUN_ARGS`
+//! Once a crash is fixed, keep it fixed
 //! 
-//! You can use `HFUZZ_RUN_ARGS` to send additional arguments to `honggfuzz`.
-//! See [USAGE](https://github.com/google/honggfuzz/blob/master/docs/USAGE.md) for the list of those.
+//! ```sh
+//! # prints a `#[test]` embedding the crash bytes and running the body of the `fuzz!` harness on them,
+//! # to paste where what the harness uses is in scope (e.g. a `#[cfg(test)]` module of the target, or a
+//! # module of the library for a `#![no_main]` harness of `fuzz_target!`, which can't have tests)
+//! cargo hfuzz gen-test example hfuzz_workspace/example/SIGABRT.PC.7ffff7a42e97.STACK.18a3b2c1d.ADDR.0.fuzz
+//! ```
 //! 
-//! For example:
+//! Import seeds from another machine or another fuzzer
 //! 
 //! ```sh
-//! # 1 second of timeout
-//! # use 12 fuzzing thread
-//! # be verbose
-//! # stop after 1000000 fuzzing iteration
-//! # exit upon crash
-//! HFUZZ_RUN_ARGS="-t 1 -n 12 -v -N 1000000 --exit_upon_crash" cargo hfuzz run example
+//! # adds to the corpus of "example" the inputs of these directories bringing new coverage
+//! cargo hfuzz merge example ../other_machine/input ./afl_seeds
+//! 
+//! # minimizes the corpus of "example" with honggfuzz's `-M` instead of fuzzing it: the inputs covering
+//! # nothing the others don't are removed (honggfuzz saving the ones it keeps aside, the corpus being only
+//! # changed once it is done), the kept inputs of the other "--input" directories being added to it
+//! cargo hfuzz run --minimize example
+//! cargo hfuzz run --minimize --input hfuzz_workspace/example/input --input ../other_machine/input example
+//! 
+//! # moves corpora between honggfuzz and AFL/AFL++: imports the queues, crashes and hangs of every
+//! # instance of an afl-fuzz output directory, or exports the workspace of "example" in the same layout
+//! cargo hfuzz corpus import-afl example ../afl/output
+//! cargo hfuzz corpus export-afl example ../afl/seeds
+//! 
+//! # shares the corpus of "example" between machines and CI jobs, in `example/` under the remote
+//! # (s3://, gs://, http(s):// with PUT requests, or anything rsync takes), pushes and pulls only
+//! # adding inputs, without `REMOTE` they use HFUZZ_CORPUS_REMOTE or `corpus.remote` of hfuzz.toml
+//! cargo hfuzz corpus push example s3://my-bucket/corpora
+//! cargo hfuzz corpus pull example s3://my-bucket/corpora
 //! ```
 //! 
-//! #### `HFUZZ_DEBUGGER`
+//! Check how a campaign is going
 //! 
-//! By default we use `rust-lldb` but you can change it to `rust-gdb`, `gdb`, `/usr/bin/lldb-7` ...
+//! ```sh
+//! # prints the coverage, execs/sec, corpus size and crash counts of the last "cargo hfuzz run",
+//! # read from its log in `hfuzz_workspace/example/honggfuzz.log`, `--json` is also supported
+//! # (the log is saved when honggfuzz doesn't draw its screen, i.e. out of a terminal or with `-v`)
+//! cargo hfuzz stats example
 //! 
-//! #### `CARGO_TARGET_DIR`
+//! # each "cargo hfuzz run" also ends by writing `hfuzz_workspace/example/summary.json`, with the crashes
+//! # and unique stacks found, the corpus growth, the execs, the duration and where the target is defined
+//! cat hfuzz_workspace/example/summary.json
 //! 
-//! Target compilation directory, defaults to `hfuzz_target` to not clash with `cargo build`'s default `target` directory.
+//! # while fuzzing (from another terminal with run-parallel or several "cargo hfuzz run"), shows the
+//! # edges, last new path, crashes and corpus growth of every target, all its honggfuzz instances
+//! # together, and their execs/sec once they stopped, from the logs "cargo hfuzz stats" reads
+//! cargo hfuzz dashboard
 //! 
-//! #### `HFUZZ_WORKSPACE`
+//! # each "cargo hfuzz run" records its options, environment variables, git commit and corpus in
+//! # `hfuzz_workspace/example/sessions/`, their list is printed by `--list`, and "resume" goes on fuzzing
+//! # with the settings of the last one (warning if the sources or hfuzz.toml changed since)
+//! cargo hfuzz resume example
+//! ```
 //! 
-//! Honggfuzz working directory, defaults to `hfuzz_workspace`.
+//! Measure the throughput of a target
 //! 
-//! #### `HFUZZ_INPUT`
+//! ```sh
+//! # builds "example" instrumented like for fuzzing, and for 30 seconds its harness replays its corpus
+//! # without mutation, in the same process like when fuzzing, printing the execs/sec, the latency
+//! # percentiles of an input and the allocations per input (`--json` is also supported), the
+//! # instrumentation options of "cargo hfuzz build", like `--no-cmp-coverage`, telling what they cost
+//! cargo hfuzz bench --time 30 example
+//! ```
 //! 
-//! Honggfuzz input files (also called "corpus"), defaults to `$HFUZZ_WORKSPACE/{TARGET}/input`.
+//! Find out which code your fuzzer never reaches
 //! 
-//! ## Conditionnal compilation
+//! ```sh
+//! # rebuilds with source-based coverage (needs `rustup component add llvm-tools-preview`),
+//! # replays the corpus and prints a report, the HTML version is in `hfuzz_workspace/example/coverage/html`
+//! cargo hfuzz coverage example
+//! # or only builds it, into `hfuzz_target/coverage` not to invalidate the fuzzing build, for tools of your own
+//! cargo hfuzz build --profile coverage --bin example
+//! ```
 //! 
-//! Sometimes, it is necessary to make some specific adaptation to your code to yield a better fuzzing efficiency.
+//! You can also build and run your project without compile-time software instrumentation (LLVM's SanCov passes)
 //! 
-//! For instance:
-//! - Make you software behavior as much as possible deterministic on the fuzzing input
-//!   - [PRNG](https://en.wikipedia.org/wiki/Pseudorandom_number_generator)s must be seeded with a constant or the fuzzer input
-//!   - Behavior shouldn't change based on the computer's clock.
-//!   - Avoid potential undeterministic behavior from racing threads.
-//!   - ...
-//! - Never ever call `std::process::exit()`.
-//! - Disable logging and other unnecessary functionnalities.
-//! - Try to avoid modifying global state when possible.
+//! This allows you for example to try hardware-only feedback driven fuzzing:
 //! 
+//! ```sh
+//! # builds without fuzzing instrumentation and then fuzz the "example" target using hardware-based feedback
+//! HFUZZ_RUN_ARGS="--linux_perf_ipt_block --linux_perf_instr --linux_perf_branch" cargo hfuzz run-no-instr example
+//! ```
 //! 
-//! When building with `cargo hfuzz`, the argument `--cfg fuzzing` is passed to `rustc` to allow you to condition the compilation of thoses adaptations thanks to the `cfg` macro like so:
+//! The same feedback fuzzes executables which aren't Rust targets at all, like the tests of a C library your crate
+//! wraps, side by side with its harnesses: `cargo hfuzz run-binary NAME -- PROGRAM [ARGS ...]` runs the program once
+//! per input, written to the file named by `___FILE___` in its arguments or given on its stdin without it, guided by what
+//! perf counts on Linux (`--feedback instr` by default, `branch`, `bts-edge` or `ipt-block` on Intel, `none` for
+//! blind fuzzing) and catching its crashes with ptrace. NAME is the workspace it gets in `hfuzz_workspace`, with its
+//! corpus, crashes, stats and summary like those of a target, and the settings of `hfuzz.toml` apply to it. The engine
+//! is the one `cargo hfuzz build` installs.
 //! 
-//! ```rust
-//! # use rand::{self, Rng, SeedableRng};
-//! # use rand_chacha;
-//! # fn main() {
-//! #[cfg(fuzzing)]
-//! let mut rng = rand_chacha::ChaCha8Rng::from_seed(&[0]);
-//! #[cfg(not(fuzzing))]
-//! let mut rng = rand::thread_rng();
-//! # }
+//! ```sh
+//! # fuzzes pngtest for 10 minutes, the inputs in hfuzz_workspace/libpng and its crashes there too
+//! cargo hfuzz run-binary --max-time 600 libpng -- ./vendor/libpng/pngtest ___FILE___
 //! ```
 //! 
-//! Also, when building in debug mode, the `fuzzing_debug` argument is added in addition to `fuzzing`.
+//! Build
 //! 
-//! For more information about conditional compilation, please see the [reference](https://doc.rust-lang.org/reference/attributes.html#conditional-compilation).
+//! ```sh
+//! # builds every fuzz target of the crate with fuzzing instrumentation, without running any of them
+//! # (also works with "build-debug", "build-no-instr" and "build-grcov")
+//! cargo hfuzz build --all-targets
+//! ```
 //! 
-//! ## Relevant documentation about honggfuzz
-//! * [USAGE](https://github.com/google/honggfuzz/blob/master/docs/USAGE.md)
-//! * [FeedbackDrivenFuzzing](https://github.com/google/honggfuzz/blob/master/docs/FeedbackDrivenFuzzing.md)
-//! * [PersistentFuzzing](https://github.com/google/honggfuzz/blob/master/docs/PersistentFuzzing.md)
+//! List
 //! 
-//! ## About Rust fuzzing
-//!  
-//! There is other projects providing Rust fuzzing support at [github.com/rust-fuzz](https://github.com/rust-fuzz). 
-//!  
-//! You'll find support for [AFL](https://github.com/rust-fuzz/afl.rs) and LLVM's [LibFuzzer](https://github.com/rust-fuzz/cargo-fuzz) and there is also a [trophy case](https://github.com/rust-fuzz/trophy-case) ;-) .
+//! ```sh
+//! # lists the fuzz targets of the crate (and of the workspace members) with their corpus size and crash count
+//! cargo hfuzz list
 //! 
-//! This crate was inspired by those projects!
-
-/// Re-export of arbitrary crate used to generate structured inputs
-pub use arbitrary;
-
-#[cfg(all(fuzzing, not(fuzzing_debug)))]
-extern "C" {
+//! # same thing, for scripts
+//! cargo hfuzz list --json
+//! ```
+//! 
+//! Clean
+//! 
+//! ```sh
+//! # a wrapper on "cargo clean" which cleans the fuzzing_target directory
+//! cargo hfuzz clean 
+//! 
+//! # only removes the binaries of the "example" target, and with `--workspace` its corpus, crashes and logs too
+//! cargo hfuzz clean example --workspace
+//! ```
+//! 
+//! Migrate
+//! 
+//! ```sh
+//! # moves the corpora, crashes and logs of the targets where this cargo-hfuzz expects them, like into
+//! # hfuzz_workspace/PACKAGE/TARGET once several workspace members have fuzz targets, and stamps the
+//! # version of the layout in hfuzz_workspace/.layout-version ("--dry-run" tells what would be moved)
+//! cargo hfuzz migrate
+//! ```
+//! 
+//! Version
+//! 
+//! ```sh
+//! # also prints the versions of the honggfuzz library and engine built in `hfuzz_target`,
+//! # warning when they are older than or don't match this cargo-hfuzz
+//! cargo hfuzz version
+//! ```
+//! 
+//! ### Configuration file
+//! 
+//! Instead of a wall of environment variables, the settings of a project can be written once
+//! in a `hfuzz.toml` at the root of the crate. Command line options and environment variables
+//! still take precedence over them.
+//! 
+//! ```toml
+//! [run]
+//! # given to honggfuzz before HFUZZ_RUN_ARGS
+//! args = ["--exit_upon_crash"]
+//! # same as the options of "cargo hfuzz run"
+//! timeout = 1
+//! jobs = 12
+//! dict = "fuzz/http.dict"
+//! max_len = 4096
+//! mutations = 3
+//! # like `--base-files-only`, over the `mutations` of the targets too
+//! base_files_only = false
+//! # same as HFUZZ_INPUT, or a list of directories like `--input`
+//! input = "fuzz/corpus"
+//! # seed inputs of every target, see the `seeds` of a target below
+//! seeds = ["fuzz/seeds/common"]
+//! link_seeds = false
+//! # like `--post-process`
+//! post_process = true
+//! 
+//! [build]
+//! # given to cargo build before HFUZZ_BUILD_ARGS
+//! args = ["--features", "fuzzing"]
+//! # builds with `-Z sanitizer=address` (or "leak", "memory", "thread"), needs a nightly toolchain
+//! sanitizer = "address"
+//! # builds this release of honggfuzz, downloaded from GitHub once, instead of the sources bundled with
+//! # the library
+//! engine_version = "2.6"
+//! 
+//! [workspace]
+//! # directories of the crashes, reports and logs, a subdirectory per target, instead of the workspace
+//! # of each target (HFUZZ_CRASH_DIR, HFUZZ_REPORT_DIR and HFUZZ_LOG_DIR take precedence)
+//! crash_dir = "fuzz/crashes"
+//! report_dir = "fuzz/reports"
+//! log_dir = "fuzz/logs"
+//! 
+//! [debug]
+//! # a debugger profile (built in are lldb, gdb, cgdb and windbg) or binary for run-debug,
+//! # HFUZZ_DEBUGGER takes precedence
+//! debugger = "project-gdb"
+//! # commands run by any debugger before breaking on panics
+//! init = ["b my_crate::parser::check"]
+//! 
+//! [corpus]
+//! # where "cargo hfuzz corpus push/pull" share the corpora, HFUZZ_CORPUS_REMOTE takes precedence
+//! remote = "gs://my-bucket/corpora"
+//! 
+//! [notify]
+//! # POSTs JSON to this URL for every new unique crash, coverage plateau and end of a fuzzing session,
+//! # HFUZZ_WEBHOOK takes precedence
+//! webhook = "https://hooks.slack.com/services/T000/B000/XXXX"
+//! # seconds without new coverage making a plateau, an hour by default, told from the log of honggfuzz
+//! # (so not while it draws its screen in a terminal, see "cargo hfuzz stats")
+//! plateau = 7200
+//! 
+//! # profiles can be added, or override the built-in ones
+//! [debugger.project-gdb]
+//! program = "rust-gdb"
+//! # how the debugger takes its commands: "lldb", "gdb", "cgdb" or "windbg"
+//! style = "gdb"
+//! init = ["source fuzz/pretty_printers.py"]
+//! ```
+//! 
+//! Settings specific to a fuzz target go in the `Cargo.toml` of its package, they take precedence
+//! over `hfuzz.toml`. Paths are relative to that `Cargo.toml`.
+//! 
+//! ```toml
+//! [package.metadata.hfuzz.targets.example]
+//! timeout = 5
+//! max_len = 1024
+//! dict = "http.dict"
+//! mutations = 2
+//! # given to honggfuzz after the ones of hfuzz.toml
+//! args = ["--only_printable"]
+//! # inputs added to the corpus of the target before fuzzing, if it doesn't have them yet: directories,
+//! # files or patterns (`**` matching any number of directories), put back if a minimization removed them
+//! seeds = ["seeds/example", "tests/fixtures/*.bin"]
+//! # symlinks them in the corpus instead of copying them, like `link_seeds` of hfuzz.toml
+//! link_seeds = true
+//! ```
+//! 
+//! ### Environment variables
+//! 
+//! #### `RUSTFLAGS`
+//! 
+//! You can use `RUSTFLAGS` to send additional arguments to `rustc`.
+//! 
+//! For instance, you can enable the use of LLVM's [sanitizers](https://github.com/japaric/rust-san).
+//! This is a recommended option if you want to test your `unsafe` rust code but it will have an impact on performance.
+//! 
+//! ```sh
+//! # the same as RUSTFLAGS="-Z sanitizer=address" cargo hfuzz run example
+//! cargo hfuzz run --sanitizer address example
+//! ```
+//! 
+//! `--sanitizer` (also accepted by `cargo hfuzz build`, or set with `HFUZZ_SANITIZER`) takes `address`,
+//! `leak`, `memory` or `thread` and needs a nightly toolchain. It also tracks the origins of
+//! uninitialized memory for `memory`, rebuilds the standard library with `-Z build-std` for `memory`
+//! and `thread` (install the `rust-src` component), and makes `run-debug` stop on the first report.
+//! 
+//! Before building, cargo-hfuzz asks the rustc running what it supports rather than relying on a recipe of
+//! `RUSTFLAGS`: it fails with a fix when rustc isn't a nightly one and `RUSTC_BOOTSTRAP` isn't set, when the target
+//! spec of the triple doesn't list the sanitizer, or when `rust-src` is missing. The build of the targets is always
+//! given `--target`, so that the build scripts and the proc-macros, built for the host, are not sanitized.
+//! 
+//! Besides the edges, instrumented builds trace comparisons and divisions, which helps the fuzzer get
+//! past magic values and checksums at the cost of throughput. `build` and `run` can turn each of them
+//! on or off, along with array indices:
+//! 
+//! ```sh
+//! cargo hfuzz run --no-cmp-coverage --gep-coverage example
+//! ```
+//! 
+//! To experiment with a flag set of your own, like new coverage options on nightly, `--no-default-rustflags`
+//! (or setting `HFUZZ_NO_DEFAULT_RUSTFLAGS`) leaves out the flags cargo-hfuzz builds with (optimization,
+//! debug assertions, the sancov passes and the traces above) and only keeps its `--cfg fuzzing` ones, the
+//! ones of `--sanitizer` and `RUSTFLAGS`.
+//! 
+//! ```sh
+//! RUSTFLAGS="-C opt-level=3 -C passes=sancov-module -C llvm-args=-sanitizer-coverage-level=3 \
+//!     -C llvm-args=-sanitizer-coverage-trace-pc-guard" cargo hfuzz run --no-default-rustflags example
+//! ```
+//! 
+//! The cargo features of the targets are chosen with `--features`, `--all-features` and
+//! `--no-default-features`, which `build`, `run`, `replay`, `verify`, `triage`, `tmin` and `bench` check
+//! against the manifests of the fuzz targets. Each session records them, and replaying a crash with other
+//! features than the session which found it prints a warning, as it may not reproduce then.
+//! 
+//! ```sh
+//! cargo hfuzz run --features simd,std --no-default-features example
+//! # warns if the crash was found without these features
+//! cargo hfuzz replay --features simd,std --no-default-features example
+//! ```
+//! 
+//! To build the targets with another build system (Bazel, Meson, a wrapper script...), `print-env` prints
+//! the RUSTFLAGS and the other environment variables of the instrumented builds, with the arguments of
+//! cargo build. It takes the options of `build` changing them, and `--no-instr`, `--debug` or `--grcov`
+//! for the other builds.
+//! 
+//! ```sh
+//! # export VAR=... lines for a shell
+//! eval "$(cargo hfuzz print-env --sanitizer address)"
+//! # {"env":{"RUSTFLAGS":"--cfg fuzzing ...",...},"cargo_args":["build","--target","...","--release"]}
+//! cargo hfuzz print-env --json
+//! ```
+//! 
+//! `build` and `run` cross-compile the targets and honggfuzz with `--target TRIPLE` (or `target` in the
+//! `[build]` section of `hfuzz.toml`). honggfuzz is built with the C compiler of `CC_<triple>`,
+//! `TARGET_CC` or `CC`, `<triple without "unknown">-gcc` by default. It lands in `hfuzz_target/<triple>` and runs
+//! through the command of `HFUZZ_RUNNER` (or `runner` in the `[run]` section), like an emulator.
+//! 
+//! ```sh
+//! rustup target add aarch64-unknown-linux-gnu
+//! HFUZZ_RUNNER="qemu-aarch64 -L /usr/aarch64-linux-gnu" cargo hfuzz run --target aarch64-unknown-linux-gnu example
+//! ```
+//! 
+//! The musl targets of the architecture of the host, like `x86_64-unknown-linux-musl` on `x86_64-unknown-linux-gnu`,
+//! run on it without a runner: honggfuzz is built for the host, only the libraries linked into the targets being built
+//! with the C compiler of the target, `musl-gcc` of the musl tools by default. The targets are linked statically, and
+//! the sanitizers are not available for them:
+//! 
+//! ```sh
+//! sudo apt install musl-tools
+//! rustup target add x86_64-unknown-linux-musl
+//! cargo hfuzz run --target x86_64-unknown-linux-musl example
+//! ```
+//! 
+//! On Alpine, where musl is the host, honggfuzz builds with the packages of its dependencies, or without `binutils-dev`
+//! when the `engine-bfd` feature is disabled:
+//! 
+//! ```sh
+//! apk add build-base linux-headers binutils-dev libunwind-dev xz-dev
+//! ```
+//! 
+//! `run --android` builds the target and honggfuzz for Android, `aarch64-linux-android` unless `--target` gives
+//! another Android triple, and fuzzes on the device of adb (`ANDROID_SERIAL` picking one, `HFUZZ_ADB` another adb):
+//! the engine, the target and its workspace are pushed to `/data/local/tmp/hfuzz`, and the workspace is pulled back
+//! with the new inputs and crashes when honggfuzz exits. honggfuzz is built by ndk-build with the NDK of
+//! `ANDROID_NDK_HOME`, for the API level of `ANDROID_API` (`android-26` by default), and the target is linked by the
+//! clang of the NDK unless `CARGO_TARGET_<TRIPLE>_LINKER` is set. The Android engine needs the libunwind and capstone
+//! submodules of honggfuzz, missing from the sources packaged on crates.io: depend on honggfuzz-rs from git, cargo
+//! checking out its submodules.
+//! 
+//! ```sh
+//! rustup target add aarch64-linux-android
+//! export ANDROID_NDK_HOME=~/Android/Sdk/ndk/26.1.10909125
+//! cargo hfuzz run --android --max-time 600 example
+//! ```
+//! 
+//! #### `HFUZZ_BUILD_ARGS`
+//! 
+//! You can use `HFUZZ_BUILD_ARGS` to send additional arguments to `cargo build`, quoted like on
+//! a command line.
+//! 
+//! #### `HFUZZ_RUN_ARGS`
+//! 
+//! You can use `HFUZZ_RUN_ARGS` to send additional arguments to `honggfuzz`.
+//! See [USAGE](https://github.com/google/honggfuzz/blob/master/docs/USAGE.md) for the list of those.
+//! 
+//! For example:
+//! 
+//! ```sh
+//! # 1 second of timeout
+//! # use 12 fuzzing thread
+//! # be verbose
+//! # stop after 1000000 fuzzing iteration
+//! # exit upon crash
+//! HFUZZ_RUN_ARGS="-t 1 -n 12 -v -N 1000000 --exit_upon_crash" cargo hfuzz run example
+//! ```
+//! 
+//! Arguments are split like a shell would: quote or escape the ones containing spaces.
+//! 
+//! ```sh
+//! HFUZZ_RUN_ARGS='-w "my dict.txt"' cargo hfuzz run example
+//! ```
+//! 
+//! #### `HFUZZ_DEBUGGER`
+//! 
+//! By default we use `rust-lldb` but you can change it to `rust-gdb`, `gdb`, `/usr/bin/lldb-7` ...
+//! It can also name a debugger profile of `hfuzz.toml`.
+//! 
+//! #### `CARGO_TARGET_DIR`
+//! 
+//! Target compilation directory, defaults to `hfuzz_target` to not clash with `cargo build`'s default `target` directory.
+//! 
+//! #### `HFUZZ_WORKSPACE`
+//! 
+//! Honggfuzz working directory, defaults to `hfuzz_workspace`.
+//! 
+//! #### `HFUZZ_CRASH_DIR`, `HFUZZ_REPORT_DIR` and `HFUZZ_LOG_DIR`
+//! 
+//! Where the crashes, the reports and the logs of the targets are kept, in `{DIR}/{TARGET}`, instead of
+//! `$HFUZZ_WORKSPACE/{TARGET}`, like `--crash-dir`, `--report-dir` and `--log-dir` of `cargo hfuzz run`.
+//! 
+//! #### `HFUZZ_INPUT`
+//! 
+//! Honggfuzz input files (also called "corpus"), defaults to `$HFUZZ_WORKSPACE/{TARGET}/input`.
+//! Several directories separated by `:` are all drawn from, the new inputs being saved in the first one.
+//! 
+//! #### `HFUZZ_CORPUS_REMOTE`
+//! 
+//! Where `cargo hfuzz corpus push` and `pull` share the corpora when no remote is given: an `s3://` or
+//! `gs://` URL (with the AWS CLI or gsutil), an `http://` or `https://` one (with curl, the server taking
+//! PUT requests) or any rsync destination like `host:dir`.
+//! 
+//! #### `HFUZZ_ENGINE_PATH`
+//! 
+//! A honggfuzz executable to fuzz with instead of the one built into `hfuzz_target`, like the one of a
+//! distribution or of a container image, also given to `cargo hfuzz run` as `--engine PATH`. Its major
+//! version has to be the one of the honggfuzz sources of this crate, which the targets link.
+//! 
+//! #### `HFUZZ_WEBHOOK`
+//! 
+//! A URL that `cargo hfuzz run` POSTs JSON to (with curl) when a new unique crash is found, when the
+//! coverage plateaus and when the session ends, like `notify.webhook` in `hfuzz.toml`. Every notification
+//! has an `event` (`crash`, `plateau` or `session_end`), the `target`, the `host` and a `text` summary
+//! which chat webhooks like the ones of Slack show.
+//! 
+//! ## Conditionnal compilation
+//! 
+//! Sometimes, it is necessary to make some specific adaptation to your code to yield a better fuzzing efficiency.
+//! 
+//! For instance:
+//! - Make you software behavior as much as possible deterministic on the fuzzing input
+//!   - [PRNG](https://en.wikipedia.org/wiki/Pseudorandom_number_generator)s must be seeded with a constant or the fuzzer input, like the one of `honggfuzz::rng()`, seeded from the input running (`honggfuzz::seed_rng(data)` seeding it the same way in a test)
+//!   - Behavior shouldn't change based on the computer's clock.
+//!   - Avoid potential undeterministic behavior from racing threads.
+//!   - ...
+//! - Never ever call `std::process::exit()`.
+//! - Disable logging and other unnecessary functionnalities.
+//! - Try to avoid modifying global state when possible.
+//! 
+//! 
+//! When building with `cargo hfuzz`, the argument `--cfg fuzzing` is passed to `rustc` to allow you to condition the compilation of thoses adaptations thanks to the `cfg` macro like so:
+//! 
+//! ```rust
+//! # use rand::{self, Rng, SeedableRng};
+//! # use rand_chacha;
+//! # fn main() {
+//! #[cfg(fuzzing)]
+//! let mut rng = rand_chacha::ChaCha8Rng::from_seed(&[0]);
+//! #[cfg(not(fuzzing))]
+//! let mut rng = rand::thread_rng();
+//! # }
+//! ```
+//! 
+//! Also, when building in debug mode, the `fuzzing_debug` argument is added in addition to `fuzzing`.
+//! 
+//! The builds of `cargo hfuzz bench` get `fuzzing_bench` too, and a global allocator counting the
+//! allocations: a target with its own global allocator has to leave it out when `cfg(fuzzing_bench)` is set.
+//! 
+//! For more information about conditional compilation, please see the [reference](https://doc.rust-lang.org/reference/attributes.html#conditional-compilation).
+//! 
+//! ## Relevant documentation about honggfuzz
+//! * [USAGE](https://github.com/google/honggfuzz/blob/master/docs/USAGE.md)
+//! * [FeedbackDrivenFuzzing](https://github.com/google/honggfuzz/blob/master/docs/FeedbackDrivenFuzzing.md)
+//! * [PersistentFuzzing](https://github.com/google/honggfuzz/blob/master/docs/PersistentFuzzing.md)
+//! 
+//! ## About Rust fuzzing
+//!  
+//! There is other projects providing Rust fuzzing support at [github.com/rust-fuzz](https://github.com/rust-fuzz). 
+//!  
+//! You'll find support for [AFL](https://github.com/rust-fuzz/afl.rs) and LLVM's [LibFuzzer](https://github.com/rust-fuzz/cargo-fuzz) and there is also a [trophy case](https://github.com/rust-fuzz/trophy-case) ;-) .
+//! 
+//! This crate was inspired by those projects!
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(fuzzing, any(fuzzing_debug, fuzzing_bench), not(feature = "std")))]
+compile_error!("the builds of \"cargo hfuzz run-debug\" and \"cargo hfuzz bench\" need the \"std\" feature of honggfuzz");
+
+/// Re-export of arbitrary crate used to generate structured inputs
+#[cfg(feature = "std")]
+pub use arbitrary;
+
+pub mod input;
+pub use input::FuzzInput;
+mod rng;
+pub use rng::{rng, seed_rng, Rng};
+pub mod feedback;
+#[cfg(feature = "std")]
+pub mod logs;
+#[cfg(feature = "std")]
+pub mod net;
+#[cfg(all(feature = "std", unix))]
+pub mod socket_fuzzer;
+/// Derive [`FuzzInput`] for a struct or an enum, see the [`input`] module.
+pub use honggfuzz_derive::FuzzInput;
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+extern "C" {
     fn HF_ITER(buf_ptr: *mut *const u8, len_ptr: *mut usize );
 }
 
@@ -239,113 +1107,941 @@ extern "C" {
 #[cfg(not(fuzzing))]
 #[allow(unused_variables)]
 pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
+    not_built_with_cargo_hfuzz();
+}
+
+#[cfg(all(not(fuzzing), feature = "std"))]
+fn not_built_with_cargo_hfuzz() -> ! {
     eprintln!("This executable hasn't been built with \"cargo hfuzz\".");
     eprintln!("Try executing \"cargo hfuzz build\" and check out \"hfuzz_target\" directory.");
     eprintln!("Or execute \"cargo hfuzz run TARGET\"");
     std::process::exit(17);
 }
 
+#[cfg(all(not(fuzzing), not(feature = "std")))]
+fn not_built_with_cargo_hfuzz() -> ! {
+    panic!("This executable hasn't been built with \"cargo hfuzz\", try executing \"cargo hfuzz run TARGET\"");
+}
+
 // Registers a panic hook that aborts the process before unwinding.
 // It is useful to abort before unwinding so that the fuzzer will then be
 // able to analyse the process stack frames to tell different bugs appart.
-#[cfg(all(fuzzing, not(fuzzing_debug)))]
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench), feature = "std"))]
 lazy_static::lazy_static! {
     static ref PANIC_HOOK: () = {
-        std::panic::set_hook(Box::new(|_| {
+        std::panic::set_hook(Box::new(|info| {
+            // the report shows which input crashed, not only the file it is saved in
+            eprintln!("{}", info);
+            with_running_input(|input| input.map(dump_input));
             std::process::abort();
         }))
     };
 }
 
-#[cfg(all(fuzzing, not(fuzzing_debug)))]
-pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
-    use std::mem::MaybeUninit;
+// Maximum length of the inputs, set by "cargo hfuzz run --max-len BYTES" which gives
+// the same limit to honggfuzz, longer inputs are truncated so that both always agree.
+#[cfg(all(fuzzing, feature = "std"))]
+lazy_static::lazy_static! {
+    static ref MAX_LEN: usize = std::env::var("CARGO_HONGGFUZZ_MAX_LEN").ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(usize::MAX);
+}
 
-    // sets panic hook if not already done
-    lazy_static::initialize(&PANIC_HOOK);
+// Set while the harness runs over an input, for `fuzz_init!` to tell when its setup comes too late.
+#[cfg(fuzzing)]
+static RUNNING_INPUT: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
 
-    // get buffer from honggfuzz runtime
-    let buf;
+// The input the closure runs over, for `rng()` and the failed `fuzz_assert!` to see it, null outside of it.
+static INPUT_PTR: core::sync::atomic::AtomicPtr<u8> = core::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
+static INPUT_LEN: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
 
-    let mut buf_ptr = MaybeUninit::<*const u8>::uninit();
-    let mut len_ptr = MaybeUninit::<usize>::uninit();
+fn with_running_input<F, R>(f: F) -> R where F: FnOnce(Option<&[u8]>) -> R {
+    use core::sync::atomic::Ordering;
 
-    unsafe {
-        HF_ITER(buf_ptr.as_mut_ptr(), len_ptr.as_mut_ptr());
-        buf = ::std::slice::from_raw_parts(buf_ptr.assume_init(), len_ptr.assume_init());
+    let ptr = INPUT_PTR.load(Ordering::Relaxed);
+    match ptr.is_null() {
+        true => f(None),
+        // only set while the closure runs over the input, which outlives it
+        false => f(Some(unsafe { core::slice::from_raw_parts(ptr, INPUT_LEN.load(Ordering::Relaxed)) })),
     }
+}
 
-    // We still catch unwinding panics just in case the fuzzed code modifies
-    // the panic hook.
-    // If so, the fuzzer will be unable to tell different bugs appart and you will
-    // only be able to find one bug at a time before fixing it to then find a new one.
-    // The closure is assumed to be unwind-safe, which might be unsafe. For more info, check the
-    // [`std::panic::UnwindSafe`] trait.
-    let did_panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        closure(buf);
-    })).is_err();
+#[cfg(fuzzing)]
+fn run_input<F>(closure: F, input: &[u8]) where F: FnOnce(&[u8]) {
+    start_input(input);
+    closure(input);
+    end_input();
+}
 
-    if did_panic {
-        // hopefully the custom panic hook will be called before and abort the
-        // process before the stack frames are unwinded.
-        std::process::abort();
-    }
+// The harness starts to run over `input`, until `end_input`: `fuzz!` does it around its closure, the
+// loops of `fetch_input` between it and `iteration_done`.
+#[cfg(fuzzing)]
+fn start_input(input: &[u8]) {
+    use core::sync::atomic::Ordering;
+
+    #[cfg(feature = "std")]
+    INPUT_ALLOCATED.store(0, Ordering::Relaxed);
+    INPUT_LEN.store(input.len(), Ordering::Relaxed);
+    INPUT_PTR.store(input.as_ptr() as *mut u8, Ordering::Relaxed);
+    RUNNING_INPUT.store(true, Ordering::Relaxed);
+    rng::start_input();
+    #[cfg(feature = "std")]
+    watchdog::start();
 }
 
-#[cfg(all(fuzzing, fuzzing_debug))]
-pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
-    use std::env;
-    use std::fs::File;
-    use mapr::MmapOptions;
-    
-    let filename = env::var("CARGO_HONGGFUZZ_CRASH_FILENAME").unwrap_or_else(|_|{
-        eprintln!("error: Environment variable CARGO_HONGGFUZZ_CRASH_FILENAME not set. Try launching with \"cargo hfuzz run-debug TARGET CRASH_FILENAME [ ARGS ... ]\"");
-        std::process::exit(1);
-    });
+#[cfg(fuzzing)]
+fn end_input() {
+    use core::sync::atomic::Ordering;
 
-    let file = File::open(&filename).unwrap_or_else(|_|{
-        eprintln!("error: failed to open \"{}\"", &filename);
-        std::process::exit(1);
-    });
+    #[cfg(feature = "std")]
+    watchdog::end();
+    INPUT_PTR.store(core::ptr::null_mut(), Ordering::Relaxed);
+    RUNNING_INPUT.store(false, Ordering::Relaxed);
+}
 
-    let mmap = unsafe {MmapOptions::new().map(&file)}.unwrap_or_else(|_|{
-        eprintln!("error: failed to mmap file \"{}\"", &filename);
-        std::process::exit(1);
-    });
+// The watchdog of `set_iteration_timeout`: a thread looking at when the running input started, which
+// aborts the process when it has been running for too long. The harness only stores the start of the
+// inputs, the watchdog doing the rest.
+#[cfg(all(fuzzing, feature = "std"))]
+mod watchdog {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    #[cfg(fuzzing_debug)]
+    use std::sync::Mutex;
+    use std::sync::Once;
+    use std::time::{Duration, Instant};
 
-    closure(&mmap);
+    static TIMEOUT_NANOS: AtomicU64 = AtomicU64::new(0);
+    // nanoseconds since `EPOCH` at which the running input started plus one, 0 when none is
+    static STARTED: AtomicU64 = AtomicU64::new(0);
+    static SPAWN: Once = Once::new();
+    // the file replayed by the debug builds, for the timeout to tell which one hangs
+    #[cfg(fuzzing_debug)]
+    static INPUT_NAME: Mutex<String> = Mutex::new(String::new());
 
-    eprintln!("This crashfile didn't trigger any panics...");
-    eprintln!("Are you sure that you selected the correct crashfile and that your program's behavior is entirely deterministic and only dependent on the fuzzing input?");
-    std::process::exit(2);
+    lazy_static::lazy_static! {
+        static ref EPOCH: Instant = Instant::now();
+    }
+
+    fn now() -> u64 {
+        EPOCH.elapsed().as_nanos() as u64 + 1
+    }
+
+    pub fn set_timeout(timeout: Duration) {
+        TIMEOUT_NANOS.store(timeout.as_nanos().clamp(1, u64::MAX as u128) as u64, Ordering::Relaxed);
+        lazy_static::initialize(&EPOCH);
+        SPAWN.call_once(|| {
+            std::thread::spawn(watch);
+        });
+    }
+
+    #[cfg(fuzzing_debug)]
+    pub fn name_input(name: &str) {
+        *INPUT_NAME.lock().unwrap_or_else(|e| e.into_inner()) = name.to_string();
+    }
+
+    #[cfg(fuzzing_debug)]
+    fn input() -> String {
+        format!("\"{}\"", INPUT_NAME.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    #[cfg(not(fuzzing_debug))]
+    fn input() -> String {
+        "the input".to_string()
+    }
+
+    pub fn start() {
+        if TIMEOUT_NANOS.load(Ordering::Relaxed) != 0 {
+            STARTED.store(now(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn end() {
+        STARTED.store(0, Ordering::Relaxed);
+    }
+
+    fn watch() {
+        loop {
+            let timeout = TIMEOUT_NANOS.load(Ordering::Relaxed);
+            // a tenth of the timeout between the checks, the abort coming at most 10% late
+            std::thread::sleep(Duration::from_nanos(timeout / 10).clamp(Duration::from_millis(1), Duration::from_millis(100)));
+            let started = STARTED.load(Ordering::Relaxed);
+            if started == 0 || now().saturating_sub(started) <= timeout {
+                continue;
+            }
+            // "cargo hfuzz triage" and the like tell the hangs by this line
+            eprintln!("error: timeout, {} ran for more than {:?}", input(), Duration::from_nanos(timeout));
+            std::process::abort();
+        }
+    }
 }
 
-/// Fuzz a closure-like block of code by passing it an object of arbitrary type.
-///
-/// You can choose the type of the argument using the syntax as in the example below.
-/// Please check out the `arbitrary` crate to see which types are available.
+/// Abort the process when a single input runs for longer than `timeout`.
 ///
-/// For performance reasons, it is recommended that you use the native type `&[u8]` when possible.
+/// Honggfuzz has its own timeout (`-t`, `cargo hfuzz run --timeout`), which reports the inputs
+/// running for too long as hangs, but only once they exceed it and not in every mode: this one, in
+/// the harness, makes such an input crash the process with a `SIGABRT`, after an
+/// `error: timeout, ...` line telling it apart from the other crashes. The replays of
+/// `cargo hfuzz run-debug`, `triage` and `verify` report these crashes as hangs.
 ///
-/// For perstistent fuzzing to work, you have to call it ad vita aeternam in an infinite loop.
+/// It applies to the inputs given to [`fuzz!`] from then on, and can be changed at any time.
+/// Without `cargo hfuzz`, it does nothing.
 ///
 /// ```rust,should_panic
 /// # use honggfuzz::fuzz;
 /// # fn main() {
+/// honggfuzz::set_iteration_timeout(std::time::Duration::from_millis(500));
 /// loop {
 ///     fuzz!(|data: &[u8]| {
-///         if data.len() != 3 {return}
-///         if data[0] != b'h' {return}
-///         if data[1] != b'e' {return}
-///         if data[2] != b'y' {return}
-///         panic!("BOOM")
+///         if data == b"hey" {
+///             panic!("BOOM")
+///         }
 ///     });
 /// }
 /// # }
 /// ```
+#[cfg(all(not(fuzzing), feature = "std"))]
+#[allow(unused_variables)]
+pub fn set_iteration_timeout(timeout: std::time::Duration) {}
 
+#[cfg(all(fuzzing, feature = "std"))]
+pub fn set_iteration_timeout(timeout: std::time::Duration) {
+    watchdog::set_timeout(timeout);
+}
+
+// The bytes allocated by the running input and not freed yet, for `LimitedAllocator`. Freeing what was
+// allocated before the input makes it negative.
+#[cfg(all(fuzzing, feature = "std"))]
+static INPUT_ALLOCATED: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+#[cfg(all(fuzzing, feature = "std"))]
+static REPORTING_OOM: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// A global allocator aborting the process when a single input allocates more than a limit, like the
+/// `-malloc_limit_mb` of libFuzzer.
+///
+/// An input making the target allocate without end would otherwise exhaust the memory of the machine
+/// instead of being reported: with this allocator, it crashes the process with a `SIGABRT` after an
+/// `error: out of memory, ...` line, and honggfuzz saves it like any other crash. What an input has
+/// allocated and not freed yet is counted, from its start on, the setup before the first input and the
+/// frees of what it allocated not counting. Without `cargo hfuzz`, it only forwards to the allocator
+/// it wraps, `System` by default.
+///
+/// The builds of `cargo hfuzz bench` have their own global allocator, so it has to be left out of
+/// `cfg(fuzzing_bench)`.
+///
+/// ```rust,should_panic
+/// # use honggfuzz::fuzz;
+/// #[cfg(not(fuzzing_bench))]
+/// #[global_allocator]
+/// static ALLOCATOR: honggfuzz::LimitedAllocator = honggfuzz::LimitedAllocator::new(2048);
+///
+/// # fn main() {
+/// loop {
+///     fuzz!(|data: &[u8]| {
+///         let _buffer = vec![0u8; data.len() * 1024];
+///     });
+/// }
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub struct LimitedAllocator<A = std::alloc::System> {
+    allocator: A,
+    limit: usize,
+}
+
+#[cfg(feature = "std")]
+impl LimitedAllocator {
+    /// Limits what an input allocates to `limit_mb` megabytes, from the system allocator.
+    pub const fn new(limit_mb: usize) -> Self {
+        LimitedAllocator::with_allocator(std::alloc::System, limit_mb)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A> LimitedAllocator<A> {
+    /// Limits what an input allocates from `allocator` to `limit_mb` megabytes.
+    pub const fn with_allocator(allocator: A, limit_mb: usize) -> Self {
+        LimitedAllocator { allocator, limit: limit_mb.saturating_mul(1 << 20) }
+    }
+
+    #[cfg(fuzzing)]
+    fn allocating(&self, size: usize) {
+        use std::sync::atomic::Ordering;
+
+        let allocated = INPUT_ALLOCATED.fetch_add(size as isize, Ordering::Relaxed).saturating_add(size as isize);
+        // the report coming back here, if it allocates, doesn't report again
+        if RUNNING_INPUT.load(Ordering::Relaxed) && allocated > self.limit as isize && !REPORTING_OOM.swap(true, Ordering::Relaxed) {
+            eprintln!("error: out of memory, the input allocated {} bytes, more than the {} MB of honggfuzz::LimitedAllocator ({} bytes requested)",
+                allocated, self.limit >> 20, size);
+            std::process::abort();
+        }
+    }
+
+    #[cfg(fuzzing)]
+    fn freeing(&self, size: usize) {
+        INPUT_ALLOCATED.fetch_sub(size as isize, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    #[cfg(not(fuzzing))]
+    fn allocating(&self, _size: usize) {
+        let _ = self.limit;
+    }
+
+    #[cfg(not(fuzzing))]
+    fn freeing(&self, _size: usize) {}
+}
+
+#[cfg(feature = "std")]
+unsafe impl<A: std::alloc::GlobalAlloc> std::alloc::GlobalAlloc for LimitedAllocator<A> {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.allocating(layout.size());
+        self.allocator.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.allocating(layout.size());
+        self.allocator.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        self.freeing(layout.size());
+        self.allocator.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        match new_size >= layout.size() {
+            true => self.allocating(new_size - layout.size()),
+            false => self.freeing(layout.size() - new_size),
+        }
+        self.allocator.realloc(ptr, layout, new_size)
+    }
+}
+
+// The inputs which "cargo hfuzz run" sorts out of the corpus after a session: honggfuzz keeps the
+// rejected inputs which found new coverage, so the target replays the ones it saved, listed in this
+// file, and removes those its closure rejects.
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench), feature = "std"))]
+lazy_static::lazy_static! {
+    static ref FILTERED_INPUTS: Option<std::sync::Mutex<Vec<std::path::PathBuf>>> = {
+        let list = std::env::var("CARGO_HONGGFUZZ_FILTER_INPUTS").ok()?;
+        let files = std::fs::read_to_string(&list).unwrap_or_else(|_| {
+            eprintln!("error: failed to read \"{}\"", list);
+            std::process::exit(1);
+        });
+        Some(std::sync::Mutex::new(files.lines().rev().map(std::path::PathBuf::from).collect()))
+    };
+}
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench), feature = "std"))]
+fn filter_next<F>(closure: F, inputs: &std::sync::Mutex<Vec<std::path::PathBuf>>) where F: FnOnce(&[u8]) {
+    use std::sync::atomic::Ordering;
+
+    let Some(file) = inputs.lock().unwrap_or_else(|e| e.into_inner()).pop() else { std::process::exit(0) };
+    if let Ok(input) = std::fs::read(&file) {
+        reject::REJECTED.store(false, Ordering::Relaxed);
+        // a crash is for honggfuzz to find, not for the sorting of the corpus to stop on
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_input(closure, &input[..input.len().min(*MAX_LEN)]);
+        }));
+        if reject::REJECTED.load(Ordering::Relaxed) {
+            let _ = std::fs::remove_file(&file);
+        }
+    }
+    if inputs.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+        std::process::exit(0);
+    }
+}
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench), feature = "std"))]
+pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
+    if let Some(inputs) = &*FILTERED_INPUTS {
+        return filter_next(closure, inputs);
+    }
+
+    // sets panic hook if not already done
+    lazy_static::initialize(&PANIC_HOOK);
+
+    // get buffer from honggfuzz runtime
+    let buf = next_input();
+
+    // We still catch unwinding panics just in case the fuzzed code modifies
+    // the panic hook.
+    // If so, the fuzzer will be unable to tell different bugs appart and you will
+    // only be able to find one bug at a time before fixing it to then find a new one.
+    // The closure is assumed to be unwind-safe, which might be unsafe. For more info, check the
+    // [`std::panic::UnwindSafe`] trait.
+    let did_panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        run_input(closure, buf);
+    })).is_err();
+
+    if did_panic {
+        // hopefully the custom panic hook will be called before and abort the
+        // process before the stack frames are unwinded.
+        std::process::abort();
+    }
+}
+
+// Without std there is no panic hook nor unwinding to catch: the `#[panic_handler]` of the harness
+// has to abort, for honggfuzz to see the crash.
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench), not(feature = "std")))]
+pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
+    run_input(closure, next_input());
+}
+
+// The next input of the honggfuzz runtime, in the buffer of the process it maps once: the slice stays
+// valid, its bytes being those of the next input after the next call.
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+fn next_input() -> &'static [u8] {
+    use core::mem::MaybeUninit;
+
+    // without std, the environment giving the limit isn't read, honggfuzz having the same one
+    #[cfg(feature = "std")]
+    let max_len = *MAX_LEN;
+    #[cfg(not(feature = "std"))]
+    let max_len = usize::MAX;
+
+    let mut buf_ptr = MaybeUninit::<*const u8>::uninit();
+    let mut len_ptr = MaybeUninit::<usize>::uninit();
+
+    unsafe {
+        HF_ITER(buf_ptr.as_mut_ptr(), len_ptr.as_mut_ptr());
+        core::slice::from_raw_parts(buf_ptr.assume_init(), len_ptr.assume_init().min(max_len))
+    }
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+lazy_static::lazy_static! {
+    static ref CRASH_FILENAME: String = std::env::var("CARGO_HONGGFUZZ_CRASH_FILENAME").unwrap_or_else(|_|{
+        eprintln!("error: Environment variable CARGO_HONGGFUZZ_CRASH_FILENAME not set. Try launching with \"cargo hfuzz run-debug TARGET CRASH_FILENAME [ ARGS ... ]\"");
+        std::process::exit(1);
+    });
+
+    // set by "cargo hfuzz run-debug --timeout SECS", an input running for too long fails like a crash
+    static ref DEBUG_TIMEOUT: () = {
+        if let Some(timeout) = std::env::var("CARGO_HONGGFUZZ_TIMEOUT").ok().and_then(|t| t.parse().ok()) {
+            set_iteration_timeout(std::time::Duration::from_secs(timeout));
+        }
+    };
+
+    // the files of a directory given to "cargo hfuzz run-debug", replayed one per call
+    static ref REPLAY: std::sync::Mutex<Replay> = {
+        let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(&*CRASH_FILENAME).map(|entries| {
+            entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_file()).collect()
+        }).unwrap_or_default();
+        files.sort();
+        if files.is_empty() {
+            eprintln!("error: no crash file in \"{}\"", *CRASH_FILENAME);
+            std::process::exit(1);
+        }
+        std::sync::Mutex::new(Replay { files, next: 0, panicked: 0 })
+    };
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+struct Replay {
+    files: Vec<std::path::PathBuf>,
+    next: usize,
+    panicked: usize,
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+fn map_input(filename: &str) -> mapr::Mmap {
+    let file = std::fs::File::open(filename).unwrap_or_else(|_|{
+        eprintln!("error: failed to open \"{}\"", filename);
+        std::process::exit(1);
+    });
+
+    unsafe {mapr::MmapOptions::new().map(&file)}.unwrap_or_else(|_|{
+        eprintln!("error: failed to mmap file \"{}\"", filename);
+        std::process::exit(1);
+    })
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
+    lazy_static::initialize(&DEBUG_TIMEOUT);
+    if std::path::Path::new(&*CRASH_FILENAME).is_dir() {
+        return replay_next(closure);
+    }
+
+    let mmap = map_input(&CRASH_FILENAME);
+    watchdog::name_input(&CRASH_FILENAME);
+    run_input(closure, &mmap[..mmap.len().min(*MAX_LEN)]);
+    crash_not_reproduced();
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+fn crash_not_reproduced() -> ! {
+    eprintln!("This crashfile didn't trigger any panics...");
+    eprintln!("Are you sure that you selected the correct crashfile and that your program's behavior is entirely deterministic and only dependent on the fuzzing input?");
+    std::process::exit(2);
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+fn end_replay(replay: &Replay) -> ! {
+    eprintln!("{} of the {} files of \"{}\" triggered a panic", replay.panicked, replay.files.len(), *CRASH_FILENAME);
+    // like a single crash file, 2 when none of them triggered anything
+    std::process::exit(if replay.panicked == 0 { 2 } else { 101 });
+}
+
+// Runs the harness over the next file of the directory, its panic being caught for the triage to go
+// on with the others. Only the panics which unwind are survived, an abort or a stack overflow still
+// ends the process on the file which caused it.
+#[cfg(all(fuzzing, fuzzing_debug))]
+fn replay_next<F>(closure: F) where F: FnOnce(&[u8]) {
+    let replay = REPLAY.lock().unwrap_or_else(|e| e.into_inner());
+    let file = replay.files[replay.next].clone();
+    drop(replay);
+
+    // read rather than mapped, the empty inputs of a corpus can't be
+    let filename = file.to_string_lossy();
+    let input = std::fs::read(&file).unwrap_or_else(|_|{
+        eprintln!("error: failed to read \"{}\"", filename);
+        std::process::exit(1);
+    });
+    watchdog::name_input(&filename);
+    reject::REJECTED.store(false, std::sync::atomic::Ordering::Relaxed);
+    let did_panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        run_input(closure, &input[..input.len().min(*MAX_LEN)]);
+    })).is_err();
+    RUNNING_INPUT.store(false, std::sync::atomic::Ordering::Relaxed);
+    let result = match (did_panic, reject::REJECTED.load(std::sync::atomic::Ordering::Relaxed)) {
+        (true, _) => "panicked",
+        (false, true) => "rejected",
+        (false, false) => "ok",
+    };
+    eprintln!("{}: \"{}\"", result, filename);
+
+    let mut replay = REPLAY.lock().unwrap_or_else(|e| e.into_inner());
+    replay.panicked += did_panic as usize;
+    replay.next += 1;
+    if replay.next == replay.files.len() {
+        end_replay(&replay);
+    }
+}
+
+// Counts the allocations of the benchmarked target, "cargo hfuzz bench" reporting how many each
+// input needs. A target with its own global allocator has to leave it out of `cfg(fuzzing_bench)`.
+#[cfg(all(fuzzing, fuzzing_bench))]
+mod bench {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    pub static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    pub static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    // Latencies in nanoseconds, in buckets of 1/16th of a power of two (6% wide at most), so that
+    // the percentiles don't need to keep every measure.
+    pub struct Histogram {
+        buckets: Vec<u64>,
+        pub count: u64,
+        pub max: u64,
+    }
+
+    impl Histogram {
+        pub fn new() -> Histogram {
+            Histogram { buckets: vec![0; 64 * 16], count: 0, max: 0 }
+        }
+
+        fn bucket(nanos: u64) -> usize {
+            if nanos < 16 {
+                return nanos as usize;
+            }
+            let magnitude = 63 - nanos.leading_zeros() as usize;
+            magnitude * 16 + ((nanos >> (magnitude - 4)) & 15) as usize
+        }
+
+        fn lowest(bucket: usize) -> u64 {
+            let (magnitude, fraction) = (bucket / 16, bucket as u64 % 16);
+            if magnitude < 4 {
+                return bucket as u64;
+            }
+            (16 + fraction) << (magnitude - 4)
+        }
+
+        pub fn record(&mut self, latency: Duration) {
+            let nanos = latency.as_nanos().min(u64::MAX as u128) as u64;
+            self.buckets[Histogram::bucket(nanos)] += 1;
+            self.count += 1;
+            self.max = self.max.max(nanos);
+        }
+
+        pub fn percentile(&self, percentile: f64) -> u64 {
+            let rank = ((self.count as f64 * percentile / 100.0).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (bucket, count) in self.buckets.iter().enumerate() {
+                seen += count;
+                if seen >= rank {
+                    return Histogram::lowest(bucket);
+                }
+            }
+            self.max
+        }
+    }
+}
+
+// The state of "cargo hfuzz bench": the inputs of the corpus, loaded once, are given in turn to the
+// harness until the time is up, its report being written then.
+#[cfg(all(fuzzing, fuzzing_bench))]
+struct Bench {
+    inputs: Vec<Vec<u8>>,
+    next: usize,
+    latencies: bench::Histogram,
+    allocations: u64,
+    allocated_bytes: u64,
+    started: std::time::Instant,
+    duration: std::time::Duration,
+    // the input of `fetch_input` being run: when it started, and the allocations before it
+    fetched: Option<(std::time::Instant, u64, u64)>,
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+impl Bench {
+    fn record(&mut self, latency: std::time::Duration, allocations: u64, allocated_bytes: u64) {
+        self.latencies.record(latency);
+        self.allocations += allocations;
+        self.allocated_bytes += allocated_bytes;
+    }
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+lazy_static::lazy_static! {
+    static ref BENCH: std::sync::Mutex<Bench> = {
+        let variable = |name: &str| std::env::var(name).unwrap_or_else(|_| {
+            eprintln!("error: Environment variable {} not set. Try launching with \"cargo hfuzz bench TARGET\"", name);
+            std::process::exit(1);
+        });
+        let dir = variable("CARGO_HONGGFUZZ_BENCH_INPUTS");
+        let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(&dir).map(|entries| {
+            entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_file()).collect()
+        }).unwrap_or_default();
+        files.sort();
+        let inputs: Vec<Vec<u8>> = files.iter().filter_map(|f| std::fs::read(f).ok()).collect();
+        if inputs.is_empty() {
+            eprintln!("error: no input to benchmark with in \"{}\"", dir);
+            std::process::exit(1);
+        }
+        let seconds = variable("CARGO_HONGGFUZZ_BENCH_TIME").parse().unwrap_or(10);
+        std::sync::Mutex::new(Bench {
+            inputs,
+            next: 0,
+            latencies: bench::Histogram::new(),
+            allocations: 0,
+            allocated_bytes: 0,
+            started: std::time::Instant::now(),
+            duration: std::time::Duration::from_secs(seconds),
+            fetched: None,
+        })
+    };
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+fn write_bench_report(bench: &Bench) {
+    let elapsed = bench.started.elapsed();
+    let latencies = &bench.latencies;
+    let report = format!(
+        "inputs={}\nexecs={}\nelapsed_ns={}\np50_ns={}\np90_ns={}\np99_ns={}\nmax_ns={}\nallocations={}\nallocated_bytes={}\n",
+        bench.inputs.len(), latencies.count, elapsed.as_nanos(),
+        latencies.percentile(50.0), latencies.percentile(90.0), latencies.percentile(99.0), latencies.max,
+        bench.allocations, bench.allocated_bytes,
+    );
+    match std::env::var("CARGO_HONGGFUZZ_BENCH_REPORT") {
+        Ok(file) => std::fs::write(&file, report).unwrap_or_else(|_| {
+            eprintln!("error: failed to write \"{}\"", file);
+            std::process::exit(1);
+        }),
+        Err(_) => print!("{}", report),
+    }
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+pub fn fuzz<F>(closure: F) where F: FnOnce(&[u8]) {
+    use std::sync::atomic::Ordering;
+
+    let mut bench = BENCH.lock().unwrap_or_else(|e| e.into_inner());
+    if bench.started.elapsed() >= bench.duration {
+        write_bench_report(&bench);
+        std::process::exit(0);
+    }
+
+    // the input is taken out for the time of the run, the harness may well call back into us
+    let index = bench.next;
+    bench.next = (index + 1) % bench.inputs.len();
+    let input = std::mem::take(&mut bench.inputs[index]);
+    drop(bench);
+
+    let allocations = bench::ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = bench::ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = std::time::Instant::now();
+    run_input(closure, &input[..input.len().min(*MAX_LEN)]);
+    let latency = start.elapsed();
+    let allocations = bench::ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let allocated_bytes = bench::ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes;
+
+    let mut bench = BENCH.lock().unwrap_or_else(|e| e.into_inner());
+    bench.inputs[index] = input;
+    bench.record(latency, allocations, allocated_bytes);
+}
+
+/// Take the next input, for a harness running its own loop instead of the closure of [`fuzz!`].
+///
+/// The input runs until [`iteration_done`], or until the next call, which ends it first: batching
+/// the inputs, or fitting the fuzzing into the event loop of the target, is then up to the harness.
+/// [`rng`], [`fuzz_assert!`] and [`set_iteration_timeout`] apply to it as to the closure of `fuzz!`.
+///
+/// The bytes of the slice are those of the next input after the next call, copy them to keep them
+/// longer. In the builds of run-debug a panic ends the replay, the loop of the harness not catching
+/// it, and the rejected inputs of a [`Corpus`] are only sorted out with the closures of `fuzz!`.
+///
+/// ```rust,should_panic
+/// # fn main() {
+/// loop {
+///     let data = honggfuzz::fetch_input();
+///     if data == b"hey" {
+///         panic!("BOOM")
+///     }
+///     honggfuzz::iteration_done();
+/// }
+/// # }
+/// ```
+#[cfg(not(fuzzing))]
+pub fn fetch_input() -> &'static [u8] {
+    not_built_with_cargo_hfuzz();
+}
+
+/// Tell that the harness is done with the input of [`fetch_input`].
+///
+/// For honggfuzz the input ends at the next call of `fetch_input`, which calls this function first:
+/// calling it right after the input, before the work of the harness between two inputs, keeps that
+/// work out of what the timeouts and the allocations count for the input.
+#[cfg(not(fuzzing))]
+pub fn iteration_done() {}
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+pub fn fetch_input() -> &'static [u8] {
+    iteration_done();
+    #[cfg(feature = "std")]
+    {
+        // the loops of the harness reject no input, there is nothing to sort out of the corpus
+        if FILTERED_INPUTS.is_some() {
+            std::process::exit(0);
+        }
+        lazy_static::initialize(&PANIC_HOOK);
+    }
+
+    let input = next_input();
+    start_input(input);
+    input
+}
+
+#[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+pub fn iteration_done() {
+    end_input();
+}
+
+// How many inputs `fetch_input` gave in the builds of run-debug, the files of a directory being
+// replayed in turn.
+#[cfg(all(fuzzing, fuzzing_debug))]
+static FETCHED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+pub fn fetch_input() -> &'static [u8] {
+    use std::sync::atomic::Ordering;
+
+    iteration_done();
+    lazy_static::initialize(&DEBUG_TIMEOUT);
+    let fetched = FETCHED.fetch_add(1, Ordering::Relaxed);
+    let file = match std::path::Path::new(&*CRASH_FILENAME).is_dir() {
+        true => {
+            static REPORT_PANIC: std::sync::Once = std::sync::Once::new();
+            REPORT_PANIC.call_once(|| {
+                let hook = std::panic::take_hook();
+                std::panic::set_hook(Box::new(move |info| {
+                    hook(info);
+                    if let (true, Some(file)) = (RUNNING_INPUT.load(Ordering::Relaxed), fetched_file()) {
+                        eprintln!("panicked: \"{}\", the files after it are not replayed", file);
+                    }
+                }));
+            });
+            let replay = REPLAY.lock().unwrap_or_else(|e| e.into_inner());
+            match replay.files.get(fetched) {
+                Some(file) => file.clone(),
+                None => end_replay(&replay),
+            }
+        }
+        false if fetched == 0 => std::path::PathBuf::from(&*CRASH_FILENAME),
+        false => crash_not_reproduced(),
+    };
+
+    let filename = file.to_string_lossy();
+    let input = std::fs::read(&file).unwrap_or_else(|_|{
+        eprintln!("error: failed to read \"{}\"", filename);
+        std::process::exit(1);
+    });
+    watchdog::name_input(&filename);
+    // kept until the process ends, the replays being of a few files
+    let input: &'static [u8] = input.leak();
+    let input = &input[..input.len().min(*MAX_LEN)];
+    start_input(input);
+    input
+}
+
+// the file of the directory replayed which the running input comes from
+#[cfg(all(fuzzing, fuzzing_debug))]
+fn fetched_file() -> Option<String> {
+    let fetched = FETCHED.load(std::sync::atomic::Ordering::Relaxed);
+    let replay = REPLAY.try_lock().ok()?;
+    Some(replay.files.get(fetched.checked_sub(1)?)?.to_string_lossy().into_owned())
+}
+
+#[cfg(all(fuzzing, fuzzing_debug))]
+pub fn iteration_done() {
+    if !RUNNING_INPUT.load(std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+    end_input();
+    if std::path::Path::new(&*CRASH_FILENAME).is_dir() {
+        if let Some(file) = fetched_file() {
+            eprintln!("ok: \"{}\"", file);
+        }
+    }
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+pub fn fetch_input() -> &'static [u8] {
+    use std::sync::atomic::Ordering;
+
+    iteration_done();
+    let mut bench = BENCH.lock().unwrap_or_else(|e| e.into_inner());
+    if bench.started.elapsed() >= bench.duration {
+        write_bench_report(&bench);
+        std::process::exit(0);
+    }
+
+    let index = bench.next;
+    bench.next = (index + 1) % bench.inputs.len();
+    // the inputs are loaded once and their buffers never freed, so they outlive the loop
+    let input = unsafe { std::slice::from_raw_parts(bench.inputs[index].as_ptr(), bench.inputs[index].len()) };
+    let input = &input[..input.len().min(*MAX_LEN)];
+    let allocations = bench::ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = bench::ALLOCATED_BYTES.load(Ordering::Relaxed);
+    bench.fetched = Some((std::time::Instant::now(), allocations, allocated_bytes));
+    drop(bench);
+    start_input(input);
+    input
+}
+
+#[cfg(all(fuzzing, fuzzing_bench))]
+pub fn iteration_done() {
+    use std::sync::atomic::Ordering;
+
+    let mut bench = BENCH.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((start, allocations, allocated_bytes)) = bench.fetched.take() else { return };
+    let latency = start.elapsed();
+    end_input();
+    let allocations = bench::ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let allocated_bytes = bench::ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes;
+    bench.record(latency, allocations, allocated_bytes);
+}
+
+/// Fuzz a closure-like block of code by passing it an object of arbitrary type.
+///
+/// You can choose the type of the argument using the syntax as in the example below.
+/// Please check out the `arbitrary` crate to see which types are available.
+///
+/// For performance reasons, it is recommended that you use the native type `&[u8]` when possible.
+///
+/// For perstistent fuzzing to work, you have to call it ad vita aeternam in an infinite loop.
+///
+/// ```rust,should_panic
+/// # use honggfuzz::fuzz;
+/// # fn main() {
+/// loop {
+///     fuzz!(|data: &[u8]| {
+///         if data.len() != 3 {return}
+///         if data[0] != b'h' {return}
+///         if data[1] != b'e' {return}
+///         if data[2] != b'y' {return}
+///         panic!("BOOM")
+///     });
+/// }
+/// # }
+/// ```
+///
+/// Any type implementing `Arbitrary` can be fuzzed, a struct of your own with `#[derive(Arbitrary)]`
+/// (the `derive` feature of the `arbitrary` crate) too. The value is built from the whole input with
+/// `Arbitrary::arbitrary_take_rest`, and the inputs which fail to decode are skipped.
+///
+/// The closure may return a [`Corpus`], or a `Result<(), E>` like `|data| -> Result<(), Error> { ... }`
+/// whose `Err` rejects the input: the rejected inputs don't stay in the corpus.
+///
+/// Several arguments, like `|a: u32, b: &[u8]|`, are decoded from the input as a tuple of their
+/// types, the same bytes always giving the same arguments.
+///
+/// ```rust,should_panic
+/// # use honggfuzz::fuzz;
+/// # fn main() {
+/// loop {
+///     fuzz!(|data: (bool, i32, Vec<u8>)| {
+///         if !data.0 {return}
+///         if data.1 < 0 {return}
+///         if data.2 != b"hey" {return}
+///         panic!("BOOM")
+///     });
+/// }
+/// # }
+/// ```
+///
+/// ```rust,should_panic
+/// # use honggfuzz::fuzz;
+/// # fn compare(a: &[u8], b: &[u8]) -> bool { a == b }
+/// # fn main() {
+/// loop {
+///     fuzz!(|a: &[u8], b: &[u8], strict: bool| {
+///         if compare(a, b) && strict && a != b {
+///             panic!("BOOM")
+///         }
+///     });
+/// }
+/// # }
+/// ```
 #[macro_export]
 macro_rules! fuzz {
+    // `&[u8]` spelled out, a type forwarded as `$dty:ty` no longer matching the arm of the raw input
+    (|$buf:ident: &[u8]| -> $rty:ty $body:block) => {
+        $crate::fuzz!(|$buf: &[u8]| {
+            $crate::reject::report((move || -> $rty { $body })().into());
+        });
+    };
+    (|$buf:ident $(: $dty:ty)?| -> $rty:ty $body:block) => {
+        $crate::fuzz!(|$buf $(: $dty)?| {
+            $crate::reject::report((move || -> $rty { $body })().into());
+        });
+    };
     (|$buf:ident| $body:block) => {
         $crate::fuzz(|$buf| $body);
     };
@@ -357,10 +2053,13 @@ macro_rules! fuzz {
             let $buf: $dty = {
                 use $crate::arbitrary::{Arbitrary, Unstructured};
 
-                let mut buf = Unstructured::new($buf); 
-                if let Ok(buf) = Arbitrary::arbitrary(&mut buf) {
+                // the whole input makes the value, like with cargo-fuzz, so that the last field of a
+                // struct or a top-level `Vec` gets the rest of the bytes
+                let buf = Unstructured::new($buf);
+                if let Ok(buf) = Arbitrary::arbitrary_take_rest(buf) {
                     buf
                 } else {
+                    // the inputs which don't decode are skipped
                     return
                 }
             };
@@ -368,5 +2067,663 @@ macro_rules! fuzz {
             $body
         });
     };
+    (|$($buf:ident: $dty:ty),+ $(,)?| $body:block) => {
+        // the arguments are decoded as a tuple, the last one getting the rest of the input
+        $crate::fuzz!(|input: ($($dty,)+)| {
+            let ($($buf,)+) = input;
+            $body
+        });
+    };
+}
+
+
+/// Generate a value of a proptest `Strategy` from the fuzzing input, to fuzz with the generators of
+/// a proptest suite.
+///
+/// The bytes of the input are the random numbers of the strategy (proptest's `PassThrough` RNG), so
+/// that a mutation of the input makes a close value and the coverage guides the generation. The bytes
+/// missing at the end of the input read as zeros, the values proptest shrinks towards. It gives `None`
+/// when the strategy rejects the value, like with a `prop_filter`.
+///
+/// The expansion uses the `proptest` crate of the harness, which needs it in its dependencies.
+///
+/// ```rust,ignore
+/// use honggfuzz::{fuzz, proptest_value};
+/// use proptest::prelude::*;
+///
+/// fn operations() -> impl Strategy<Value = Vec<(u8, String)>> {
+///     prop::collection::vec((0u8..4, "[a-z]{1,8}"), 0..32)
+/// }
+///
+/// fn main() {
+///     loop {
+///         fuzz!(|data: &[u8]| {
+///             let Some(operations) = proptest_value!(operations(), data) else { return };
+///             run(operations);
+///         });
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! proptest_value {
+    ($strategy:expr, $data:expr) => {{
+        use ::proptest::strategy::{Strategy, ValueTree};
+        use ::proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+        // the zeros after the input keep `PassThrough` from running out of bytes
+        let data: &[u8] = $data;
+        let mut seed = ::std::vec::Vec::with_capacity(data.len() + $crate::PROPTEST_PADDING);
+        seed.extend_from_slice(data);
+        seed.resize(data.len() + $crate::PROPTEST_PADDING, 0);
+        let mut runner = TestRunner::new_with_rng(Config::default(), TestRng::from_seed(RngAlgorithm::PassThrough, &seed));
+        match Strategy::new_tree(&$strategy, &mut runner) {
+            ::std::result::Result::Ok(tree) => ::std::option::Option::Some(ValueTree::current(&tree)),
+            ::std::result::Result::Err(_) => ::std::option::Option::None,
+        }
+    }};
+}
+
+// the zeros after the input given to proptest
+#[doc(hidden)]
+pub const PROPTEST_PADDING: usize = 1 << 16;
+
+/// Install a logger of the `log` or the `tracing` crate dropping the logs when fuzzing, see [`logs`].
+///
+/// In the fuzzing builds the logs are dropped before being formatted, and in the builds of run-debug
+/// they are all printed to stderr. [`logs::set_enabled`] switches between the two at any time. Out of
+/// the fuzzing builds it does nothing.
+///
+/// The expansion uses the `log` or the `tracing` crate of the harness, the one named, which needs it
+/// in its dependencies. Call it in `main` before the loop of `fuzz!`, before the target installs a
+/// logger of its own.
+///
+/// ```rust,ignore
+/// fn main() {
+///     honggfuzz::fuzz_logger!(log);
+///     loop {
+///         fuzz!(|data: &[u8]| {
+///             log::debug!("parsing {} bytes", data.len());
+///             parse(data);
+///         });
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! fuzz_logger {
+    (log) => {{
+        struct FuzzLogger;
+
+        impl ::log::Log for FuzzLogger {
+            fn enabled(&self, _: &::log::Metadata) -> bool {
+                $crate::logs::enabled()
+            }
+
+            fn log(&self, record: &::log::Record) {
+                if $crate::logs::enabled() {
+                    $crate::logs::write(record.level().as_str(), record.target(), *record.args());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: FuzzLogger = FuzzLogger;
+        // the disabled logs stop at the level check of the macros of `log`
+        let toggle: fn(bool) = |enabled| ::log::set_max_level(if enabled { ::log::LevelFilter::Trace } else { ::log::LevelFilter::Off });
+        if $crate::logs::install(toggle) && ::log::set_logger(&LOGGER).is_err() {
+            ::std::eprintln!("warning: fuzz_logger!(log) is called after a logger was installed, the logs are not dropped");
+        }
+    }};
+    (tracing) => {{
+        struct FuzzSubscriber;
+
+        impl ::tracing::Subscriber for FuzzSubscriber {
+            fn register_callsite(&self, _: &'static ::tracing::Metadata<'static>) -> ::tracing::subscriber::Interest {
+                // asking again at every call, the logs being enabled or disabled at any time
+                ::tracing::subscriber::Interest::sometimes()
+            }
+
+            fn enabled(&self, _: &::tracing::Metadata) -> bool {
+                $crate::logs::enabled()
+            }
+
+            fn new_span(&self, _: &::tracing::span::Attributes) -> ::tracing::span::Id {
+                ::tracing::span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &::tracing::span::Id, _: &::tracing::span::Record) {}
+
+            fn record_follows_from(&self, _: &::tracing::span::Id, _: &::tracing::span::Id) {}
+
+            fn event(&self, event: &::tracing::Event) {
+                struct Fields(::std::string::String);
+
+                impl ::tracing::field::Visit for Fields {
+                    fn record_debug(&mut self, field: &::tracing::field::Field, value: &dyn ::std::fmt::Debug) {
+                        use ::std::fmt::Write;
+                        let _ = match field.name() {
+                            "message" => ::std::write!(self.0, " {:?}", value),
+                            name => ::std::write!(self.0, " {}={:?}", name, value),
+                        };
+                    }
+                }
+
+                let mut fields = Fields(::std::string::String::new());
+                event.record(&mut fields);
+                let metadata = event.metadata();
+                $crate::logs::write(metadata.level().as_str(), metadata.target(), ::std::format_args!("{}", fields.0.trim_start()));
+            }
+
+            fn enter(&self, _: &::tracing::span::Id) {}
+
+            fn exit(&self, _: &::tracing::span::Id) {}
+        }
+
+        // `enabled` is called for every event, nothing to update
+        let toggle: fn(bool) = |_| {};
+        if $crate::logs::install(toggle) && ::tracing::subscriber::set_global_default(FuzzSubscriber).is_err() {
+            ::std::eprintln!("warning: fuzz_logger!(tracing) is called after a subscriber was installed, the logs are not dropped");
+        }
+    }};
+}
+
+/// Mutate the inputs with a function of your own, for structure-aware fuzzing.
+///
+/// With `cargo hfuzz run --custom-mutator TARGET`, honggfuzz gives every input it mutated to the target
+/// run with `CARGO_HONGGFUZZ_MUTATE` set (honggfuzz's `--pprocess_cmd`), and this function replaces it
+/// with what the closure returns, then exits. Otherwise it returns right away, so call it first in `main`.
+///
+/// The closure receives the input, the longest input it may return (the `--max-len` of
+/// `cargo hfuzz run`, 1 MiB without one, longer ones being truncated) and a random seed.
+///
+/// ```rust
+/// # fn main() {
+/// honggfuzz::custom_mutator(|data, max_size, seed| {
+///     // e.g. decode the input, change one of its fields and encode it again
+///     let mut mutated = data.to_vec();
+///     if !mutated.is_empty() {
+///         let index = seed as usize % mutated.len();
+///         mutated[index] = mutated[index].wrapping_add(1);
+///     }
+///     mutated.truncate(max_size);
+///     mutated
+/// });
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn custom_mutator<F>(mutator: F) where F: FnOnce(&[u8], usize, u64) -> Vec<u8> {
+    use std::env;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    if env::var_os("CARGO_HONGGFUZZ_MUTATE").is_none() {
+        return;
+    }
+
+    // honggfuzz gives the path of a file holding the input, and reads it back once rewritten
+    let filename = env::args_os().nth(1).unwrap_or_else(|| {
+        eprintln!("error: no input file given to the custom mutator, it is run by \"cargo hfuzz run --custom-mutator TARGET\"");
+        std::process::exit(1);
+    });
+    let data = std::fs::read(&filename).unwrap_or_else(|_| {
+        eprintln!("error: failed to read \"{}\"", filename.to_string_lossy());
+        std::process::exit(1);
+    });
+    let max_size = env::var("CARGO_HONGGFUZZ_MAX_LEN").ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(1024 * 1024);
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64) ^ ((std::process::id() as u64) << 32);
+
+    let mut mutated = mutator(&data, max_size, seed);
+    mutated.truncate(max_size);
+    if std::fs::write(&filename, mutated).is_err() {
+        eprintln!("error: failed to write \"{}\"", filename.to_string_lossy());
+        std::process::exit(1);
+    }
+    std::process::exit(0);
+}
+
+// The tokens of `fuzz_dictionary!`, encoded at compile time in a static of the target, which
+// "cargo hfuzz run" finds in the binary and gives to honggfuzz before it starts.
+#[doc(hidden)]
+pub mod dictionary {
+    // followed by the number of tokens and by each of them, their length first, as 32 bits little
+    // endian numbers
+    pub const MAGIC: &[u8] = b"\xffhfuzz-dictionary\xff";
+
+    // `"text"` and `b"bytes"` tokens
+    pub struct Token<T>(pub T);
+
+    impl Token<&'static str> {
+        pub const fn bytes(self) -> &'static [u8] {
+            self.0.as_bytes()
+        }
+    }
+
+    impl<const N: usize> Token<&'static [u8; N]> {
+        pub const fn bytes(self) -> &'static [u8] {
+            self.0
+        }
+    }
+
+    impl Token<&'static [u8]> {
+        pub const fn bytes(self) -> &'static [u8] {
+            self.0
+        }
+    }
+
+    pub const fn encoded_len(tokens: &[&[u8]]) -> usize {
+        let mut len = MAGIC.len() + 4;
+        let mut i = 0;
+        while i < tokens.len() {
+            len += 4 + tokens[i].len();
+            i += 1;
+        }
+        len
+    }
+
+    pub const fn encode<const LEN: usize>(tokens: &[&[u8]]) -> [u8; LEN] {
+        let mut encoded = [0; LEN];
+        let mut at = 0;
+        while at < MAGIC.len() {
+            encoded[at] = MAGIC[at];
+            at += 1;
+        }
+        let count = (tokens.len() as u32).to_le_bytes();
+        let mut i = 0;
+        while i < 4 {
+            encoded[at] = count[i];
+            at += 1;
+            i += 1;
+        }
+        let mut token = 0;
+        while token < tokens.len() {
+            let len = (tokens[token].len() as u32).to_le_bytes();
+            let mut i = 0;
+            while i < 4 {
+                encoded[at] = len[i];
+                at += 1;
+                i += 1;
+            }
+            let mut i = 0;
+            while i < tokens[token].len() {
+                encoded[at] = tokens[token][i];
+                at += 1;
+                i += 1;
+            }
+            token += 1;
+        }
+        encoded
+    }
+}
+
+/// Embed the tokens of a dictionary in the target, so that it travels with the harness.
+///
+/// The tokens are strings or byte strings. They are compiled into the binary, and
+/// `cargo hfuzz run` gives them to honggfuzz when it starts (its `-w`), with the ones of `--dict`
+/// or of `run.dict` if any. Call it in `main`, before fuzzing, or in the `init:` of a `fuzz_target!`.
+///
+/// ```rust
+/// # fn main() {
+/// honggfuzz::fuzz_dictionary![b"\x89PNG", "Content-Length:", "chunked"];
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fuzz_dictionary {
+    ($($token:expr),* $(,)?) => {{
+        const TOKENS: &[&[u8]] = &[$($crate::dictionary::Token($token).bytes()),*];
+        static DICTIONARY: [u8; $crate::dictionary::encoded_len(TOKENS)] = $crate::dictionary::encode(TOKENS);
+        // a static which isn't read would be left out of the binary
+        ::core::hint::black_box(&DICTIONARY);
+    }};
+}
+
+/// Add an input to the corpus, like one the harness made interesting by fixing its checksum.
+///
+/// Honggfuzz reads the corpus when it starts: with `cargo hfuzz run`, the inputs are saved in the
+/// workspace of the target and join the corpus when the session ends, to be fuzzed by the next one
+/// (or after a restart of `--watch`). Otherwise, like when replaying a crash, it does nothing.
+///
+/// ```rust
+/// # fn main() {
+/// let data = b"PNG";
+/// let mut fixed = data.to_vec();
+/// fixed.insert(0, 0x89);
+/// honggfuzz::add_to_corpus(&fixed);
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn add_to_corpus(data: &[u8]) {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let Some(dir) = std::env::var_os("CARGO_HONGGFUZZ_ADDED_DIR").map(std::path::PathBuf::from) else { return };
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let name = format!("added-{:016x}", hasher.finish());
+    if dir.join(&name).exists() {
+        return;
+    }
+    // written under a hidden name first, cargo-hfuzz only taking complete files
+    let partial = dir.join(format!(".{}-{}", name, std::process::id()));
+    if std::fs::write(&partial, data).and_then(|_| std::fs::rename(&partial, dir.join(&name))).is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+}
+
+/// Assert that a condition of the harness holds, with the input breaking it in the report.
+///
+/// In the builds of `cargo hfuzz`, a failed assertion prints its message, the length of the input and
+/// a hex and ASCII dump of its first kilobyte, then aborts right away, without unwinding, honggfuzz
+/// saving the input as a crash. The debug builds of `cargo hfuzz run-debug` panic after the report, for the debugger to stop
+/// there and the replays of a directory to go on. Otherwise it is an `assert!`.
+///
+/// ```rust,should_panic
+/// # use honggfuzz::{fuzz, fuzz_assert};
+/// # fn main() {
+/// loop {
+///     fuzz!(|data: &[u8]| {
+///         let encoded = data.to_vec();
+///         fuzz_assert!(encoded.len() == data.len(), "the encoding lost {} bytes", data.len() - encoded.len());
+///     });
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fuzz_assert {
+    ($cond:expr $(,)?) => {
+        $crate::fuzz_assert!($cond, "assertion failed: {}", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::__fuzz_assert_failed(format_args!($($arg)+));
+        }
+    };
+}
+
+/// Assert that two values are equal, with the input making them differ in the report, like
+/// [`fuzz_assert!`].
+///
+/// ```rust,should_panic
+/// # use honggfuzz::{fuzz, fuzz_assert_eq};
+/// # fn main() {
+/// loop {
+///     fuzz!(|data: &[u8]| {
+///         let decoded = String::from_utf8_lossy(data).into_owned();
+///         fuzz_assert_eq!(decoded.len() >= data.len(), true, "decoding shrank the input");
+///     });
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fuzz_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::__fuzz_assert_failed(format_args!("assertion `left == right` failed\n  left: {:?}\n right: {:?}", left, right));
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::__fuzz_assert_failed(format_args!("assertion `left == right` failed: {}\n  left: {:?}\n right: {:?}", format_args!($($arg)+), left, right));
+            }
+        }
+    };
+}
+
+// the bytes of the input shown by a crash report, the rest being left out
+#[cfg(all(fuzzing, feature = "std"))]
+const INPUT_DUMP_LEN: usize = 1024;
+
+// Prints the length of the input and a hex and ASCII dump of its first bytes, for the report of a
+// crash to show it.
+#[cfg(all(fuzzing, feature = "std"))]
+fn dump_input(input: &[u8]) {
+    use std::fmt::Write;
+
+    let mut dump = format!("input ({} bytes):\n", input.len());
+    for (line, bytes) in input[..input.len().min(INPUT_DUMP_LEN)].chunks(16).enumerate() {
+        let hex: String = bytes.iter().map(|b| format!("{:02x} ", b)).collect();
+        let ascii: String = bytes.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+        let _ = writeln!(dump, "{:08x}  {:<48} |{}|", line * 16, hex, ascii);
+    }
+    if input.len() > INPUT_DUMP_LEN {
+        let _ = writeln!(dump, "... {} more bytes", input.len() - INPUT_DUMP_LEN);
+    }
+    // in one write, not mixed with what other threads print
+    eprint!("{}", dump);
+}
+
+#[cfg(all(fuzzing, feature = "std"))]
+#[doc(hidden)]
+#[track_caller]
+pub fn __fuzz_assert_failed(message: core::fmt::Arguments) -> ! {
+    // the panic of the debug builds prints the message, after the input
+    #[cfg(not(fuzzing_debug))]
+    eprintln!("{}", message);
+    with_running_input(|input| match input {
+        Some(input) => dump_input(input),
+        None => eprintln!("input: none, the assertion failed outside of fuzz!"),
+    });
+    #[cfg(fuzzing_debug)]
+    panic!("{}", message);
+    #[cfg(not(fuzzing_debug))]
+    std::process::abort();
+}
+
+// without std, the `#[panic_handler]` of the harness reports it
+#[cfg(not(all(fuzzing, feature = "std")))]
+#[doc(hidden)]
+#[track_caller]
+pub fn __fuzz_assert_failed(message: core::fmt::Arguments) -> ! {
+    panic!("{}", message);
+}
+
+/// Run a one-time setup once per process, before the first input.
+///
+/// Loading a model, warming a cache or installing a logging filter inside the closure of [`fuzz!`]
+/// would redo it for every input, and doing it lazily there would count its code in the coverage of
+/// the first input. The block given to `fuzz_init!` runs the first time the macro is reached and never
+/// again, so it can sit in the fuzzing loop, before `fuzz!`. In a [`fuzz_target!`] harness, give it to
+/// `init:` instead.
+///
+/// A setup first reached while an input is running, from the closure, warns about it.
+///
+/// ```rust,should_panic
+/// # use honggfuzz::{fuzz, fuzz_init};
+/// # fn main() {
+/// loop {
+///     fuzz_init!({
+///         std::env::set_var("RUST_LOG", "off");
+///     });
+///     fuzz!(|data: &[u8]| {
+///         if data == b"hey" {
+///             panic!("BOOM")
+///         }
+///     });
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fuzz_init {
+    ($init:block) => {{
+        static INIT: $crate::__Once = $crate::__Once::new();
+        INIT.call_once(|| {
+            $crate::__check_init(concat!(file!(), ":", line!()));
+            $init
+        });
+    }};
+}
+
+/// Fuzz a TCP server, which listens on the address given to the closure and is sent every input over
+/// a connection, like with the NetDriver of honggfuzz: see [`net`] for how.
+///
+/// ```rust,no_run
+/// # fn serve(_listener: std::net::TcpListener) {}
+/// fn main() {
+///     honggfuzz::fuzz_net!(|addr| {
+///         serve(std::net::TcpListener::bind(addr).unwrap());
+///     });
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! fuzz_net {
+    (|$addr:ident| $body:block) => {
+        $crate::net::fuzz_net(move |$addr| $body)
+    };
+    ($server:expr) => {
+        $crate::net::fuzz_net($server)
+    };
+}
+
+#[cfg(all(fuzzing, feature = "std"))]
+#[doc(hidden)]
+pub fn __check_init(location: &str) {
+    if RUNNING_INPUT.load(core::sync::atomic::Ordering::Relaxed) {
+        eprintln!("warning: the fuzz_init! of {} ran during an input, counting in its coverage: move it before fuzz!", location);
+    }
 }
 
+#[cfg(not(all(fuzzing, feature = "std")))]
+#[doc(hidden)]
+pub fn __check_init(_location: &str) {}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use std::sync::Once as __Once;
+
+// `std::sync::Once` without std, for `fuzz_init!` and the feedback signals: the threads reaching it
+// while it runs wait for it to end
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+pub struct __Once(core::sync::atomic::AtomicU8);
+
+#[cfg(not(feature = "std"))]
+impl __Once {
+    const NEW: u8 = 0;
+    const RUNNING: u8 = 1;
+    const DONE: u8 = 2;
+
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        __Once(core::sync::atomic::AtomicU8::new(Self::NEW))
+    }
+
+    pub fn call_once<F>(&self, f: F) where F: FnOnce() {
+        use core::sync::atomic::Ordering;
+
+        match self.0.compare_exchange(Self::NEW, Self::RUNNING, Ordering::Acquire, Ordering::Acquire) {
+            Ok(_) => {
+                f();
+                self.0.store(Self::DONE, Ordering::Release);
+            }
+            Err(_) => while self.0.load(Ordering::Acquire) != Self::DONE {
+                core::hint::spin_loop();
+            },
+        }
+    }
+}
+
+/// What a `fuzz_target!` closure may return, as with libfuzzer-sys.
+///
+/// The closure of [`fuzz!`] may return it too, or a `Result<(), E>` whose `Err` rejects the input.
+///
+/// Honggfuzz has no way to be told that an input is not interesting: it keeps a rejected input which
+/// found new coverage, but `cargo hfuzz run` replays the inputs saved during the session when it ends
+/// and removes the rejected ones from the corpus, so that it doesn't fill up with inputs failing an
+/// early validation. The coverage they found still guides the session which found them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corpus {
+    /// Keep the input in the corpus
+    Keep,
+    /// Don't keep the input in the corpus
+    Reject,
+}
+
+impl From<()> for Corpus {
+    fn from(_: ()) -> Self {
+        Corpus::Keep
+    }
+}
+
+impl<E> From<Result<(), E>> for Corpus {
+    fn from(result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => Corpus::Keep,
+            Err(_) => Corpus::Reject,
+        }
+    }
+}
+
+// What the closures returning a `Corpus` report their rejects with.
+#[doc(hidden)]
+pub mod reject {
+    use core::sync::atomic::AtomicBool;
+
+    // found in the targets by cargo-hfuzz, which sorts the rejected inputs out of their corpus then
+    pub static MARKER: [u8; 17] = *b"\xffhfuzz-rejecting\xff";
+
+    // set when the closure rejects the running input
+    pub static REJECTED: AtomicBool = AtomicBool::new(false);
+
+    pub fn report(corpus: crate::Corpus) {
+        // the targets without std don't sort their inputs, their binary is left without the marker
+        #[cfg(feature = "std")]
+        core::hint::black_box(&MARKER);
+        if corpus == crate::Corpus::Reject {
+            REJECTED.store(true, core::sync::atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+/// Define a fuzz target the way cargo-fuzz does, so that its harnesses build with honggfuzz unchanged.
+///
+/// It takes the same forms as the macro of libfuzzer-sys: a closure of `&[u8]` or of any type
+/// implementing `Arbitrary`, which may return a [`Corpus`], and an `init:` expression run once before
+/// the first input. It defines the `main` function of the target, which has to be `#![no_main]` as
+/// with cargo-fuzz, and calls [`fuzz!`] in a loop.
+///
+/// Renaming the dependency keeps the `use libfuzzer_sys::...` lines working too:
+/// `libfuzzer-sys = { package = "honggfuzz", version = "0.5" }`.
+///
+/// ```rust,ignore
+/// #![no_main]
+/// use honggfuzz::{fuzz_target, Corpus};
+///
+/// fuzz_target!(init: env_logger::init(), |data: (bool, &str)| -> Corpus {
+///     if !data.0 {
+///         return Corpus::Reject;
+///     }
+///     if data.1 == "hey" {
+///         panic!("BOOM")
+///     }
+///     Corpus::Keep
+/// });
+/// ```
+#[macro_export]
+macro_rules! fuzz_target {
+    (init: $init:expr, $($rest:tt)*) => {
+        $crate::__fuzz_target!($init; $($rest)*);
+    };
+    ($($rest:tt)*) => {
+        $crate::__fuzz_target!((); $($rest)*);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fuzz_target {
+    // the closure forwarded as tokens, for `fuzz!` to tell `&[u8]` from the other types
+    ($init:expr; $($closure:tt)*) => {
+        #[no_mangle]
+        pub extern "C" fn main(_argc: ::core::ffi::c_int, _argv: *const *const ::core::ffi::c_char) -> ::core::ffi::c_int {
+            $init;
+            loop {
+                $crate::fuzz!($($closure)*);
+            }
+        }
+    };
+}
@@ -0,0 +1,53 @@
+//! The logs of the target while it is fuzzed, installed with [`fuzz_logger!`](crate::fuzz_logger).
+//!
+//! A target logging at every input spends most of its time formatting lines nobody reads and fills
+//! the disk over a campaign. The logger of `fuzz_logger!` drops the logs in the fuzzing builds and
+//! prints all of them, down to the trace level, in the builds of run-debug. [`set_enabled`] changes
+//! that while the target runs, like to look into the logs of one part of the harness only.
+//!
+//! ```rust
+//! # fn main() {
+//! // print the logs of the setup, even in the fuzzing builds
+//! honggfuzz::logs::set_enabled(true);
+//! // ...
+//! honggfuzz::logs::set_enabled(false);
+//! # }
+//! ```
+//!
+//! Out of the fuzzing builds `fuzz_logger!` installs nothing, the target keeping its own logger.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(fuzzing_debug));
+// called when the logs are enabled or disabled, for the loggers to update their levels
+static TOGGLES: Mutex<Vec<fn(bool)>> = Mutex::new(Vec::new());
+
+/// Whether the logs are printed, by default only in the builds of run-debug.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Prints the logs, or drops them.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    for toggle in TOGGLES.lock().unwrap().iter() {
+        toggle(enabled);
+    }
+}
+
+// whether the logger of `fuzz_logger!` is to be installed, `toggle` being called from now on
+#[doc(hidden)]
+pub fn install(toggle: fn(bool)) -> bool {
+    if !cfg!(fuzzing) {
+        return false;
+    }
+    TOGGLES.lock().unwrap().push(toggle);
+    toggle(enabled());
+    true
+}
+
+#[doc(hidden)]
+pub fn write(level: &str, target: &str, message: std::fmt::Arguments) {
+    eprintln!("[{} {}] {}", level, target, message);
+}
@@ -0,0 +1,83 @@
+//! Fuzzing of TCP servers, sent the inputs over sockets like by the NetDriver of honggfuzz.
+//!
+//! A server reads its requests from connections, not from a function taking an input: with
+//! [`fuzz_net!`](crate::fuzz_net) it runs in a thread of the target, listening on the address it is
+//! given, and every input is sent on a connection of its own, closed for writing once sent, whose
+//! response is read until the server closes it. A server keeping it open makes every input hang.
+//!
+//! ```rust,no_run
+//! # mod server { pub fn serve(_listener: std::net::TcpListener) {} }
+//! fn main() {
+//!     honggfuzz::fuzz_net!(|addr| {
+//!         let listener = std::net::TcpListener::bind(addr).unwrap();
+//!         server::serve(listener);
+//!     });
+//! }
+//! ```
+//!
+//! The server gets a free port of the loopback, another one in each process of honggfuzz, unless
+//! `HFND_TCP_PORT` (set by `cargo hfuzz run --net-port PORT`) gives the fixed one of a server which
+//! can't be told. A panic of the server, in any of its threads, is a crash of the input it was sent,
+//! and so is a server which stops accepting connections or returns.
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// how long the server has to listen once started, and to close the connection of an input once it
+// is sent, like the 10 seconds of the NetDriver
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// the port of HFND_TCP_PORT, or a free one: free again once the listener is dropped, for the server
+// to bind it
+fn address() -> SocketAddr {
+    let port = match std::env::var("HFND_TCP_PORT") {
+        Ok(port) => port.trim().parse().unwrap_or_else(|_| panic!("invalid HFND_TCP_PORT \"{}\", expected a port", port)),
+        Err(_) => TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .map(|addr| addr.port())
+            .unwrap_or_else(|error| panic!("no free port on the loopback ({})", error)),
+    };
+    SocketAddr::from((Ipv4Addr::LOCALHOST, port))
+}
+
+// once the server accepts a connection, which it reads nothing from
+fn wait_listening(addr: SocketAddr, server: &JoinHandle<()>) {
+    let started = Instant::now();
+    while TcpStream::connect_timeout(&addr, Duration::from_millis(100)).is_err() {
+        if server.is_finished() {
+            panic!("the server returned before listening on {}", addr);
+        }
+        if started.elapsed() > START_TIMEOUT {
+            panic!("the server doesn't listen on {} after {}s", addr, START_TIMEOUT.as_secs());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn send(addr: SocketAddr, data: &[u8], server: &JoinHandle<()>) {
+    let mut stream = TcpStream::connect_timeout(&addr, START_TIMEOUT)
+        .unwrap_or_else(|error| panic!("the server doesn't accept connections on {} anymore ({})", addr, error));
+    // a server closing the connection before reading all of a bad request crashes nothing
+    if stream.write_all(data).is_ok() && stream.shutdown(Shutdown::Write).is_ok() {
+        let _ = stream.set_read_timeout(Some(RESPONSE_TIMEOUT));
+        let mut response = vec![0; 64 * 1024];
+        while matches!(stream.read(&mut response), Ok(n) if n > 0) {}
+    }
+    if server.is_finished() {
+        panic!("the server returned after this input");
+    }
+}
+
+/// Runs `server` listening on the address it is given and fuzzes it, sending it every input over a
+/// connection: what [`fuzz_net!`](crate::fuzz_net) calls.
+pub fn fuzz_net<S>(server: S) -> ! where S: FnOnce(SocketAddr) + Send + 'static {
+    let addr = address();
+    let server = thread::spawn(move || server(addr));
+    wait_listening(addr, &server);
+    loop {
+        crate::fuzz(|data| send(addr, data, &server));
+    }
+}
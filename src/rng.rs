@@ -0,0 +1,100 @@
+// A small deterministic PRNG for the harnesses, seeded from the input they run over.
+//
+// Randomness from the clock or the OS would make the crashes not reproduce: seeded from the bytes of
+// the input, the same input always makes the same random choices, when fuzzing as when replaying.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// FNV-1a, stable across the builds and the versions of Rust unlike the hashers of std
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// the seed used outside of an input, set by `seed_rng`
+static SEED: AtomicU64 = AtomicU64::new(FNV_OFFSET);
+// how many generators were given for the input, for each of them to get different numbers
+static STREAM: AtomicU64 = AtomicU64::new(0);
+
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(FNV_OFFSET, |hash, &b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(fuzzing)]
+pub(crate) fn start_input() {
+    STREAM.store(0, Ordering::Relaxed);
+}
+
+/// A generator seeded from the input running, the same input always giving the same numbers.
+///
+/// Each call gives a new generator, the second one of an input getting other numbers than the first
+/// one, but the same ones every time the input runs. Outside of an input, like in a test, it is seeded
+/// from the bytes given to [`seed_rng`], or from no input at all.
+///
+/// ```rust
+/// # fn main() {
+/// let mut items = vec![1, 2, 3, 4];
+/// let mut rng = honggfuzz::rng();
+/// rng.shuffle(&mut items);
+/// if rng.chance(0.1) {
+///     items.pop();
+/// }
+/// # }
+/// ```
+pub fn rng() -> Rng {
+    let seed = crate::with_running_input(|input| input.map(hash)).unwrap_or_else(|| SEED.load(Ordering::Relaxed));
+    let stream = STREAM.fetch_add(1, Ordering::Relaxed);
+    Rng { state: seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15) }
+}
+
+/// Seeds [`rng`] as the input `data` does when fuzzing, for a test replaying a crash to make the
+/// same random choices (`cargo hfuzz gen-test` adds it to the tests of the harnesses calling `rng()`).
+pub fn seed_rng(data: &[u8]) {
+    SEED.store(hash(data), Ordering::Relaxed);
+    STREAM.store(0, Ordering::Relaxed);
+}
+
+/// The generator of [`rng`], a SplitMix64: fast and small, not cryptographic.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A number in `0..bound`, 0 when `bound` is 0
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// `true` with the probability `p`, between 0 and 1
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// One of the elements of `slice`, `None` when it is empty
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        slice.get(self.below(slice.len() as u64) as usize)
+    }
+
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+
+    pub fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Drivers of the socketfuzzer mode of honggfuzz, sending the inputs to a target themselves.
+//!
+//! A target which can't be a closure fed with inputs, like a server which has to be talked to in
+//! sessions, is run by honggfuzz as it is, without `fuzz!`, and fuzzed by a driver: whenever honggfuzz
+//! asks for an input, the driver sends its own to the target and tells when it is done, honggfuzz
+//! telling it in return which ones reached new coverage and which ones crashed the target.
+//!
+//! ```rust,no_run
+//! # mod client { pub fn send_session(_messages: &[Vec<u8>]) -> bool { true } pub fn mutate(_messages: &mut Vec<Vec<u8>>) {} }
+//! use honggfuzz::socket_fuzzer::{Driver, Event};
+//!
+//! fn main() -> std::io::Result<()> {
+//!     let mut driver = Driver::connect()?;
+//!     let mut messages = vec![b"HELLO".to_vec()];
+//!     while let Some(event) = driver.next_event()? {
+//!         match event {
+//!             Event::Fuzz => {
+//!                 client::mutate(&mut messages);
+//!                 match client::send_session(&messages) {
+//!                     true => driver.sent()?,
+//!                     false => driver.target_down()?,
+//!                 }
+//!             }
+//!             Event::NewCoverage => { /* keep the messages as a seed */ }
+//!             Event::Crash => { /* save the messages, honggfuzz's crash file is empty */ }
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! `cargo hfuzz run --socket-fuzzer=DRIVER TARGET` runs the driver once honggfuzz listens, with the
+//! path of its socket in `HFUZZ_SOCKET_FUZZER`, and stops honggfuzz when the driver exits. The target
+//! calls [`init_target`] first thing in its `main`, for its panics to be crashes.
+
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+/// Makes the panics of the target crashes honggfuzz sees, aborting like in `fuzz!`: what its `main`
+/// calls first, which also links the instrumentation of the crate when the target uses nothing else.
+pub fn init_target() {
+    #[cfg(all(fuzzing, not(fuzzing_debug), not(fuzzing_bench)))]
+    lazy_static::initialize(&crate::PANIC_HOOK);
+}
+
+/// What honggfuzz tells the driver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Send the next input to the target, then call [`Driver::sent`] or [`Driver::target_down`].
+    Fuzz,
+    /// The last input reached new coverage.
+    NewCoverage,
+    /// The last input crashed the target, which honggfuzz restarts.
+    Crash,
+}
+
+/// The connection of a driver to honggfuzz.
+pub struct Driver {
+    stream: UnixStream,
+}
+
+impl Driver {
+    /// Connects to the socket of `HFUZZ_SOCKET_FUZZER`, set by `cargo hfuzz run --socket-fuzzer`.
+    pub fn connect() -> io::Result<Driver> {
+        match std::env::var_os("HFUZZ_SOCKET_FUZZER") {
+            Some(socket) => Driver::connect_to(socket),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "HFUZZ_SOCKET_FUZZER isn't set, is the driver run by \"cargo hfuzz run --socket-fuzzer\"?")),
+        }
+    }
+
+    /// Connects to the socket honggfuzz prints it waits on, `/tmp/honggfuzz_socket.<PID>`.
+    pub fn connect_to<P: AsRef<Path>>(socket: P) -> io::Result<Driver> {
+        Ok(Driver { stream: UnixStream::connect(socket)? })
+    }
+
+    /// The next event, `None` once honggfuzz exited.
+    pub fn next_event(&mut self) -> io::Result<Option<Event>> {
+        let mut message = [0; 4];
+        match self.stream.read_exact(&mut message) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+        match &message {
+            b"Fuzz" => Ok(Some(Event::Fuzz)),
+            b"New!" => Ok(Some(Event::NewCoverage)),
+            b"Cras" => Ok(Some(Event::Crash)),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown message {:?} from honggfuzz", String::from_utf8_lossy(&message)))),
+        }
+    }
+
+    /// Tells honggfuzz the input was sent, for it to collect the coverage of the target.
+    pub fn sent(&mut self) -> io::Result<()> {
+        self.stream.write_all(b"okay")
+    }
+
+    /// Tells honggfuzz the target doesn't answer anymore, for it to be restarted.
+    pub fn target_down(&mut self) -> io::Result<()> {
+        self.stream.write_all(b"bad!")
+    }
+
+    /// Stops honggfuzz and the target, answering an [`Event::Fuzz`] instead of sending an input.
+    pub fn halt(mut self) -> io::Result<()> {
+        self.stream.write_all(b"halt")
+    }
+}